@@ -0,0 +1,52 @@
+use crate::events::ATTENTION_EVENT;
+use crate::settings::{AttentionSettings, HookEvent, Severity};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, UserAttentionType};
+
+/// Cues the main window plays for an event (payload of `app://attention`)
+#[derive(Debug, Clone, Serialize)]
+pub struct Attention {
+    pub event: HookEvent,
+    pub severity: Severity,
+    pub message: String,
+    pub sound: bool,
+    pub flash: bool,
+    pub notify: bool,
+}
+
+/// Ask for the operator's attention as the rule for `event` says. Sound, screen flash
+/// and notification are the window's job; the taskbar/dock entry is flashed from here
+/// so it happens even while the window is minimized.
+pub fn raise(app: &AppHandle, settings: &AttentionSettings, event: HookEvent, message: &str) {
+    if !settings.enabled {
+        return;
+    }
+    let Some(rule) = settings.rules.iter().find(|r| r.event == event) else {
+        return;
+    };
+    if !(rule.sound || rule.flash || rule.notify) {
+        return;
+    }
+
+    let attention = Attention {
+        event,
+        severity: rule.severity,
+        message: message.to_string(),
+        sound: rule.sound,
+        flash: rule.flash,
+        notify: rule.notify,
+    };
+    let _ = app.emit(ATTENTION_EVENT, &attention);
+
+    if rule.flash {
+        if let Some(window) = app.get_webview_window("main") {
+            let kind = match rule.severity {
+                Severity::Critical => UserAttentionType::Critical,
+                Severity::Info | Severity::Warning => UserAttentionType::Informational,
+            };
+            if let Err(e) = window.request_user_attention(Some(kind)) {
+                eprintln!("[ATTENTION] Failed to flash the window: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::serial::{check_nak, SerialConnection, SerialError};
+use crate::settings::{AppSettings, ClaimSettings};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const CLAIM_TIMEOUT_MS: u64 = 1000;
+
+/// Who owns the device on the other end, as recorded in its NVS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ClaimStatus {
+    /// The device had no owner and is now claimed by this app
+    Claimed,
+    /// Already claimed by this app
+    Ours,
+    /// Claimed by another installation; its configuration may be someone else's work
+    Foreign { owner_id: String, owner_name: String },
+    /// Firmware has no owner tag support, or claiming is disabled
+    Unsupported,
+}
+
+/// Give this installation an owner ID the first time it runs; true if one was generated
+pub fn ensure_owner_id(settings: &mut AppSettings) -> bool {
+    if !settings.claim.owner_id.is_empty() {
+        return false;
+    }
+    settings.claim.owner_id = uuid::Uuid::new_v4().simple().to_string();
+    true
+}
+
+/// Name written next to the owner ID: the configured one, else the OS user
+fn owner_name(claim: &ClaimSettings) -> String {
+    let name = if claim.owner_name.trim().is_empty() {
+        std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".into())
+    } else {
+        claim.owner_name.clone()
+    };
+    // Owner tags travel in space-separated commands
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Parse "OWNER:<id> <name>" or "OWNER:NONE"
+fn parse_owner(line: &str) -> Option<Option<(String, String)>> {
+    let rest = line.strip_prefix("OWNER:")?.trim();
+    if rest == "NONE" || rest.is_empty() {
+        return Some(None);
+    }
+    let (id, name) = rest.split_once(' ').unwrap_or((rest, ""));
+    Some(Some((id.to_string(), name.trim().to_string())))
+}
+
+/// Read the device's owner tag (`<OWNER?>`) and claim it (`<OWNER id name>`) when unowned
+pub fn check_or_claim(connection: &mut SerialConnection, claim: &ClaimSettings) -> Result<ClaimStatus, SerialError> {
+    if !claim.enabled || claim.owner_id.is_empty() {
+        return Ok(ClaimStatus::Unsupported);
+    }
+
+    let timeout = Duration::from_millis(CLAIM_TIMEOUT_MS);
+    let lines = match connection.transact("<OWNER?>", timeout, |l| l.starts_with("OWNER:") || l.starts_with("NAK:")) {
+        Ok(lines) => lines,
+        // Older firmware ignores the query entirely
+        Err(SerialError::Timeout) => return Ok(ClaimStatus::Unsupported),
+        Err(e) => return Err(e),
+    };
+    let Some(owner) = lines.last().and_then(|l| parse_owner(l)) else {
+        return Ok(ClaimStatus::Unsupported);
+    };
+
+    match owner {
+        Some((id, _)) if id == claim.owner_id => Ok(ClaimStatus::Ours),
+        Some((owner_id, owner_name)) => Ok(ClaimStatus::Foreign { owner_id, owner_name }),
+        None => {
+            let request = format!("<OWNER {} {}>", claim.owner_id, owner_name(claim));
+            let lines = connection.transact(&request, timeout, |l| l == "ACK" || l.starts_with("NAK:"))?;
+            check_nak(&lines)?;
+            Ok(ClaimStatus::Claimed)
+        }
+    }
+}
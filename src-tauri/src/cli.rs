@@ -0,0 +1,433 @@
+use crate::claim;
+use crate::health::{self, HealthReport, HealthState};
+use crate::profiles::ProtocolProfile;
+use crate::serial::{SerialConnection, SerialError, SerialParams, UploadResult};
+use crate::settings::AppSettings;
+use crate::setup::{self, HandshakeResult, SetupRecord};
+use crate::signals;
+use crate::storage;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Exit codes, stable so provisioning scripts can branch on them
+const EXIT_OK: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_NAK: i32 = 3;
+const EXIT_TIMEOUT: i32 = 4;
+const EXIT_PORT_NOT_FOUND: i32 = 5;
+const EXIT_INVALID_CONFIG: i32 = 6;
+// Pause before retrying an upload, to let the device finish whatever it was doing
+const RETRY_DELAY_MS: u64 = 1000;
+
+const USAGE: &str = "\
+Usage: esp32-signal-injector <command> [options]
+
+Commands:
+  list-ports                     Serial ports that could be the ESP32
+  status --port <port>           Connect and print the device status
+  upload --port <port> --file <config.json>
+                                 Upload a device config and print the result
+  doctor [--data-dir <dir>]      Check ports, storage, settings and disk space
+  list-signals [--data-dir <dir>]
+                                 Signals saved in the library
+  init [--port <port>] [--data-dir <dir>] [--presets]
+                                 First-run setup: find the board, write default
+                                 settings and optionally install preset signals
+
+Options:
+  --json                         Print results as JSON, in the same shape the app's IPC returns
+  --baud <rate>                  Line speed of the firmware build (default 115200)
+  --no-reset                     Keep DTR/RTS low on connect so a running board isn't reset
+  --timeout <ms>                 How long an upload waits for the device's ACK
+  --retries <n>                  Retry an upload that timed out or wasn't acknowledged
+  --data-dir <dir>               App data folder for init, doctor and list-signals
+                                 (default: the portable folder when running portable)
+  --presets                      Install the bundled trigger-wheel signals during init
+
+Exit codes:
+  0 success, 1 other failure, 2 bad usage, 3 device rejected (NAK),
+  4 timeout, 5 port not found, 6 invalid config file";
+
+/// Why a subcommand failed, with the exit code reporting it
+struct CliError {
+    code: i32,
+    message: String,
+}
+
+impl CliError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        CliError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<&SerialError> for CliError {
+    fn from(err: &SerialError) -> Self {
+        let code = match err {
+            SerialError::Timeout => EXIT_TIMEOUT,
+            SerialError::DeviceError(_) => EXIT_NAK,
+            _ => EXIT_FAILURE,
+        };
+        CliError::new(code, err.to_string())
+    }
+}
+
+impl From<SerialError> for CliError {
+    fn from(err: SerialError) -> Self {
+        CliError::from(&err)
+    }
+}
+
+/// Options shared by all subcommands
+struct Args {
+    command: String,
+    port: Option<String>,
+    file: Option<String>,
+    json: bool,
+    timeout_ms: Option<u64>,
+    retries: u32,
+    baud_rate: Option<u32>,
+    no_reset: bool,
+    data_dir: Option<String>,
+    presets: bool,
+}
+
+fn parse(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args {
+        command: args[0].clone(),
+        port: None,
+        file: None,
+        json: false,
+        timeout_ms: None,
+        retries: 0,
+        baud_rate: None,
+        no_reset: false,
+        data_dir: None,
+        presets: false,
+    };
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--json" => parsed.json = true,
+            "--port" => parsed.port = Some(rest.next().ok_or("--port needs a value")?.clone()),
+            "--file" => parsed.file = Some(rest.next().ok_or("--file needs a value")?.clone()),
+            "--timeout" => parsed.timeout_ms = Some(number(rest.next(), "--timeout")?),
+            "--retries" => parsed.retries = number(rest.next(), "--retries")?,
+            "--no-reset" => parsed.no_reset = true,
+            "--baud" => parsed.baud_rate = Some(number(rest.next(), "--baud")?),
+            "--data-dir" => parsed.data_dir = Some(rest.next().ok_or("--data-dir needs a value")?.clone()),
+            "--presets" => parsed.presets = true,
+            other => return Err(format!("Unknown option '{}'", other)),
+        }
+    }
+    Ok(parsed)
+}
+
+fn number<T: std::str::FromStr>(value: Option<&String>, option: &str) -> Result<T, String> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("{} needs a number", option))
+}
+
+/// Whether the arguments ask for a CLI subcommand rather than the app window.
+/// Deep links also arrive as arguments, so only known subcommands count.
+pub fn is_cli_invocation(args: &[String]) -> bool {
+    matches!(
+        args.first().map(String::as_str),
+        Some("list-ports" | "status" | "upload" | "init" | "doctor" | "list-signals" | "help" | "--help" | "-h")
+    )
+}
+
+/// Run a CLI subcommand and return the process exit code
+pub fn run(args: &[String]) -> i32 {
+    if matches!(args[0].as_str(), "help" | "--help" | "-h") {
+        println!("{}", USAGE);
+        return EXIT_OK;
+    }
+    let args = match parse(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    let result = match args.command.as_str() {
+        "list-ports" => list_ports(&args),
+        "status" => status(&args),
+        "upload" => upload(&args),
+        "init" => init(&args),
+        "doctor" => doctor(&args),
+        "list-signals" => list_signals(&args),
+        _ => unreachable!("checked by is_cli_invocation"),
+    };
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            if args.json {
+                println!("{}", serde_json::json!({ "error": e.message, "exitCode": e.code }));
+            } else {
+                eprintln!("Error: {}", e.message);
+            }
+            e.code
+        }
+    }
+}
+
+fn print<T: Serialize>(args: &Args, value: &T, text: impl FnOnce(&T) -> String) -> Result<(), CliError> {
+    if args.json {
+        let json = serde_json::to_string_pretty(value).map_err(|e| CliError::new(EXIT_FAILURE, e.to_string()))?;
+        println!("{}", json);
+    } else {
+        println!("{}", text(value));
+    }
+    Ok(())
+}
+
+/// Open the port, let the board finish booting and make sure our firmware answers
+fn connect(args: &Args) -> Result<SerialConnection, CliError> {
+    let port = args.port.as_deref().ok_or_else(|| CliError::new(EXIT_USAGE, "--port is required"))?;
+    let listed = SerialConnection::list_ports()?.iter().any(|p| p.name == port);
+    if !listed {
+        return Err(CliError::new(EXIT_PORT_NOT_FOUND, format!("Port {} not found", port)));
+    }
+
+    let mut connection = SerialConnection::new();
+    if let Some(timeout_ms) = args.timeout_ms {
+        connection.set_protocol(ProtocolProfile {
+            upload_timeout_ms: timeout_ms,
+            ..Default::default()
+        });
+    }
+    let params = serial_params(args)?;
+    connection.connect(port, &params)?;
+    if let Err(e) = connection.settle(None).and_then(|_| connection.identify(None)) {
+        let _ = connection.disconnect();
+        return Err(e.into());
+    }
+    Ok(connection)
+}
+
+fn serial_params(args: &Args) -> Result<SerialParams, CliError> {
+    let mut params = SerialParams {
+        suppress_reset: args.no_reset,
+        ..Default::default()
+    };
+    if let Some(baud_rate) = args.baud_rate {
+        params.baud_rate = baud_rate;
+    }
+    params.validate().map_err(|e| CliError::new(EXIT_USAGE, e))?;
+    Ok(params)
+}
+
+/// Each subcommand prints its result and returns the exit code; errors are for when
+/// there is no result to print
+fn list_ports(args: &Args) -> Result<i32, CliError> {
+    let ports = SerialConnection::list_ports()?;
+    print(args, &ports, |ports| {
+        ports
+            .iter()
+            .map(|p| format!("{}\t{}", p.name, p.describe()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    Ok(EXIT_OK)
+}
+
+fn status(args: &Args) -> Result<i32, CliError> {
+    let mut connection = connect(args)?;
+    let status = connection.get_status();
+    let _ = connection.disconnect();
+    let status = status?;
+    print(args, &status, |s| {
+        format!(
+            "RPM: {}\nState: {}\nFault: {}",
+            s.rpm,
+            if s.running { "running" } else { "stopped" },
+            s.fault.as_deref().unwrap_or("none")
+        )
+    })?;
+    Ok(EXIT_OK)
+}
+
+fn upload(args: &Args) -> Result<i32, CliError> {
+    let file = args.file.as_deref().ok_or_else(|| CliError::new(EXIT_USAGE, "--file is required"))?;
+    let config = fs::read_to_string(file)
+        .map_err(|e| CliError::new(EXIT_INVALID_CONFIG, format!("Can't read {}: {}", file, e)))?;
+    if !serde_json::from_str::<serde_json::Value>(&config).is_ok_and(|v| v.is_object()) {
+        return Err(CliError::new(EXIT_INVALID_CONFIG, format!("{} is not a JSON config", file)));
+    }
+
+    let mut connection = connect(args)?;
+    let mut attempt = 0;
+    let result = loop {
+        attempt += 1;
+        let result = connection.send_config(&config, |_| {});
+        let failure = result.as_ref().map_or_else(|e| Some(CliError::from(e)), upload_failure);
+        match failure {
+            Some(e) if attempt <= args.retries && e.code != EXIT_NAK => {
+                eprintln!("Attempt {} failed ({}), retrying", attempt, e.message);
+                std::thread::sleep(Duration::from_millis(RETRY_DELAY_MS));
+            }
+            _ => break result,
+        }
+    };
+    let _ = connection.disconnect();
+    let result = result?;
+
+    print(args, &result, |r| match &r.error_message {
+        None => format!("Uploaded {} bytes in {} chunks", r.bytes_sent, r.chunks_sent),
+        Some(e) => format!("Upload failed: {}", e),
+    })?;
+    Ok(upload_failure(&result).map_or(EXIT_OK, |e| e.code))
+}
+
+/// The data folder given with --data-dir, or the portable one
+fn data_dir(args: &Args) -> Result<PathBuf, CliError> {
+    match &args.data_dir {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None if storage::is_portable_mode() => storage::portable_dir().map_err(|e| CliError::new(EXIT_FAILURE, e)),
+        // The default location is resolved through the app, which the CLI doesn't start
+        None => Err(CliError::new(EXIT_USAGE, "--data-dir is required unless running portable")),
+    }
+}
+
+/// The subset of the app's health check that works without the app running; fails
+/// when any check does
+fn doctor(args: &Args) -> Result<i32, CliError> {
+    let dir = data_dir(args)?;
+    let report = HealthReport::new(vec![
+        health::port_enumeration(),
+        health::library_storage(&dir),
+        health::settings_file(&dir),
+        health::disk_space(&dir),
+    ]);
+    print(args, &report, |r| {
+        r.checks
+            .iter()
+            .map(|c| format!("{:<8}{}: {}", format!("{:?}", c.state).to_uppercase(), c.subsystem, c.detail))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    Ok(if report.overall == HealthState::Failed { EXIT_FAILURE } else { EXIT_OK })
+}
+
+/// The signal library of the data folder, as the app's signal list shows it
+fn list_signals(args: &Args) -> Result<i32, CliError> {
+    let dir = data_dir(args)?;
+    let signals = signals::list_signals_at(&dir).map_err(|e| CliError::new(EXIT_FAILURE, e.to_string()))?;
+    print(args, &signals, |signals| {
+        signals
+            .iter()
+            .map(|s| {
+                let channels = [("CKP", s.has_ckp), ("CMP1", s.has_cmp1), ("CMP2", s.has_cmp2)]
+                    .iter()
+                    .filter(|(_, present)| *present)
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join("+");
+                format!("{}\t{}\t{}", s.filename, s.name, channels)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    Ok(EXIT_OK)
+}
+
+/// What `init` did
+#[derive(Serialize)]
+struct InitReport {
+    data_dir: String,
+    /// The port that answered, if any did
+    handshake: Option<HandshakeResult>,
+    settings_created: bool,
+    presets_installed: Vec<String>,
+    setup: SetupRecord,
+}
+
+/// The CLI side of the first-run wizard. Without --port every likely port is tried
+/// until one answers; not finding a board doesn't stop the rest of the setup.
+fn init(args: &Args) -> Result<i32, CliError> {
+    let failed = |e: String| CliError::new(EXIT_FAILURE, e);
+    let dir = data_dir(args)?;
+
+    let params = serial_params(args)?;
+    let handshake = match &args.port {
+        Some(port) => {
+            if !SerialConnection::list_ports()?.iter().any(|p| &p.name == port) {
+                return Err(CliError::new(EXIT_PORT_NOT_FOUND, format!("Port {} not found", port)));
+            }
+            let result = setup::test_handshake(port, &params);
+            if let Some(e) = &result.error {
+                return Err(failed(format!("No answer from {}: {}", port, e)));
+            }
+            Some(result)
+        }
+        None => setup::candidate_ports()?
+            .iter()
+            .map(|p| setup::test_handshake(&p.name, &params))
+            .find(|r| r.ok),
+    };
+
+    let mut settings = AppSettings::default();
+    claim::ensure_owner_id(&mut settings);
+    let settings_created = setup::create_settings(&dir, &settings).map_err(failed)?;
+    let presets_installed = if args.presets {
+        setup::install_presets(&dir).map_err(failed)?
+    } else {
+        Vec::new()
+    };
+    let record = setup::complete(&dir, handshake.as_ref().map(|h| h.port.clone())).map_err(failed)?;
+
+    let report = InitReport {
+        data_dir: dir.to_string_lossy().to_string(),
+        handshake,
+        settings_created,
+        presets_installed,
+        setup: record,
+    };
+    print(args, &report, |r| {
+        let board = match &r.handshake {
+            Some(h) => format!(
+                "{} (firmware {})",
+                h.port,
+                h.identity
+                    .as_ref()
+                    .and_then(|i| i.firmware_version.as_deref())
+                    .unwrap_or("unknown")
+            ),
+            None => "none answered".to_string(),
+        };
+        let presets = if r.presets_installed.is_empty() {
+            "none".to_string()
+        } else {
+            r.presets_installed.join(", ")
+        };
+        format!(
+            "Data folder: {}\nBoard: {}\nSettings: {}\nPresets installed: {}",
+            r.data_dir,
+            board,
+            if r.settings_created { "created" } else { "already present" },
+            presets
+        )
+    })?;
+    Ok(EXIT_OK)
+}
+
+/// Classify an unsuccessful upload result for the exit code
+fn upload_failure(result: &UploadResult) -> Option<CliError> {
+    let message = result.error_message.clone()?;
+    let code = if result.raw_response.trim().is_empty() {
+        EXIT_TIMEOUT
+    } else if result.raw_response.lines().any(|l| l.trim().starts_with("NAK:")) {
+        EXIT_NAK
+    } else {
+        EXIT_FAILURE
+    };
+    Some(CliError::new(code, message))
+}
@@ -0,0 +1,274 @@
+// Longest run of bytes kept while waiting for a packet delimiter
+const MAX_PENDING: usize = 64 * 1024;
+// Kind byte plus the u32 body length
+const HEADER_LEN: usize = 5;
+
+/// COBS-encode `data`: the output holds no zero bytes, so a zero can end a packet.
+/// Adds one byte per 254 of input at most.
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 254 + 2);
+    let mut code_index = 0;
+    let mut code: u8 = 1;
+    out.push(0);
+    for (i, &byte) in data.iter().enumerate() {
+        if byte != 0 {
+            out.push(byte);
+            code += 1;
+        }
+        // A full block only needs closing when more input follows it
+        if byte == 0 || (code == 0xFF && i + 1 < data.len()) {
+            out[code_index] = code;
+            code_index = out.len();
+            out.push(0);
+            code = 1;
+        }
+    }
+    out[code_index] = code;
+    out
+}
+
+/// Reverse of `encode` for one packet without its delimiter; `None` if it is malformed
+pub fn decode(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let code = data[i] as usize;
+        if code == 0 || i + code > data.len() {
+            return None;
+        }
+        out.extend_from_slice(&data[i + 1..i + code]);
+        i += code;
+        if code < 0xFF && i < data.len() {
+            out.push(0);
+        }
+    }
+    Some(out)
+}
+
+/// What a packet carries, from its first byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketKind {
+    /// Transfer payload sent to the device
+    Data = 0x01,
+    /// Text the device printed during the transfer (logs, progress)
+    Line = 0x02,
+    /// The transfer was accepted
+    Ack = 0x06,
+    /// Flow-control credit: the device's received byte count as u32 LE
+    Credit = 0x11,
+    /// The transfer was rejected; the body is the reason
+    Nak = 0x15,
+}
+
+impl PacketKind {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x01 => Some(PacketKind::Data),
+            0x02 => Some(PacketKind::Line),
+            0x06 => Some(PacketKind::Ack),
+            0x11 => Some(PacketKind::Credit),
+            0x15 => Some(PacketKind::Nak),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Packet {
+    pub kind: PacketKind,
+    pub body: Vec<u8>,
+}
+
+impl Packet {
+    /// Body as text, for lines and NAK reasons
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).trim().to_string()
+    }
+
+    /// Byte count of a credit packet
+    pub fn credit(&self) -> Option<usize> {
+        let bytes: [u8; 4] = self.body.get(..4)?.try_into().ok()?;
+        Some(u32::from_le_bytes(bytes) as usize)
+    }
+}
+
+/// `[kind][body length, u32 LE][body]`, COBS-encoded between zero bytes. The leading
+/// zero ends whatever the receiver has buffered (e.g. a half line of noise) so it can't
+/// corrupt this packet; the length prefix lets it check it got the whole body.
+pub fn encode_packet(kind: PacketKind, body: &[u8]) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(HEADER_LEN + body.len());
+    raw.push(kind as u8);
+    raw.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    raw.extend_from_slice(body);
+    let mut out = vec![0];
+    out.extend(encode(&raw));
+    out.push(0);
+    out
+}
+
+fn decode_packet(frame: &[u8]) -> Option<Packet> {
+    let raw = decode(frame)?;
+    if raw.len() < HEADER_LEN {
+        return None;
+    }
+    let kind = PacketKind::from_byte(raw[0])?;
+    let len = u32::from_le_bytes(raw[1..HEADER_LEN].try_into().ok()?) as usize;
+    if raw.len() - HEADER_LEN != len {
+        return None;
+    }
+    Some(Packet {
+        kind,
+        body: raw[HEADER_LEN..].to_vec(),
+    })
+}
+
+/// Splits received bytes into packets at the zero delimiters
+#[derive(Debug, Default)]
+pub struct PacketReader {
+    pending: Vec<u8>,
+}
+
+impl PacketReader {
+    /// Feed received bytes. Each complete packet comes back as `Ok`; bytes outside any
+    /// packet (log lines the firmware prints between packets, noise) come back as `Err`.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Result<Packet, Vec<u8>>> {
+        let mut packets = Vec::new();
+        for &byte in bytes {
+            if byte != 0 {
+                self.pending.push(byte);
+                if self.pending.len() >= MAX_PENDING {
+                    packets.push(Err(std::mem::take(&mut self.pending)));
+                }
+                continue;
+            }
+            let run = std::mem::take(&mut self.pending);
+            if !run.is_empty() {
+                split_run(run, &mut packets);
+            }
+        }
+        packets
+    }
+}
+
+/// Take the packet out of a run ended by a zero byte. Text printed before the packet
+/// ends in a newline, so when the whole run doesn't decode, the packet is looked for
+/// after each newline and what precedes it is passed on as text.
+fn split_run(run: Vec<u8>, packets: &mut Vec<Result<Packet, Vec<u8>>>) {
+    if let Some(packet) = decode_packet(&run) {
+        packets.push(Ok(packet));
+        return;
+    }
+    for (i, _) in run.iter().enumerate().filter(|(_, &b)| b == b'\n') {
+        if let Some(packet) = decode_packet(&run[i + 1..]) {
+            packets.push(Err(run[..=i].to_vec()));
+            packets.push(Ok(packet));
+            return;
+        }
+    }
+    packets.push(Err(run));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(data: &[u8]) -> Vec<u8> {
+        let encoded = encode(data);
+        assert!(!encoded.contains(&0), "encoded data holds a zero: {:?}", encoded);
+        let decoded = decode(&encoded).expect("decodes");
+        assert_eq!(decoded, data);
+        encoded
+    }
+
+    fn packets(reader: &mut PacketReader, bytes: &[u8]) -> Vec<Packet> {
+        reader.feed(bytes).into_iter().filter_map(Result::ok).collect()
+    }
+
+    #[test]
+    fn encodes_empty_input() {
+        assert_eq!(roundtrip(&[]), vec![0x01]);
+    }
+
+    #[test]
+    fn encodes_zero_bytes() {
+        assert_eq!(roundtrip(&[0x00]), vec![0x01, 0x01]);
+        assert_eq!(roundtrip(&[0x00, 0x00]), vec![0x01, 0x01, 0x01]);
+        assert_eq!(roundtrip(&[0x11, 0x22, 0x00, 0x33]), vec![0x03, 0x11, 0x22, 0x02, 0x33]);
+        assert_eq!(roundtrip(&[0x11, 0x00, 0x00, 0x00]), vec![0x02, 0x11, 0x01, 0x01, 0x01]);
+    }
+
+    #[test]
+    fn encodes_long_runs() {
+        let block: Vec<u8> = (1..=254).collect();
+        let mut expected = vec![0xFF];
+        expected.extend(&block);
+        assert_eq!(roundtrip(&block), expected);
+
+        let longer: Vec<u8> = (1..=255).collect();
+        let mut expected = vec![0xFF];
+        expected.extend(&block);
+        expected.extend([0x02, 0xFF]);
+        assert_eq!(roundtrip(&longer), expected);
+
+        let mut then_zero = block.clone();
+        then_zero.push(0x00);
+        roundtrip(&then_zero);
+        roundtrip(&[0xAB; 1000]);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(decode(&[0x03, 0x11]).is_none());
+        assert!(decode(&[0x02, 0x11, 0x00, 0x22]).is_none());
+    }
+
+    #[test]
+    fn packets_are_delimited_by_zero_bytes() {
+        let packet = encode_packet(PacketKind::Data, b"<END>ACK");
+        assert_eq!(packet.first(), Some(&0));
+        assert_eq!(packet.last(), Some(&0));
+        assert!(!packet[1..packet.len() - 1].contains(&0));
+    }
+
+    #[test]
+    fn reads_packets_with_any_body() {
+        let mut reader = PacketReader::default();
+        let bodies: [&[u8]; 4] = [b"", b"\0\0\0", b"<END>\nACK\n", &[0x0A; 600]];
+        for body in bodies {
+            let read = packets(&mut reader, &encode_packet(PacketKind::Line, body));
+            assert_eq!(read.len(), 1);
+            assert_eq!(read[0].kind, PacketKind::Line);
+            assert_eq!(read[0].body, body);
+        }
+    }
+
+    #[test]
+    fn reads_a_packet_split_across_reads() {
+        let mut reader = PacketReader::default();
+        let packet = encode_packet(PacketKind::Credit, &512u32.to_le_bytes());
+        let (first, second) = packet.split_at(4);
+        assert!(reader.feed(first).is_empty());
+        let read = packets(&mut reader, second);
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].credit(), Some(512));
+    }
+
+    #[test]
+    fn separates_text_from_packets() {
+        let mut reader = PacketReader::default();
+        let mut bytes = b"I (120) cfg: parsing\nACK from the log\n".to_vec();
+        bytes.extend(&encode(&[PacketKind::Ack as u8, 0, 0, 0, 0]));
+        bytes.push(0);
+        bytes.extend(b"boot: ready\n");
+        bytes.extend(encode_packet(PacketKind::Nak, b"CRC mismatch"));
+
+        let read = reader.feed(&bytes);
+        assert_eq!(read.len(), 4);
+        assert_eq!(read[0].as_ref().err().map(Vec::as_slice), Some(&b"I (120) cfg: parsing\nACK from the log\n"[..]));
+        assert_eq!(read[1].as_ref().map(|p| p.kind).ok(), Some(PacketKind::Ack));
+        assert_eq!(read[2].as_ref().err().map(Vec::as_slice), Some(&b"boot: ready\n"[..]));
+        let nak = read[3].as_ref().expect("NAK packet");
+        assert_eq!(nak.kind, PacketKind::Nak);
+        assert_eq!(nak.text(), "CRC mismatch");
+    }
+}
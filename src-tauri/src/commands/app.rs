@@ -0,0 +1,48 @@
+use crate::health::{self, HealthReport};
+use crate::retention::{self, CleanupReport};
+use crate::scheduler;
+use crate::settings::SettingsState;
+use crate::snapshot::{self, AppSnapshot};
+use crate::storage;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+const MONITOR_WINDOW: &str = "monitor";
+
+/// Connection, device, settings, library, running jobs and recent events in one payload,
+/// for the UI to restore itself after a reload
+#[tauri::command]
+pub async fn get_app_snapshot(app: AppHandle) -> Result<AppSnapshot, String> {
+    Ok(snapshot::collect(&app).await)
+}
+
+/// State of every subsystem, for the diagnostics page
+#[tauri::command]
+pub async fn health_check(app: AppHandle) -> Result<HealthReport, String> {
+    Ok(health::run(&app).await)
+}
+
+/// Apply the retention policies from settings now, whether or not scheduled cleanup is
+/// enabled; with `dry_run` only report what would be deleted
+#[tauri::command]
+pub async fn run_cleanup_now(app: AppHandle, dry_run: bool) -> Result<CleanupReport, String> {
+    let settings = app.state::<SettingsState>().get().retention;
+    let dir = storage::data_dir(&app)?;
+    scheduler::blocking(move || retention::run(&dir, &settings, dry_run))
+        .await
+        .ok_or_else(|| "Cleanup failed".to_string())
+}
+
+/// Open the serial monitor in its own window, or focus it if already open.
+/// Async because creating windows from a sync command deadlocks on Windows.
+#[tauri::command]
+pub async fn open_monitor_window(app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(MONITOR_WINDOW) {
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+    WebviewWindowBuilder::new(&app, MONITOR_WINDOW, WebviewUrl::App("index.html?view=monitor".into()))
+        .title("Serial Monitor")
+        .inner_size(720.0, 480.0)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
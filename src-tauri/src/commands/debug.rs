@@ -0,0 +1,66 @@
+use super::device::send_logged;
+use crate::concurrency::{ConcurrencyGate, Operation};
+use crate::debug_commands::{self, DebugAuditEntry, DebugCommand};
+use crate::device_command::{CommandOutcome, DeviceCommand};
+use crate::serial::SerialState;
+use crate::settings::SettingsState;
+use tauri::{AppHandle, Manager, State};
+
+// Audit entries returned when the caller doesn't ask for a number
+const DEFAULT_AUDIT_LIMIT: usize = 100;
+
+fn require_developer_mode(app: &AppHandle) -> Result<(), String> {
+    if app.state::<SettingsState>().get().developer_mode {
+        Ok(())
+    } else {
+        Err("Debug commands are only available in developer mode".into())
+    }
+}
+
+/// Debug commands of the protocol profile in use; empty outside developer mode
+#[tauri::command]
+pub async fn list_debug_commands(app: AppHandle, state: State<'_, SerialState>) -> Result<Vec<DebugCommand>, String> {
+    if require_developer_mode(&app).is_err() {
+        return Ok(Vec::new());
+    }
+    state.with(|connection| connection.protocol().debug_commands.clone()).await
+}
+
+/// Send a debug command with its parameters from `args`, and record it in the audit
+/// trail. Disruptive ones (e.g. a forced crash) need `confirm`.
+#[tauri::command]
+pub async fn run_debug_command(name: String, args: Option<serde_json::Map<String, serde_json::Value>>, confirm: Option<bool>, app: AppHandle, state: State<'_, SerialState>) -> Result<CommandOutcome, String> {
+    require_developer_mode(&app)?;
+    let lookup = name.clone();
+    let debug = state
+        .with(move |connection| {
+            connection
+                .protocol()
+                .debug_commands
+                .iter()
+                .find(|d| d.command.name == lookup)
+                .cloned()
+        })
+        .await?
+        .ok_or_else(|| format!("The protocol profile has no debug command named '{}'", name))?;
+    if debug.disruptive && !confirm.unwrap_or(false) {
+        return Err(format!("'{}' restarts or stops the device; confirm to send it", name));
+    }
+    let call = debug.command.bind(&args.unwrap_or_default())?;
+    let command = DeviceCommand::Debug(call.clone());
+    command.validate()?;
+    let _command = app.state::<ConcurrencyGate>().try_begin(Operation::Command)?;
+    state
+        .with(move |connection| {
+            let result = send_logged(&app, connection, command);
+            debug_commands::audit(&app, connection, &call, &result);
+            result
+        })
+        .await?
+}
+
+/// The most recent debug commands sent, oldest first
+#[tauri::command]
+pub fn get_debug_audit(limit: Option<usize>, app: AppHandle) -> Result<Vec<DebugAuditEntry>, String> {
+    debug_commands::recent(&app, limit.unwrap_or(DEFAULT_AUDIT_LIMIT))
+}
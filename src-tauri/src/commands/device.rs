@@ -0,0 +1,535 @@
+use super::{start_upload, CommandError};
+use crate::claim::{self, ClaimStatus};
+use crate::concurrency::{ConcurrencyGate, Operation};
+use crate::critical;
+use crate::device_backup::{self, DeviceBackup};
+use crate::device_command::{CommandOutcome, DeviceCommand, OutcomeStatus};
+use crate::events::{ConnectPhase, ConnectProgress, CONNECTION_PROGRESS_EVENT, DEVICE_RESET_EVENT, DEVICE_STATUS_EVENT};
+use crate::hooks::{self, HookState};
+use crate::interlocks;
+use crate::jobs::{JobKind, JobManager};
+use crate::preview::{self, PreflightReport};
+use crate::port_cache::{self, PortCache};
+use crate::port_history;
+use crate::profiles::{self, DeviceLogLevel, ProtocolProfile};
+use crate::serial::{BootReport, ChunkPacing, DeviceStatus, PortInfo, RpmReading, SerialConnection, SerialParams, SerialState};
+use crate::serial_reader;
+use crate::session::{SessionEventKind, SessionLog};
+use crate::settings::{HookEvent, SettingsState};
+use crate::status_history::{ExportFormat, RpmStats, StatusHistory};
+use crate::supervisor::ConnectionSupervisor;
+use crate::timing::TimingStats;
+use crate::tray::{self, BenchState};
+use crate::upload_queue::UploadRequest;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Available serial ports, served from a short-lived cache unless `refresh` is set.
+/// Ports that can't be the ESP32 are hidden per settings unless `include_all` is set;
+/// `esp32_only` keeps just the USB bridges known on ESP32 boards.
+#[tauri::command]
+pub fn list_ports(refresh: Option<bool>, include_all: Option<bool>, esp32_only: Option<bool>, app: AppHandle, port_cache: State<PortCache>, settings: State<SettingsState>) -> Result<Vec<PortInfo>, String> {
+    let mut ports = port_cache.list(refresh.unwrap_or(false)).map_err(|e| e.to_string())?;
+    if settings.get().hide_irrelevant_ports && !include_all.unwrap_or(false) {
+        ports.retain(|p| !port_cache::is_irrelevant(p));
+    }
+    if esp32_only.unwrap_or(false) {
+        ports.retain(port_cache::is_esp32_bridge);
+    }
+    port_history::annotate(&app, &mut ports);
+    Ok(ports)
+}
+
+/// Connect to `port`; `params` overrides the default 115200 8N1 line settings
+#[tauri::command]
+pub async fn connect(port: String, params: Option<SerialParams>, app: AppHandle, state: State<'_, SerialState>) -> Result<ClaimStatus, CommandError> {
+    let params = params.unwrap_or_default();
+    params.validate()?;
+    let note = format!("Connected to {} at {} baud", port, params.baud_rate);
+    open_connection(&app, &state, ConnectAttempt::new(&app, port, params, None), note).await
+}
+
+/// Connect using a saved profile's port, line settings and protocol parameters
+#[tauri::command]
+pub async fn connect_profile(name: String, app: AppHandle, state: State<'_, SerialState>) -> Result<ClaimStatus, CommandError> {
+    let profile = profiles::get_profile(&app, &name).map_err(|e| e.to_string())?;
+    let note = format!("Connected to {} (profile '{}')", profile.port_name, name);
+    profile.serial.validate()?;
+    let attempt = ConnectAttempt::new(&app, profile.port_name, profile.serial, Some((name, profile.protocol)));
+    open_connection(&app, &state, attempt, note).await
+}
+
+/// Connect to the USB device with this serial number, wherever the OS put it this time
+/// (`/dev/ttyUSB0` and COM numbers change between reboots)
+#[tauri::command]
+pub async fn connect_by_serial(serial_number: String, params: Option<SerialParams>, app: AppHandle, state: State<'_, SerialState>, port_cache: State<'_, PortCache>) -> Result<ClaimStatus, CommandError> {
+    let port = port_for_serial(&port_cache, &serial_number)?;
+    let params = params.unwrap_or_default();
+    params.validate()?;
+    let note = format!("Connected to {} (USB serial {}) at {} baud", port, serial_number, params.baud_rate);
+    open_connection(&app, &state, ConnectAttempt::new(&app, port, params, None), note).await
+}
+
+/// Port currently carrying the USB device with this serial number
+fn port_for_serial(port_cache: &PortCache, serial_number: &str) -> Result<String, String> {
+    let ports = port_cache.list(true).map_err(|e| e.to_string())?;
+    let mut matching = ports
+        .into_iter()
+        .filter(|p| p.serial_number.as_deref() == Some(serial_number));
+    let port = matching
+        .next()
+        .ok_or_else(|| format!("No USB device with serial number {} is plugged in", serial_number))?;
+    // Cheap clones share a placeholder serial; picking one would be a guess
+    if let Some(other) = matching.next() {
+        return Err(format!(
+            "Serial number {} is reported by both {} and {}; connect by port instead",
+            serial_number, port.name, other.name
+        ));
+    }
+    Ok(port.name)
+}
+
+/// One connect attempt, held to the deadline from settings
+struct ConnectAttempt {
+    app: AppHandle,
+    port: String,
+    params: SerialParams,
+    profile: Option<(String, ProtocolProfile)>,
+    started: Instant,
+    limit: Option<Duration>,
+}
+
+impl ConnectAttempt {
+    fn new(app: &AppHandle, port: String, params: SerialParams, profile: Option<(String, ProtocolProfile)>) -> Self {
+        let secs = app.state::<SettingsState>().get().connect_timeout_secs;
+        ConnectAttempt {
+            app: app.clone(),
+            port,
+            params,
+            profile,
+            started: Instant::now(),
+            limit: (secs > 0).then(|| Duration::from_secs(secs as u64)),
+        }
+    }
+
+    fn check_deadline(&self) -> Result<(), CommandError> {
+        match self.limit {
+            Some(limit) if self.started.elapsed() >= limit => Err(CommandError::connect_timeout(&self.port, limit)),
+            _ => Ok(()),
+        }
+    }
+
+    fn remaining(&self) -> Option<Duration> {
+        self.limit.map(|limit| limit.saturating_sub(self.started.elapsed()))
+    }
+
+    /// Report the next step to the UI, unless the deadline has already passed
+    fn enter(&self, phase: ConnectPhase) -> Result<(), CommandError> {
+        self.check_deadline()?;
+        let progress = ConnectProgress {
+            port: self.port.clone(),
+            phase,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+        };
+        let _ = self.app.emit(CONNECTION_PROGRESS_EVENT, progress);
+        Ok(())
+    }
+
+    /// Open the port and make sure our firmware answers; otherwise don't stay connected
+    /// to someone else's board. Runs on the serial worker, and when the deadline passes
+    /// before the handshake is done the port is closed again rather than left open
+    /// behind the UI's back. Settling, the handshake and the version request only get the
+    /// time that is left; the owner check runs to the end, so a claim is never cut off
+    /// halfway.
+    fn run(mut self, connection: &mut SerialConnection, note: String) -> Result<ClaimStatus, CommandError> {
+        self.enter(ConnectPhase::Opening)?;
+        connection.connect(&self.port, &self.params).map_err(|e| {
+            // The port may have vanished since it was listed
+            self.app.state::<PortCache>().invalidate();
+            e.to_string()
+        })?;
+        if let Some((name, protocol)) = self.profile.take() {
+            connection.use_profile(&name, protocol);
+        }
+        let opened = self
+            .enter(ConnectPhase::Settling)
+            .and_then(|_| connection.settle(self.remaining()).map_err(CommandError::from))
+            .and_then(|_| self.enter(ConnectPhase::Handshaking))
+            // Silence cut short by the deadline is a timeout, not someone else's board
+            .and_then(|_| {
+                connection
+                    .identify(self.remaining())
+                    .map_err(CommandError::from)
+                    .or_else(|e| self.check_deadline().and(Err(e)))
+            })
+            .and_then(|_| self.check_deadline());
+        if let Err(e) = opened {
+            let _ = connection.disconnect();
+            return Err(e);
+        }
+        // Firmware predating the version request still connects
+        if let Err(e) = connection.read_version(self.remaining()) {
+            eprintln!("[SERIAL] No version reply: {}", e);
+        }
+        let session = self.app.state::<SessionLog>();
+        session.record(SessionEventKind::Connected, note, None);
+        if let Some(warning) = connection.firmware_warning() {
+            eprintln!("[SERIAL] {}", warning);
+            session.record(SessionEventKind::Connected, warning, None);
+        }
+        Ok(check_claim(connection, &self.app.state::<SettingsState>(), &session))
+    }
+}
+
+/// Connect within the deadline from settings, then start the work bound to the connection.
+/// The deadline is enforced by the attempt itself, which also counts the time spent
+/// queued behind other port work: giving up on the reply here would leave the worker
+/// to finish connecting with nobody starting the reader.
+async fn open_connection(app: &AppHandle, state: &SerialState, attempt: ConnectAttempt, note: String) -> Result<ClaimStatus, CommandError> {
+    let port = attempt.port.clone();
+    let status = state.with(move |connection| attempt.run(connection, note)).await??;
+    remember_port(app, &port);
+    tray::show(app, BenchState::Idle);
+    serial_reader::start(app);
+    Ok(status)
+}
+
+/// Claim an unowned device; a failed claim never fails the connection
+fn check_claim(connection: &mut SerialConnection, settings: &SettingsState, session: &SessionLog) -> ClaimStatus {
+    let status = claim::check_or_claim(connection, &settings.get().claim).unwrap_or_else(|e| {
+        eprintln!("[SERIAL] Owner check failed: {}", e);
+        ClaimStatus::Unsupported
+    });
+    match &status {
+        ClaimStatus::Claimed => session.record(SessionEventKind::Connected, "Claimed the device", None),
+        ClaimStatus::Foreign { owner_name, .. } => session.record(
+            SessionEventKind::Connected,
+            format!("Device is claimed by {}", owner_name),
+            None,
+        ),
+        ClaimStatus::Ours | ClaimStatus::Unsupported => {}
+    }
+    status
+}
+
+fn remember_port(app: &AppHandle, port: &str) {
+    if let Err(e) = port_history::record_connection(app, port) {
+        eprintln!("[SERIAL] Failed to record port history: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn disconnect(app: AppHandle, state: State<'_, SerialState>, session: State<'_, SessionLog>, supervisor: State<'_, ConnectionSupervisor>) -> Result<(), String> {
+    // Stop and join connection-bound background work before the port goes away
+    supervisor.shutdown().await;
+    let port = state
+        .with(|connection| {
+            let port = connection.port_name().unwrap_or_default().to_string();
+            connection.disconnect().map(|_| port).map_err(|e| e.to_string())
+        })
+        .await??;
+    session.record(SessionEventKind::Disconnected, format!("Disconnected from {}", port), None);
+    tray::show(&app, BenchState::Disconnected);
+    Ok(())
+}
+
+/// Start the signal once the interlocks enabled in settings pass; `confirmed` is the
+/// operator's go-ahead for the confirmation interlock
+#[tauri::command]
+pub async fn run_signal(confirmed: Option<bool>, app: AppHandle, state: State<'_, SerialState>) -> Result<CommandOutcome, CommandError> {
+    let _command = app.state::<ConcurrencyGate>().try_begin(Operation::Command)?;
+    state
+        .with(move |connection| {
+            let enabled = app.state::<SettingsState>().get().interlocks;
+            let failures = interlocks::check(connection, &enabled, confirmed.unwrap_or(false));
+            if !failures.is_empty() {
+                return Err(CommandError::interlocks(&failures));
+            }
+            let outcome = send_logged(&app, connection, DeviceCommand::Run)?;
+            if outcome.status != OutcomeStatus::Rejected {
+                app.state::<HookState>().set_expected_running(true);
+            }
+            refresh_after(&app, connection);
+            Ok(outcome)
+        })
+        .await?
+}
+
+/// Not subject to the concurrency matrix, so a stop is never refused for what else is
+/// running. It still queues on the port like any request: behind a running upload,
+/// flash or step test it goes out once that finishes.
+#[tauri::command]
+pub async fn stop_signal(app: AppHandle, state: State<'_, SerialState>, hook_state: State<'_, HookState>) -> Result<CommandOutcome, String> {
+    hook_state.set_expected_running(false);
+    send_and_refresh(app, &state, DeviceCommand::Stop).await
+}
+
+#[tauri::command]
+pub async fn increase_rpm(app: AppHandle, state: State<'_, SerialState>) -> Result<CommandOutcome, String> {
+    let _command = app.state::<ConcurrencyGate>().try_begin(Operation::Command)?;
+    send_and_refresh(app, &state, DeviceCommand::RpmUp).await
+}
+
+#[tauri::command]
+pub async fn decrease_rpm(app: AppHandle, state: State<'_, SerialState>) -> Result<CommandOutcome, String> {
+    let _command = app.state::<ConcurrencyGate>().try_begin(Operation::Command)?;
+    send_and_refresh(app, &state, DeviceCommand::RpmDown).await
+}
+
+#[tauri::command]
+pub async fn save_to_nvs(app: AppHandle, state: State<'_, SerialState>) -> Result<CommandOutcome, String> {
+    let _nvs = app.state::<ConcurrencyGate>().try_begin(Operation::NvsWrite)?;
+    let _critical = critical::enter(&app, "NVS write");
+    state
+        .with(move |connection| send_logged(&app, connection, DeviceCommand::SaveNvs))
+        .await?
+}
+
+/// Reset the device to factory defaults, after backing up its config when the
+/// protocol profile can read it back. A failed backup cancels the reset.
+#[tauri::command]
+pub async fn reset_defaults(app: AppHandle, state: State<'_, SerialState>) -> Result<CommandOutcome, String> {
+    let _nvs = app.state::<ConcurrencyGate>().try_begin(Operation::NvsWrite)?;
+    let _critical = critical::enter(&app, "NVS reset");
+    state
+        .with(move |connection| {
+            let label = DeviceCommand::ResetDefaults.label();
+            match device_backup::take(&app, connection, &label) {
+                Ok(Some(_)) => app.state::<SessionLog>().record(
+                    SessionEventKind::Command,
+                    format!("Device config backed up before {}", label),
+                    None,
+                ),
+                Ok(None) => eprintln!("[SERIAL] Profile has no config readback, resetting without a backup"),
+                Err(e) => return Err(format!("Config backup failed, reset not sent: {}", e)),
+            }
+            let outcome = send_logged(&app, connection, DeviceCommand::ResetDefaults)?;
+            refresh_after(&app, connection);
+            Ok(outcome)
+        })
+        .await?
+}
+
+/// Assert or deassert DTR and RTS on the open port; a line left out keeps its state
+#[tauri::command]
+pub async fn set_modem_lines(dtr: Option<bool>, rts: Option<bool>, state: State<'_, SerialState>) -> Result<(), String> {
+    state
+        .with(move |connection| connection.set_modem_lines(dtr, rts).map_err(|e| e.to_string()))
+        .await?
+}
+
+/// Reboot the ESP32 through DTR/RTS like esptool: normally (e.g. when the firmware
+/// wedged) or into the ROM download mode for flashing. Returns the boot banner so the
+/// UI can confirm the reset happened.
+#[tauri::command]
+pub async fn reset_device(bootloader: Option<bool>, app: AppHandle, state: State<'_, SerialState>, hook_state: State<'_, HookState>) -> Result<BootReport, String> {
+    let bootloader = bootloader.unwrap_or(false);
+    let _reset = app.state::<ConcurrencyGate>().try_begin(Operation::Reset)?;
+    let _critical = critical::enter(&app, if bootloader { "bootloader entry" } else { "hard reset" });
+    // The signal stops with the restart; that's not worth a hook
+    hook_state.set_expected_running(false);
+    state
+        .with(move |connection| {
+            let report = connection.reset(bootloader).map_err(|e| e.to_string())?;
+            let label = if bootloader { "Reset into download mode" } else { "Hard reset" };
+            app.state::<SessionLog>().record(SessionEventKind::Command, label, report.reset_reason.clone());
+            // In download mode our firmware isn't running to answer a status query
+            if !bootloader {
+                refresh_after(&app, connection);
+            }
+            Ok(report)
+        })
+        .await?
+}
+
+/// The backup taken before the last destructive operation, if any
+#[tauri::command]
+pub fn get_last_device_backup(app: AppHandle) -> Result<Option<DeviceBackup>, String> {
+    device_backup::latest(&app)
+}
+
+/// Upload the most recent device backup again; runs as a job and returns its ID
+#[tauri::command]
+pub async fn restore_last_device_backup(app: AppHandle) -> Result<u64, CommandError> {
+    let backup = device_backup::latest(&app)?.ok_or_else(|| "No device backup to restore".to_string())?;
+    let note = format!("Restore of the backup taken before \"{}\"", backup.reason);
+    start_upload(&app, UploadRequest::Config { config: backup.config, pacing: ChunkPacing::default() }, Some(note)).await
+}
+
+/// Send a raw command line for firmware features without a dedicated button
+#[tauri::command]
+pub async fn send_custom_command(text: String, app: AppHandle, state: State<'_, SerialState>) -> Result<CommandOutcome, String> {
+    let command = DeviceCommand::Custom(text);
+    command.validate()?;
+    let _command = app.state::<ConcurrencyGate>().try_begin(Operation::Command)?;
+    state.with(move |connection| send_logged(&app, connection, command)).await?
+}
+
+/// Send a command that changes the run state or RPM, then push the fresh status
+async fn send_and_refresh(app: AppHandle, state: &SerialState, command: DeviceCommand) -> Result<CommandOutcome, String> {
+    state
+        .with(move |connection| {
+            let outcome = send_logged(&app, connection, command)?;
+            refresh_after(&app, connection);
+            Ok(outcome)
+        })
+        .await?
+}
+
+/// Send a device command and note it in the session log, with the reason when the
+/// device didn't confirm it
+pub(super) fn send_logged(app: &AppHandle, connection: &mut SerialConnection, command: DeviceCommand) -> Result<CommandOutcome, String> {
+    let outcome = connection.send_command(&command).map_err(|e| e.to_string())?;
+    let note = (!outcome.is_accepted()).then(|| outcome.describe());
+    app.state::<SessionLog>().record(SessionEventKind::Command, command.label(), note);
+    Ok(outcome)
+}
+
+/// Set the firmware's log verbosity (requires a profile with log level control)
+#[tauri::command]
+pub async fn set_device_log_level(level: DeviceLogLevel, state: State<'_, SerialState>) -> Result<(), String> {
+    state
+        .with(move |connection| connection.set_log_level(level).map_err(|e| e.to_string()))
+        .await?
+}
+
+#[tauri::command]
+pub async fn get_status(app: AppHandle, state: State<'_, SerialState>, gate: State<'_, ConcurrencyGate>) -> Result<DeviceStatus, String> {
+    // Don't queue up behind an upload or flash holding the port
+    let _poll = gate.try_begin(Operation::StatusPoll)?;
+    let status = state
+        .with(|connection| connection.get_status().map_err(|e| e.to_string()))
+        .await??;
+    observe_status(&app, &status);
+    Ok(status)
+}
+
+/// RPM and run state only, for gauges refreshed many times a second
+#[tauri::command]
+pub async fn get_rpm_fast(state: State<'_, SerialState>, gate: State<'_, ConcurrencyGate>) -> Result<RpmReading, String> {
+    let _poll = gate.try_begin(Operation::StatusPoll)?;
+    state
+        .with(|connection| connection.get_rpm_fast().map_err(|e| e.to_string()))
+        .await?
+}
+
+/// Feed a status response to the history and the fault/reset/stop hooks
+fn observe_status(app: &AppHandle, status: &DeviceStatus) {
+    app.state::<StatusHistory>().record(status);
+    tray::show(app, BenchState::of(status));
+    if !status.connected {
+        return;
+    }
+
+    let hook_state = app.state::<HookState>();
+    let session = app.state::<SessionLog>();
+    if status.reset_detected {
+        report_reset(app, &hook_state, &session, status);
+    }
+    if hook_state.check_new_fault(status.fault.as_deref()) {
+        let message = format!("Device fault: {}", status.fault.as_deref().unwrap_or_default());
+        session.record(SessionEventKind::Fault, message.clone(), None);
+        hooks::fire(app, HookEvent::DeviceFault, status.port_name.clone(), message);
+    }
+    if hook_state.check_unexpected_stop(status.running) {
+        hooks::fire(app, HookEvent::SignalStopped, status.port_name.clone(), "Signal stopped unexpectedly");
+    }
+}
+
+/// Query the status right after a command that changed the run state or RPM and push
+/// it as `device://status`, so the UI never shows what was true before the command
+fn refresh_after(app: &AppHandle, connection: &mut SerialConnection) {
+    match connection.get_status() {
+        Ok(status) => {
+            observe_status(app, &status);
+            let _ = app.emit(DEVICE_STATUS_EVENT, &status);
+        }
+        Err(e) => eprintln!("[SERIAL] Status refresh after command failed: {}", e),
+    }
+}
+
+fn report_reset(app: &AppHandle, hook_state: &HookState, session: &SessionLog, status: &DeviceStatus) {
+    let message = format!("Device reset detected (reset #{} this connection)", status.reset_count);
+    session.record(SessionEventKind::Fault, message.clone(), None);
+    if hook_state.expects_running() {
+        let _ = app.emit(DEVICE_RESET_EVENT, status);
+        hooks::fire(app, HookEvent::DeviceReset, status.port_name.clone(), message);
+    }
+}
+
+/// Rolling RPM statistics over the status responses of the last `window_secs` seconds
+#[tauri::command]
+pub fn get_rpm_stats(window_secs: u64, history: State<StatusHistory>) -> Result<RpmStats, String> {
+    Ok(history.rpm_stats(window_secs))
+}
+
+/// Reply time histograms per firmware build and command type, for tuning the
+/// per-command timeouts; `clear` starts a fresh collection after reading
+#[tauri::command]
+pub async fn get_timing_stats(clear: Option<bool>, state: State<'_, SerialState>) -> Result<TimingStats, String> {
+    state
+        .with(move |connection| {
+            let stats = connection.timing_stats();
+            if clear.unwrap_or(false) {
+                connection.clear_timing_stats();
+            }
+            stats
+        })
+        .await
+}
+
+/// Dump the status samples of the last `window_secs` seconds (what the RPM chart shows)
+/// to `dest` as CSV or JSON; returns the number of samples written
+#[tauri::command]
+pub fn export_status_history(window_secs: u64, dest: String, format: ExportFormat, history: State<StatusHistory>) -> Result<usize, String> {
+    history.export(window_secs, std::path::Path::new(&dest), format)
+}
+
+/// Upload a config to the device; runs as a job and returns its ID.
+/// The `UploadResult` arrives with the finished job. `chunk_size` and `chunk_delay_ms`
+/// default to the 64-byte / 2 ms pacing any ESP32 keeps up with; native-USB firmware
+/// can take much larger chunks.
+#[tauri::command]
+pub async fn upload_config(
+    config: String,
+    note: Option<String>,
+    chunk_size: Option<usize>,
+    chunk_delay_ms: Option<u64>,
+    app: AppHandle,
+) -> Result<u64, String> {
+    let pacing = ChunkPacing::new(chunk_size, chunk_delay_ms)?;
+    start_upload(&app, UploadRequest::Config { config, pacing }, note).await.map_err(|e| e.message)
+}
+
+/// Stop the config upload in progress (and any queued behind it) without waiting for
+/// the device's timeout; returns the IDs of the cancelled jobs
+#[tauri::command]
+pub fn abort_upload(jobs: State<JobManager>) -> Result<Vec<u64>, String> {
+    // One that finishes in the meantime simply isn't counted
+    let cancelled: Vec<u64> = jobs
+        .active()
+        .into_iter()
+        .filter(|j| j.kind == JobKind::ConfigUpload)
+        .map(|j| j.id)
+        .filter(|id| jobs.cancel(*id).is_ok())
+        .collect();
+    if cancelled.is_empty() {
+        return Err("No upload in progress".into());
+    }
+    Ok(cancelled)
+}
+
+/// Check a config before uploading it, without touching the device
+#[tauri::command]
+pub async fn preflight_upload(config: String, state: State<'_, SerialState>) -> Result<PreflightReport, String> {
+    let mut report = preview::preflight(&config);
+    report.estimate = state
+        .with(move |connection| connection.is_connected().then(|| connection.estimate_upload(&config, ChunkPacing::default())))
+        .await?;
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn is_connected(state: State<'_, SerialState>) -> Result<bool, String> {
+    state.with(|connection| connection.is_connected()).await
+}
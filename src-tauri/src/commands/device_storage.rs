@@ -0,0 +1,137 @@
+use crate::concurrency::{ConcurrencyGate, Operation};
+use crate::critical;
+use crate::device_fs::{self, DeviceFile, DeviceSignal, TransferProgress};
+use crate::jobs::{JobKind, JobManager};
+use crate::serial::SerialState;
+use crate::signal_sync::{self, SyncReport};
+use crate::signals::{self, ImportOutcome, SignalConfig};
+use tauri::{AppHandle, Manager, State};
+
+/// List files stored on the device filesystem
+#[tauri::command]
+pub async fn list_device_files(state: State<'_, SerialState>) -> Result<Vec<DeviceFile>, String> {
+    state
+        .with(|connection| device_fs::list_files(connection).map_err(|e| e.to_string()))
+        .await?
+}
+
+/// Download a file from the device into the app data folder.
+/// Runs as a job; returns its ID and the local path arrives with the finished job.
+#[tauri::command]
+pub fn download_device_file(name: String, app: AppHandle, state: State<SerialState>, jobs: State<JobManager>) -> Result<u64, String> {
+    let state = state.inner().clone();
+    let label = format!("Download of {}", name);
+    Ok(jobs.start(&app.clone(), JobKind::DeviceFileDownload, label, move |job| {
+        job.checkpoint()?;
+        let _transfer = app
+            .state::<ConcurrencyGate>()
+            .begin_blocking(Operation::FileTransfer, || job.is_cancelled())?;
+        let _critical = critical::enter(&app, "device file download");
+        let remote = name.clone();
+        let data = state
+            .with_events(
+                move |connection, on_progress| device_fs::download_file(connection, &remote, on_progress),
+                |p: TransferProgress| job.progress(p.bytes_done, p.total, None),
+            )?
+            .map_err(|e| e.to_string())?;
+
+        let path = device_fs::get_downloads_dir(&app)?.join(device_fs::local_name(&name));
+        std::fs::write(&path, data).map_err(|e| e.to_string())?;
+        Ok(path.to_string_lossy().to_string())
+    }))
+}
+
+/// Upload a local file to the device filesystem under `remote_name`; runs as a job and returns its ID
+#[tauri::command]
+pub fn upload_device_file(local_path: String, remote_name: String, app: AppHandle, state: State<SerialState>, jobs: State<JobManager>) -> Result<u64, String> {
+    let data = std::fs::read(&local_path)
+        .map_err(|e| format!("Failed to read '{}': {}", local_path, e))?;
+
+    let state = state.inner().clone();
+    let label = format!("Upload of {} to the device", remote_name);
+    Ok(jobs.start(&app.clone(), JobKind::DeviceFileUpload, label, move |job| {
+        job.checkpoint()?;
+        let _transfer = app
+            .state::<ConcurrencyGate>()
+            .begin_blocking(Operation::FileTransfer, || job.is_cancelled())?;
+        let _critical = critical::enter(&app, "device file upload");
+        state
+            .with_events(
+                move |connection, on_progress| device_fs::upload_file(connection, &remote_name, &data, on_progress),
+                |p: TransferProgress| job.progress(p.bytes_done, p.total, None),
+            )?
+            .map_err(|e| e.to_string())
+    }))
+}
+
+/// Delete a file from the device filesystem
+#[tauri::command]
+pub async fn delete_device_file(name: String, state: State<'_, SerialState>) -> Result<(), String> {
+    state
+        .with(move |connection| device_fs::delete_file(connection, &name).map_err(|e| e.to_string()))
+        .await?
+}
+
+/// List the named signals stored on the device, marking the active one
+#[tauri::command]
+pub async fn list_device_signals(state: State<'_, SerialState>) -> Result<Vec<DeviceSignal>, String> {
+    state
+        .with(|connection| device_fs::list_signals(connection).map_err(|e| e.to_string()))
+        .await?
+}
+
+/// Switch the device to one of its stored signals; returns the refreshed catalog
+#[tauri::command]
+pub async fn activate_device_signal(name: String, app: AppHandle, state: State<'_, SerialState>) -> Result<Vec<DeviceSignal>, String> {
+    let _command = app.state::<ConcurrencyGate>().try_begin(Operation::Command)?;
+    state
+        .with(move |connection| {
+            device_fs::activate_signal(connection, &name)?;
+            device_fs::list_signals(connection)
+        })
+        .await?
+        .map_err(|e| e.to_string())
+}
+
+/// Match device-stored signals to library entries by name and content, listing what
+/// is missing on either side
+#[tauri::command]
+pub async fn reconcile_device_signals(app: AppHandle, state: State<'_, SerialState>) -> Result<SyncReport, String> {
+    let device = state
+        .with(|connection| device_fs::list_signals(connection).map_err(|e| e.to_string()))
+        .await??;
+    Ok(signal_sync::reconcile(signal_sync::local_signals(&app)?, device))
+}
+
+/// Store a library signal in its device slot (see `signal_sync::slot_name`); returns the
+/// refreshed reconciliation
+#[tauri::command]
+pub async fn push_signal_to_device(filename: String, app: AppHandle, state: State<'_, SerialState>) -> Result<SyncReport, String> {
+    let config = signals::load_signal(&app, &filename).map_err(|e| e.to_string())?;
+    let _transfer = app.state::<ConcurrencyGate>().try_begin(Operation::FileTransfer)?;
+    let _critical = critical::enter(&app, "signal push to the device");
+    let slot = signal_sync::slot_name(&config.name);
+    let json = signals::format_for_esp32(&config);
+    let device = state
+        .with(move |connection| {
+            device_fs::store_signal(connection, &slot, &json)?;
+            device_fs::list_signals(connection)
+        })
+        .await?
+        .map_err(|e| e.to_string())?;
+    Ok(signal_sync::reconcile(signal_sync::local_signals(&app)?, device))
+}
+
+/// Copy a device-stored signal into the library; content already in the library is
+/// reported as a duplicate rather than saved twice
+#[tauri::command]
+pub async fn pull_signal_from_device(name: String, app: AppHandle, state: State<'_, SerialState>) -> Result<ImportOutcome, String> {
+    let _transfer = app.state::<ConcurrencyGate>().try_begin(Operation::FileTransfer)?;
+    let _critical = critical::enter(&app, "signal pull from the device");
+    let json = state
+        .with(move |connection| device_fs::read_signal(connection, &name).map_err(|e| e.to_string()))
+        .await??;
+    let config: SignalConfig = serde_json::from_str(&json)
+        .map_err(|e| format!("Device returned an invalid signal: {}", e))?;
+    signals::import_signal(&app, &config, false).map_err(|e| e.to_string())
+}
@@ -0,0 +1,31 @@
+use super::device::send_logged;
+use crate::concurrency::{ConcurrencyGate, Operation};
+use crate::device_command::{CommandOutcome, DeviceCommand};
+use crate::extensions::{self, ExtensionList, ExtensionRegistry};
+use crate::serial::SerialState;
+use tauri::{AppHandle, Manager, State};
+
+/// Extension commands registered from the descriptor folder, with any load errors
+#[tauri::command]
+pub fn list_extension_commands(registry: State<ExtensionRegistry>) -> ExtensionList {
+    registry.list()
+}
+
+/// Re-read the descriptor folder, e.g. after adding a file, without restarting the app
+#[tauri::command]
+pub fn reload_extensions(app: AppHandle) -> ExtensionList {
+    extensions::load(&app)
+}
+
+/// Send an extension command with its parameters from `args`, verified against the
+/// reply its descriptor expects
+#[tauri::command]
+pub async fn run_extension_command(name: String, args: Option<serde_json::Map<String, serde_json::Value>>, app: AppHandle, registry: State<'_, ExtensionRegistry>, state: State<'_, SerialState>) -> Result<CommandOutcome, String> {
+    let extension = registry
+        .get(&name)
+        .ok_or_else(|| format!("No extension command named '{}'", name))?;
+    let command = DeviceCommand::Extension(extension.bind(&args.unwrap_or_default())?);
+    command.validate()?;
+    let _command = app.state::<ConcurrencyGate>().try_begin(Operation::Command)?;
+    state.with(move |connection| send_logged(&app, connection, command)).await?
+}
@@ -0,0 +1,76 @@
+use crate::concurrency::{ConcurrencyGate, Operation};
+use crate::critical;
+use crate::firmware::{self, APP_OFFSET};
+use crate::jobs::{JobKind, JobManager};
+use crate::ota;
+use crate::profiles;
+use crate::serial::SerialState;
+use crate::session::{SessionEventKind, SessionLog};
+use crate::supervisor::ConnectionSupervisor;
+use crate::tray::{self, BenchState};
+use tauri::{AppHandle, Manager, State};
+
+/// Push a firmware .bin to the device over WiFi (ArduinoOTA on `host`, independent of
+/// the serial connection). The image check after reboot uses the protocol of connection
+/// profile `profile`, or of the current connection. Runs as a job; returns its ID and
+/// the `OtaResult` arrives with the finished job.
+#[tauri::command]
+pub fn ota_update(path: String, host: String, profile: Option<String>, app: AppHandle, jobs: State<JobManager>) -> Result<u64, String> {
+    let label = format!("Firmware update of {}", host);
+    Ok(jobs.start(&app.clone(), JobKind::Firmware, label, move |job| {
+        job.checkpoint()?;
+        let probe = match profile {
+            Some(name) => profiles::get_profile(&app, &name).map_err(|e| e.to_string())?.protocol.ota_image_probe,
+            None => app
+                .state::<SerialState>()
+                .with_blocking(|connection| connection.protocol().ota_image_probe.clone())?,
+        };
+        let _firmware = app
+            .state::<ConcurrencyGate>()
+            .begin_blocking(Operation::Firmware, || job.is_cancelled())?;
+        let _critical = critical::enter(&app, "firmware update");
+        ota::update(&host, std::path::Path::new(&path), probe.as_deref(), |p| {
+            job.progress(p.bytes_sent, p.total, Some(p.phase))
+        })
+        .map_err(|e| e.to_string())
+    }))
+}
+
+/// Flash a firmware .bin to the connected ESP32 over USB through its ROM bootloader,
+/// at `address` (default: the app partition). The app lets go of the port for this,
+/// so the device shows as disconnected until it is connected again. Runs as a job;
+/// returns its ID and the `FlashResult` arrives with the finished job.
+#[tauri::command]
+pub async fn flash_firmware(path: String, address: Option<u32>, app: AppHandle, state: State<'_, SerialState>, gate: State<'_, ConcurrencyGate>, session: State<'_, SessionLog>, supervisor: State<'_, ConnectionSupervisor>) -> Result<u64, String> {
+    let image = std::fs::read(&path).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    let port = state
+        .with(|connection| connection.port_name().map(String::from))
+        .await?
+        .ok_or("Connect to the ESP32 first")?;
+    // Refuse now rather than pulling the port from under an upload
+    let admission = gate.try_begin(Operation::Firmware)?;
+
+    supervisor.shutdown().await;
+    state
+        .with(|connection| connection.disconnect().map_err(|e| e.to_string()))
+        .await??;
+    session.record(SessionEventKind::Disconnected, format!("Released {} for flashing", port), None);
+    tray::show(&app, BenchState::Disconnected);
+
+    let address = address.unwrap_or(APP_OFFSET);
+    let label = format!("Firmware flash of {}", port);
+    let jobs = app.state::<JobManager>().inner().clone();
+    Ok(jobs.start(&app.clone(), JobKind::Firmware, label, move |job| {
+        let _firmware = admission;
+        job.checkpoint()?;
+        let _critical = critical::enter(&app, "firmware flash");
+        let result = firmware::flash(&port, &image, address, |p| job.progress(p.percent, 100, Some(p.phase)))
+            .map_err(|e| e.to_string())?;
+        app.state::<SessionLog>().record(
+            SessionEventKind::Command,
+            format!("Flashed {} bytes to the {} on {}", result.bytes_written, result.chip, port),
+            None,
+        );
+        Ok(result)
+    }))
+}
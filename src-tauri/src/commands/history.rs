@@ -0,0 +1,37 @@
+use crate::history::{self, UploadRecord};
+use crate::session::{SessionEvent, SessionEventKind, SessionLog};
+use tauri::{AppHandle, State};
+
+/// Upload history, newest first, optionally limited to the latest `limit` entries
+#[tauri::command]
+pub fn get_upload_history(limit: Option<usize>, app: AppHandle) -> Result<Vec<UploadRecord>, String> {
+    let mut records = history::load_history(&app)?;
+    records.reverse();
+    if let Some(limit) = limit {
+        records.truncate(limit);
+    }
+    Ok(records)
+}
+
+/// Structured log of the current app session, oldest first
+#[tauri::command]
+pub fn get_session_log(session: State<SessionLog>) -> Result<Vec<SessionEvent>, String> {
+    Ok(session.events())
+}
+
+/// Connections, commands, uploads, faults and markers between `from` and `to`
+/// (epoch milliseconds, either end optional), oldest first
+#[tauri::command]
+pub fn get_session_timeline(from: Option<u64>, to: Option<u64>, session: State<SessionLog>) -> Result<Vec<SessionEvent>, String> {
+    Ok(session.timeline(from, to))
+}
+
+/// Place an operator marker on the session timeline
+#[tauri::command]
+pub fn add_session_marker(text: String, note: Option<String>, session: State<SessionLog>) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("Marker text cannot be empty".into());
+    }
+    session.record(SessionEventKind::Marker, text, note);
+    Ok(())
+}
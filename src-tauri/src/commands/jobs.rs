@@ -0,0 +1,68 @@
+use super::start_upload;
+use crate::jobs::{Job, JobManager};
+use crate::serial::SerialState;
+use crate::upload_queue::{PendingUpload, UploadQueue};
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+/// How one interrupted upload fared when resumed
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumedUpload {
+    pub label: String,
+    pub job_id: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Current state of a job started by an upload, flash or file transfer command
+#[tauri::command]
+pub fn get_job(id: u64, jobs: State<JobManager>) -> Result<Job, String> {
+    jobs.get(id).ok_or_else(|| format!("No job {}", id))
+}
+
+/// Running and recently finished jobs, oldest first
+#[tauri::command]
+pub fn list_jobs(jobs: State<JobManager>) -> Result<Vec<Job>, String> {
+    Ok(jobs.list())
+}
+
+/// Ask a running job to stop at its next safe point
+#[tauri::command]
+pub fn cancel_job(id: u64, jobs: State<JobManager>) -> Result<(), String> {
+    jobs.cancel(id)
+}
+
+/// Uploads that were queued or running when the app last closed
+#[tauri::command]
+pub fn get_pending_jobs(queue: State<UploadQueue>) -> Result<Vec<PendingUpload>, String> {
+    Ok(queue.interrupted())
+}
+
+/// Start the interrupted uploads again, in their original order, on the connected device
+#[tauri::command]
+pub async fn resume_pending_jobs(app: AppHandle, queue: State<'_, UploadQueue>, serial: State<'_, SerialState>) -> Result<Vec<ResumedUpload>, String> {
+    if !serial.with(|connection| connection.is_connected()).await? {
+        return Err("Connect to the device before resuming uploads".into());
+    }
+    let mut resumed = Vec::new();
+    for upload in queue.take_interrupted(&app)? {
+        resumed.push(match start_upload(&app, upload.request, upload.note).await {
+            Ok(job_id) => ResumedUpload {
+                label: upload.label,
+                job_id: Some(job_id),
+                error: None,
+            },
+            Err(e) => ResumedUpload {
+                label: upload.label,
+                job_id: None,
+                error: Some(e.message),
+            },
+        });
+    }
+    Ok(resumed)
+}
+
+/// Forget the interrupted uploads; returns how many there were
+#[tauri::command]
+pub fn discard_pending_jobs(app: AppHandle, queue: State<UploadQueue>) -> Result<usize, String> {
+    Ok(queue.take_interrupted(&app)?.len())
+}
@@ -0,0 +1,146 @@
+use super::{start_upload, CommandError};
+use crate::integrity::{self, LibraryScanState, ScanSummary};
+use crate::legacy::{self, LegacyImportResult};
+use crate::signal_index;
+use crate::signal_link;
+use crate::signal_qr::{self, QrAssembly, QrChunk, QrImportProgress};
+use crate::signals::{self, ImportOutcome, SignalConfig, SignalInfo};
+use crate::sigpack::{self, Manifest};
+use crate::upload_queue::UploadRequest;
+use tauri::{AppHandle, State};
+
+/// Import a signal config from JSON string and save locally.
+/// Exact channel-blob duplicates are reported instead of saved unless `allow_duplicate` is set.
+#[tauri::command]
+pub fn import_signal(json: String, allow_duplicate: Option<bool>, app: AppHandle) -> Result<ImportOutcome, String> {
+    let config: SignalConfig = serde_json::from_str(&json)
+        .map_err(|e| format!("Invalid JSON: {}", e))?;
+    
+    signals::import_signal(&app, &config, allow_duplicate.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// List all saved signals
+#[tauri::command]
+pub fn list_saved_signals(app: AppHandle) -> Result<Vec<SignalInfo>, String> {
+    signals::list_signals(&app)
+        .map_err(|e| e.to_string())
+}
+
+/// Load a signal by filename
+#[tauri::command]
+pub fn load_saved_signal(filename: String, app: AppHandle) -> Result<SignalConfig, String> {
+    signals::load_signal(&app, &filename)
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a signal by filename
+#[tauri::command]
+pub fn delete_saved_signal(filename: String, app: AppHandle) -> Result<(), String> {
+    signals::delete_signal(&app, &filename)
+        .map_err(|e| e.to_string())
+}
+
+/// Retire a signal to the archive: it leaves the library listing but stays on disk
+#[tauri::command]
+pub fn archive_signal(filename: String, app: AppHandle) -> Result<(), String> {
+    signals::archive_signal(&app, &filename)
+        .map_err(|e| e.to_string())
+}
+
+/// Archived signals, optionally only those whose name contains `query`
+#[tauri::command]
+pub fn list_archived_signals(query: Option<String>, app: AppHandle) -> Result<Vec<SignalInfo>, String> {
+    signals::list_archived(&app, query.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// Load an archived signal for viewing; archived signals can't be uploaded
+#[tauri::command]
+pub fn load_archived_signal(filename: String, app: AppHandle) -> Result<SignalConfig, String> {
+    signals::load_archived(&app, &filename)
+        .map_err(|e| e.to_string())
+}
+
+/// Move an archived signal back into the library
+#[tauri::command]
+pub fn restore_archived_signal(filename: String, app: AppHandle) -> Result<(), String> {
+    signals::restore_signal(&app, &filename)
+        .map_err(|e| e.to_string())
+}
+
+/// Load a signal and upload it to ESP32; runs as a job and returns its ID.
+/// Device-specific signals are refused for other units unless `override_binding` is set.
+#[tauri::command]
+pub async fn upload_saved_signal(filename: String, note: Option<String>, override_binding: Option<bool>, app: AppHandle) -> Result<u64, CommandError> {
+    let request = UploadRequest::Library {
+        filename,
+        override_binding: override_binding.unwrap_or(false),
+    };
+    start_upload(&app, request, note).await
+}
+
+/// Mark a signal as calibrated for one unit, binding it to the unit it was last uploaded to
+#[tauri::command]
+pub fn set_signal_device_specific(filename: String, device_specific: bool, app: AppHandle) -> Result<(), String> {
+    signal_index::set_device_specific(&app, &filename, device_specific)
+}
+
+/// Import every legacy `.sgn` signal in a folder, reporting the result per file
+#[tauri::command]
+pub fn migrate_legacy_signals(dir: String, app: AppHandle) -> Result<Vec<LegacyImportResult>, String> {
+    legacy::migrate_dir(&app, std::path::Path::new(&dir))
+        .map_err(|e| e.to_string())
+}
+
+/// Export library signals to a checksummed `.sigpack` bundle
+#[tauri::command]
+pub fn export_sigpack(filenames: Vec<String>, dest: String, app: AppHandle) -> Result<Manifest, String> {
+    sigpack::export(&app, &filenames, std::path::Path::new(&dest))
+        .map_err(|e| e.to_string())
+}
+
+/// Verify a `.sigpack` bundle and import all of its signals
+#[tauri::command]
+pub fn import_sigpack(path: String, app: AppHandle) -> Result<Vec<String>, String> {
+    sigpack::import(&app, std::path::Path::new(&path))
+        .map_err(|e| e.to_string())
+}
+
+/// Shareable `esp32sig://` link carrying the whole signal, for pasting into chat
+#[tauri::command]
+pub fn export_signal_link(filename: String, app: AppHandle) -> Result<String, String> {
+    signal_link::export(&app, &filename).map_err(|e| e.to_string())
+}
+
+/// Import a pasted `esp32sig://` link
+#[tauri::command]
+pub fn import_signal_link(link: String, allow_duplicate: Option<bool>, app: AppHandle) -> Result<ImportOutcome, String> {
+    signal_link::import(&app, &link, allow_duplicate.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+/// A signal as a series of QR codes, for moving it to a tablet without a network
+#[tauri::command]
+pub fn export_signal_qr(filename: String, app: AppHandle) -> Result<Vec<QrChunk>, String> {
+    signal_qr::export(&app, &filename).map_err(|e| e.to_string())
+}
+
+/// Feed one scanned QR code; the signal is imported once all of its codes are in
+#[tauri::command]
+pub fn import_signal_from_qr(payload: String, app: AppHandle, assembly: State<QrAssembly>) -> Result<QrImportProgress, String> {
+    assembly.accept(&app, &payload).map_err(|e| e.to_string())
+}
+
+/// Validate every stored signal now (schema, SIG1 decode and CRC)
+#[tauri::command]
+pub async fn scan_library(app: AppHandle) -> Result<ScanSummary, String> {
+    tokio::task::spawn_blocking(move || integrity::scan(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Summary of the most recent library scan, if one has run
+#[tauri::command]
+pub fn get_library_scan_summary(scan_state: State<LibraryScanState>) -> Result<Option<ScanSummary>, String> {
+    Ok(scan_state.last())
+}
@@ -0,0 +1,445 @@
+use crate::concurrency::{ConcurrencyGate, Operation};
+use crate::critical;
+use crate::events::{self, LineBatcher, Throttle};
+use crate::history::UploadRecord;
+use crate::hooks;
+use crate::interlocks::InterlockFailure;
+use crate::jobs::{JobContext, JobKind, JobManager};
+use crate::recent_output::RecentOutput;
+use crate::running_guard;
+use crate::serial::{
+    ChunkPacing, SerialConnection, SerialError, SerialState, UploadEvent, UploadResult, UploadStage,
+};
+use crate::session::{SessionEventKind, SessionLog};
+use crate::settings::{HookEvent, SettingsState};
+use crate::signals;
+use crate::upload_queue::{UploadQueue, UploadRequest};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+// Device output kept with a failed upload's history entry
+const FAILURE_LOG_WINDOW_MS: u64 = 5000;
+
+/// Structured error for commands the UI can react to, e.g. by offering an override
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandError {
+    pub code: &'static str,
+    pub message: String,
+    /// True when repeating the call with an explicit override would proceed
+    pub can_be_overridden: bool,
+    /// Bytes the device sent, for errors about what answered on the port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_bytes: Option<Vec<u8>>,
+}
+
+impl CommandError {
+    /// Signal bound to another unit (see `signal_index::binding_conflict`)
+    fn device_mismatch(message: String) -> Self {
+        CommandError {
+            code: "device_mismatch",
+            message,
+            can_be_overridden: true,
+            raw_bytes: None,
+        }
+    }
+
+    /// Pre-run interlocks failed; only a missing confirmation can be overridden
+    fn interlocks(failures: &[InterlockFailure]) -> Self {
+        let message = failures
+            .iter()
+            .map(|f| f.message.as_str())
+            .collect::<Vec<_>>()
+            .join("; ");
+        CommandError {
+            code: "interlock",
+            message,
+            can_be_overridden: failures.iter().all(|f| f.interlock == "confirmation"),
+            raw_bytes: None,
+        }
+    }
+
+    /// A connect attempt ran past the deadline from settings
+    fn connect_timeout(port: &str, limit: std::time::Duration) -> Self {
+        CommandError {
+            code: "timeout",
+            message: format!(
+                "Connecting to {} timed out after {} s",
+                port,
+                limit.as_secs()
+            ),
+            can_be_overridden: false,
+            raw_bytes: None,
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError {
+            code: "error",
+            message,
+            can_be_overridden: false,
+            raw_bytes: None,
+        }
+    }
+}
+
+impl From<SerialError> for CommandError {
+    fn from(err: SerialError) -> Self {
+        let message = err.to_string();
+        match err {
+            SerialError::NotOurDevice(raw) => CommandError {
+                code: "not_our_device",
+                message,
+                can_be_overridden: false,
+                raw_bytes: Some(raw),
+            },
+            _ => CommandError::from(message),
+        }
+    }
+}
+
+/// Declares the command modules and builds the invoke handler from one list,
+/// so a new command is registered right next to the module that defines it
+macro_rules! command_registry {
+    ($($module:ident: [$($command:ident),* $(,)?]),* $(,)?) => {
+        $(mod $module;)*
+
+        /// Invoke handler covering every registered command
+        pub fn handler() -> impl Fn(tauri::ipc::Invoke) -> bool + Send + Sync + 'static {
+            tauri::generate_handler![$($($module::$command),*),*]
+        }
+    };
+}
+
+command_registry! {
+    app: [
+        get_app_snapshot,
+        health_check,
+        open_monitor_window,
+        run_cleanup_now,
+    ],
+    device: [
+        list_ports,
+        connect,
+        connect_profile,
+        connect_by_serial,
+        disconnect,
+        run_signal,
+        stop_signal,
+        increase_rpm,
+        decrease_rpm,
+        save_to_nvs,
+        reset_defaults,
+        set_modem_lines,
+        reset_device,
+        get_last_device_backup,
+        restore_last_device_backup,
+        send_custom_command,
+        set_device_log_level,
+        get_status,
+        get_rpm_fast,
+        get_rpm_stats,
+        get_timing_stats,
+        export_status_history,
+        upload_config,
+        abort_upload,
+        preflight_upload,
+        is_connected,
+    ],
+    standby: [
+        list_devices,
+        open_standby,
+        close_standby,
+        switch_active_device,
+    ],
+    library: [
+        import_signal,
+        list_saved_signals,
+        load_saved_signal,
+        delete_saved_signal,
+        archive_signal,
+        list_archived_signals,
+        load_archived_signal,
+        restore_archived_signal,
+        upload_saved_signal,
+        set_signal_device_specific,
+        migrate_legacy_signals,
+        export_sigpack,
+        import_sigpack,
+        export_signal_link,
+        import_signal_link,
+        export_signal_qr,
+        import_signal_from_qr,
+        scan_library,
+        get_library_scan_summary,
+    ],
+    jobs: [
+        get_job,
+        list_jobs,
+        cancel_job,
+        get_pending_jobs,
+        resume_pending_jobs,
+        discard_pending_jobs,
+    ],
+    history: [
+        get_upload_history,
+        get_session_log,
+        get_session_timeline,
+        add_session_marker,
+    ],
+    device_storage: [
+        list_device_files,
+        download_device_file,
+        upload_device_file,
+        delete_device_file,
+        list_device_signals,
+        activate_device_signal,
+        reconcile_device_signals,
+        push_signal_to_device,
+        pull_signal_from_device,
+    ],
+    firmware: [
+        ota_update,
+        flash_firmware,
+    ],
+    debug: [
+        list_debug_commands,
+        run_debug_command,
+        get_debug_audit,
+    ],
+    extensions: [
+        list_extension_commands,
+        reload_extensions,
+        run_extension_command,
+    ],
+    soak: [
+        start_soak,
+        stop_soak,
+        get_soak_status,
+        run_step_test,
+    ],
+    profiles: [
+        list_profiles,
+        save_profile,
+        delete_profile,
+        export_profiles,
+        import_profiles,
+    ],
+    settings: [
+        get_settings,
+        get_settings_revision,
+        update_settings,
+        get_storage_info,
+        migrate_app_data,
+    ],
+    setup: [
+        get_setup_state,
+        setup_candidate_ports,
+        test_handshake,
+        create_default_settings,
+        install_preset_signals,
+        complete_setup,
+    ],
+}
+
+/// Start a config upload job, kept in the persistent upload queue until it ends.
+/// Library signals bound to another unit are refused unless the request overrides the binding.
+async fn start_upload(
+    app: &AppHandle,
+    request: UploadRequest,
+    note: Option<String>,
+) -> Result<u64, CommandError> {
+    let (json, signal_name, filename, pacing) = match &request {
+        UploadRequest::Config { config, pacing } => {
+            let signal_name = serde_json::from_str::<serde_json::Value>(config)
+                .ok()
+                .and_then(|v| v.get("name").and_then(|n| n.as_str()).map(String::from));
+            let filename = signal_name
+                .as_deref()
+                .and_then(|n| signals::library_filename(app, n));
+            (config.clone(), signal_name, filename, *pacing)
+        }
+        UploadRequest::Library {
+            filename,
+            override_binding,
+        } => {
+            let config = signals::load_signal(app, filename).map_err(|e| e.to_string())?;
+            let json = signals::format_for_esp32(&config);
+
+            // Debug: print what we're sending
+            eprintln!("[UPLOAD] JSON to send ({} bytes):", json.len());
+            eprintln!("[UPLOAD] {}", &json[..json.len().min(200)]);
+
+            // Refuse before starting the job, so the UI can offer the override
+            if !override_binding {
+                let identity = app
+                    .state::<SerialState>()
+                    .with(|c| c.identity().clone())
+                    .await?;
+                if let Some(conflict) =
+                    crate::signal_index::binding_conflict(app, filename, &identity)?
+                {
+                    return Err(CommandError::device_mismatch(conflict));
+                }
+            }
+            (
+                json,
+                Some(config.name),
+                Some(filename.clone()),
+                ChunkPacing::default(),
+            )
+        }
+    };
+
+    let label = format!("Upload of {}", signal_name.as_deref().unwrap_or("config"));
+    let entry = app
+        .state::<UploadQueue>()
+        .add(app, &label, note.clone(), request)?;
+    let state = app.state::<SerialState>().inner().clone();
+    let session = app.state::<SessionLog>().inner().clone();
+    let task_app = app.clone();
+    Ok(app
+        .state::<JobManager>()
+        .start(app, JobKind::ConfigUpload, label, move |job| {
+            let _queued = entry;
+            job.checkpoint()?;
+            // Waits out whatever else holds the device, e.g. an earlier upload
+            let _upload = task_app
+                .state::<ConcurrencyGate>()
+                .begin_blocking(Operation::ConfigUpload, || job.is_cancelled())?;
+            let _critical = critical::enter(&task_app, "config upload");
+            let policy = task_app
+                .state::<SettingsState>()
+                .get()
+                .running_upload_policy;
+            let record_app = task_app.clone();
+            let token = job.token();
+            let (result, record) = state.with_events(
+                move |connection, on_event| {
+                    let result = running_guard::upload(
+                        connection,
+                        &json,
+                        policy,
+                        pacing,
+                        &|| token.is_cancelled(),
+                        on_event,
+                    )?;
+                    let record = record_upload(
+                        &record_app,
+                        &session,
+                        connection,
+                        signal_name,
+                        filename,
+                        &result,
+                        note,
+                    );
+                    Ok::<_, String>((result, record))
+                },
+                upload_event_sink(&task_app, job),
+            )??;
+            // Only now has every line the device sent during the upload been collected
+            save_upload_record(&task_app, record);
+            Ok(result)
+        }))
+}
+
+/// Forward upload progress and device output to the UI: progress throttled and mirrored
+/// to the upload's job, other lines batched. Once the device reports its own progress,
+/// the job follows that instead of the bytes sent.
+fn upload_event_sink<'a>(app: &AppHandle, job: &'a mut JobContext) -> impl FnMut(UploadEvent) + 'a {
+    let mut sent = Throttle::new(app, events::UPLOAD_PROGRESS_EVENT);
+    let mut progress = Throttle::new(app, events::DEVICE_PROGRESS_EVENT);
+    let mut log = LineBatcher::new(app, events::UPLOAD_LOG_EVENT);
+    let recent = app.state::<RecentOutput>().inner().clone();
+    let mut device_reporting = false;
+    move |event| match event {
+        UploadEvent::Sent(p) => {
+            if !device_reporting {
+                let message = match p.stage {
+                    UploadStage::Preparing => "Preparing the device",
+                    UploadStage::Sending => "Sending config",
+                    UploadStage::AwaitingAck => "Waiting for the device to store the config",
+                };
+                job.progress(p.percent as u64, 100, Some(message.to_string()));
+            }
+            sent.emit(p);
+        }
+        UploadEvent::Progress(p) => {
+            device_reporting = true;
+            job.progress(p.percent as u64, 100, Some(p.line.clone()));
+            progress.emit(p);
+        }
+        UploadEvent::Line(line) => {
+            recent.push(&line);
+            log.push(line);
+        }
+    }
+}
+
+/// Record an upload attempt in the session log and build its history entry.
+/// Successful uploads of library signals (`filename`) are also noted in the signal index.
+fn record_upload(
+    app: &AppHandle,
+    session: &SessionLog,
+    connection: &SerialConnection,
+    signal_name: Option<String>,
+    filename: Option<String>,
+    result: &UploadResult,
+    note: Option<String>,
+) -> UploadRecord {
+    let port_name = connection.port_name().map(String::from);
+    let message = format!(
+        "Upload of {} {}",
+        signal_name.as_deref().unwrap_or("config"),
+        if result.success {
+            "succeeded"
+        } else {
+            "failed"
+        }
+    );
+    session.record(SessionEventKind::Upload, message.clone(), note.clone());
+
+    if result.success {
+        hooks::fire(
+            app,
+            HookEvent::UploadCompleted,
+            port_name.clone(),
+            message.clone(),
+        );
+    } else {
+        let reason = result.error_message.as_deref().unwrap_or("unknown error");
+        hooks::fire(
+            app,
+            HookEvent::UploadFailed,
+            port_name.clone(),
+            format!("{}: {}", message, reason),
+        );
+    }
+
+    if let Some(filename) = filename.filter(|_| result.success) {
+        if let Err(e) = crate::signal_index::record_upload(
+            app,
+            &filename,
+            port_name.clone(),
+            connection.identity(),
+        ) {
+            eprintln!("[LIBRARY] Failed to update signal index: {}", e);
+        }
+    }
+
+    let usb_serial = connection.identity().usb_serial.clone();
+    UploadRecord::new(signal_name, port_name, usb_serial, result, note)
+}
+
+/// Add an upload to the persistent history. A NAK or missing ACK takes the device's own
+/// output from the seconds before along, for the post-mortem.
+fn save_upload_record(app: &AppHandle, mut record: UploadRecord) {
+    if !record.success {
+        record.device_log = app.state::<RecentOutput>().last(FAILURE_LOG_WINDOW_MS);
+    }
+    if let Err(e) = crate::history::append_record(app, record) {
+        eprintln!("[HISTORY] Failed to record upload: {}", e);
+    }
+}
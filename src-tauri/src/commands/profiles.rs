@@ -0,0 +1,59 @@
+use crate::profiles::{self, ConnectionProfile, ProfileImportSummary};
+use crate::serial::SerialState;
+use tauri::{AppHandle, State};
+
+/// List saved connection profiles
+#[tauri::command]
+pub fn list_profiles(app: AppHandle) -> Result<Vec<ConnectionProfile>, String> {
+    profiles::load_profiles(&app).map_err(|e| e.to_string())
+}
+
+/// Create or replace a connection profile; when the current connection was opened with
+/// it, the new protocol parameters take effect from the next command on
+#[tauri::command]
+pub async fn save_profile(profile: ConnectionProfile, app: AppHandle, serial: State<'_, SerialState>) -> Result<(), String> {
+    let (name, protocol) = (profile.name.clone(), profile.protocol.clone());
+    profiles::save_profile(&app, profile).map_err(|e| e.to_string())?;
+    reload_live_profile(&serial, &name, protocol).await;
+    Ok(())
+}
+
+/// Delete a connection profile by name
+#[tauri::command]
+pub fn delete_profile(name: String, app: AppHandle) -> Result<(), String> {
+    profiles::delete_profile(&app, &name).map_err(|e| e.to_string())
+}
+
+/// Export all profiles to a JSON bundle, returning how many were written
+#[tauri::command]
+pub fn export_profiles(path: String, app: AppHandle) -> Result<usize, String> {
+    profiles::export_profiles(&app, std::path::Path::new(&path)).map_err(|e| e.to_string())
+}
+
+/// Import profiles from a JSON bundle, replacing same-name entries
+#[tauri::command]
+pub async fn import_profiles(path: String, app: AppHandle, serial: State<'_, SerialState>) -> Result<ProfileImportSummary, String> {
+    let summary = profiles::import_profiles(&app, std::path::Path::new(&path)).map_err(|e| e.to_string())?;
+    for name in &summary.updated {
+        if let Ok(profile) = profiles::get_profile(&app, name) {
+            reload_live_profile(&serial, name, profile.protocol).await;
+        }
+    }
+    Ok(summary)
+}
+
+/// Swap the protocol parameters of a live connection opened with profile `name`.
+/// The serial worker runs commands one at a time, so the switch always
+/// lands between two commands and the running signal is left alone.
+async fn reload_live_profile(serial: &SerialState, name: &str, protocol: profiles::ProtocolProfile) {
+    let name = name.to_string();
+    let reloaded = serial.with(move |connection| {
+        if connection.is_connected() && connection.profile_name() == Some(name.as_str()) {
+            connection.set_protocol(protocol);
+            eprintln!("[SERIAL] Applied updated profile '{}' to the live connection", name);
+        }
+    });
+    if let Err(e) = reloaded.await {
+        eprintln!("[SERIAL] Failed to apply the updated profile: {}", e);
+    }
+}
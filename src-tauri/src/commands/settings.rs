@@ -0,0 +1,65 @@
+use crate::critical::CriticalSection;
+use crate::jobs::JobManager;
+use crate::serial::SerialState;
+use crate::settings::{self, AppSettings, SettingsState};
+use crate::storage::{self, MigrationReport, StorageInfo};
+use tauri::{AppHandle, Manager, State};
+
+#[tauri::command]
+pub fn get_settings(settings: State<SettingsState>) -> Result<AppSettings, String> {
+    Ok(settings.get())
+}
+
+/// Settings revision, bumped by every change; see `settings://changed`
+#[tauri::command]
+pub fn get_settings_revision(settings: State<SettingsState>) -> Result<u64, String> {
+    Ok(settings.revision())
+}
+
+/// Replace and persist the app settings; returns the new revision.
+/// With `expected_revision`, the update is refused if someone else changed the settings meanwhile.
+#[tauri::command]
+pub async fn update_settings(new_settings: AppSettings, expected_revision: Option<u64>, app: AppHandle, settings: State<'_, SettingsState>, serial: State<'_, SerialState>) -> Result<u64, String> {
+    new_settings.command_aliases.validate()?;
+    if let Some(expected) = expected_revision {
+        let current = settings.revision();
+        if current != expected {
+            return Err(format!("Settings were changed elsewhere (revision {} is now {}); reload and try again", expected, current));
+        }
+    }
+    let aliases = new_settings.command_aliases.clone();
+    serial.with(move |connection| connection.set_command_aliases(aliases)).await?;
+    let change = settings::apply(&app, new_settings)?;
+    Ok(change.map(|c| c.revision).unwrap_or_else(|| settings.revision()))
+}
+
+/// Where settings, logs and the signal library are stored for this run
+#[tauri::command]
+pub fn get_storage_info(app: AppHandle) -> Result<StorageInfo, String> {
+    storage::info(&app)
+}
+
+/// Move settings, logs, the library and histories to `new_location` (a new or empty
+/// folder) and keep the data there from now on
+#[tauri::command]
+pub async fn migrate_app_data(new_location: String, app: AppHandle) -> Result<MigrationReport, String> {
+    if let Some(reason) = app.state::<CriticalSection>().active() {
+        return Err(format!("Wait for the {} to finish before moving the data", reason));
+    }
+    if !app.state::<JobManager>().active().is_empty() {
+        return Err("Wait for running uploads and transfers to finish before moving the data".into());
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let report = storage::migrate(&app, std::path::Path::new(&new_location))?;
+
+        let mut updated = app.state::<SettingsState>().get();
+        updated.storage.custom_dir = Some(report.to.clone());
+        settings::apply(&app, updated.clone())?;
+        // The copy read at startup, before the data folder is known, points to the new one
+        settings::save_settings_in(&storage::bootstrap_dir(&app)?, &updated)?;
+        Ok(report)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,47 @@
+use crate::scheduler;
+use crate::serial::{PortInfo, SerialParams};
+use crate::settings::SettingsState;
+use crate::setup::{self, HandshakeResult, SetupRecord, SetupState};
+use crate::storage;
+use tauri::{AppHandle, State};
+
+/// Whether the first-run wizard has been completed, and what it would find
+#[tauri::command]
+pub fn get_setup_state(app: AppHandle) -> Result<SetupState, String> {
+    Ok(setup::state(&storage::data_dir(&app)?))
+}
+
+/// Ports worth offering in the wizard, likely ESP32 bridges first
+#[tauri::command]
+pub fn setup_candidate_ports() -> Result<Vec<PortInfo>, String> {
+    setup::candidate_ports().map_err(|e| e.to_string())
+}
+
+/// Check our firmware answers on `port` without making it the active connection
+#[tauri::command]
+pub async fn test_handshake(port: String, params: Option<SerialParams>) -> Result<HandshakeResult, String> {
+    let params = params.unwrap_or_default();
+    params.validate()?;
+    scheduler::blocking(move || setup::test_handshake(&port, &params))
+        .await
+        .ok_or_else(|| "The handshake test panicked".to_string())
+}
+
+/// Write the current (default) settings to disk if there is no settings file yet;
+/// returns whether one was created
+#[tauri::command]
+pub fn create_default_settings(app: AppHandle, settings: State<SettingsState>) -> Result<bool, String> {
+    setup::create_settings(&storage::data_dir(&app)?, &settings.get())
+}
+
+/// Add the bundled trigger-wheel examples to the signal library; returns the names added
+#[tauri::command]
+pub fn install_preset_signals(app: AppHandle) -> Result<Vec<String>, String> {
+    setup::install_presets(&storage::data_dir(&app)?)
+}
+
+/// Mark the first-run setup as done
+#[tauri::command]
+pub fn complete_setup(port: Option<String>, app: AppHandle) -> Result<SetupRecord, String> {
+    setup::complete(&storage::data_dir(&app)?, port)
+}
@@ -0,0 +1,63 @@
+use crate::concurrency::{ConcurrencyGate, Operation};
+use crate::hooks::HookState;
+use crate::jobs::{JobKind, JobManager};
+use crate::serial::SerialState;
+use crate::session::SessionLog;
+use crate::soak::{self, SoakState, SoakSummary};
+use crate::status_history::StatusHistory;
+use crate::step_test;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+/// Keep the signal running unattended, snapshotting status and link health every `interval_secs`
+#[tauri::command]
+pub async fn start_soak(interval_secs: u64, app: AppHandle) -> Result<(), String> {
+    if interval_secs == 0 {
+        return Err("Soak interval must be at least 1 second".into());
+    }
+    soak::start(&app, Duration::from_secs(interval_secs)).await
+}
+
+/// End the soak run without stopping the signal
+#[tauri::command]
+pub fn stop_soak(app: AppHandle) -> Result<(), String> {
+    soak::stop(&app)
+}
+
+/// Progress and alerts of the current or most recent soak run
+#[tauri::command]
+pub fn get_soak_status(soak_state: State<SoakState>) -> Result<Option<SoakSummary>, String> {
+    Ok(soak_state.summary())
+}
+
+/// Step through `rpm_list`, holding each setpoint for `dwell_secs` while recording
+/// telemetry. Runs as a job; the `StepTestSummary` arrives with the finished job.
+#[tauri::command]
+pub async fn run_step_test(rpm_list: Vec<u16>, dwell_secs: u64, app: AppHandle, jobs: State<'_, JobManager>, state: State<'_, SerialState>) -> Result<u64, String> {
+    if rpm_list.is_empty() {
+        return Err("Step test needs at least one RPM setpoint".into());
+    }
+    if dwell_secs == 0 {
+        return Err("Dwell must be at least 1 second".into());
+    }
+    if !state.with(|connection| connection.is_connected()).await? {
+        return Err("Not connected".into());
+    }
+
+    let label = format!("Step test ({} setpoints)", rpm_list.len());
+    let state = state.inner().clone();
+    Ok(jobs.start(&app.clone(), JobKind::StepTest, label, move |job| {
+        let _step_test = app
+            .state::<ConcurrencyGate>()
+            .begin_blocking(Operation::StepTest, || job.is_cancelled())?;
+        app.state::<HookState>().set_expected_running(true);
+        step_test::run(
+            job,
+            &state,
+            &app.state::<StatusHistory>(),
+            &app.state::<SessionLog>(),
+            &rpm_list,
+            Duration::from_secs(dwell_secs),
+        )
+    }))
+}
@@ -0,0 +1,84 @@
+use crate::events::DEVICE_STATUS_EVENT;
+use crate::scheduler;
+use crate::serial::{DeviceStatus, SerialParams, SerialState};
+use crate::serial_reader;
+use crate::session::{SessionEventKind, SessionLog};
+use crate::standby::{DeviceSlot, StandbyPool};
+use crate::supervisor::ConnectionSupervisor;
+use crate::tray::{self, BenchState};
+use tauri::{AppHandle, Emitter, State};
+
+/// The active connection (if any) followed by the standby ones
+#[tauri::command]
+pub async fn list_devices(state: State<'_, SerialState>, pool: State<'_, StandbyPool>) -> Result<Vec<DeviceSlot>, String> {
+    let active = state
+        .with(|connection| {
+            connection.port_name().map(|port| DeviceSlot {
+                id: port.to_string(),
+                identity: connection.identity().clone(),
+                active: true,
+                healthy: true,
+                last_heartbeat: None,
+            })
+        })
+        .await?;
+    Ok(active.into_iter().chain(pool.slots()).collect())
+}
+
+/// Open another bench box and keep it on standby (heartbeat only) next to the active one
+#[tauri::command]
+pub async fn open_standby(port: String, params: Option<SerialParams>, pool: State<'_, StandbyPool>, session: State<'_, SessionLog>) -> Result<DeviceSlot, String> {
+    let params = params.unwrap_or_default();
+    params.validate()?;
+    let task_pool = pool.inner().clone();
+    let task_port = port.clone();
+    let slot = scheduler::blocking(move || task_pool.open(&task_port, &params))
+        .await
+        .ok_or("Opening the standby port panicked")??;
+    session.record(SessionEventKind::Connected, format!("Opened {} on standby", port), None);
+    Ok(slot)
+}
+
+/// Close a standby connection
+#[tauri::command]
+pub async fn close_standby(id: String, pool: State<'_, StandbyPool>, session: State<'_, SessionLog>) -> Result<(), String> {
+    let task_pool = pool.inner().clone();
+    let task_id = id.clone();
+    scheduler::blocking(move || task_pool.close(&task_id))
+        .await
+        .ok_or("Closing the standby port panicked")??;
+    session.record(SessionEventKind::Disconnected, format!("Closed standby {}", id), None);
+    Ok(())
+}
+
+/// Make a standby device the active one without reconnecting; the previously active
+/// device goes on standby in its place. Work bound to the old connection (status
+/// reader, soak run) stops as on a disconnect.
+#[tauri::command]
+pub async fn switch_active_device(id: String, app: AppHandle, state: State<'_, SerialState>, pool: State<'_, StandbyPool>, session: State<'_, SessionLog>, supervisor: State<'_, ConnectionSupervisor>) -> Result<DeviceStatus, String> {
+    if !pool.contains(&id) {
+        return Err(format!("{} is not on standby", id));
+    }
+    supervisor.shutdown().await;
+    let task_pool = pool.inner().clone();
+    let task_id = id.clone();
+    let switched = state
+        .with(move |connection| {
+            let mut next = task_pool
+                .take(&task_id)
+                .ok_or_else(|| format!("{} is not on standby", task_id))?;
+            connection.swap_link(&mut next);
+            if next.is_connected() {
+                task_pool.put(next);
+            }
+            connection.get_status().map_err(|e| e.to_string())
+        })
+        .await?;
+    // Whatever happened, the active port (old or new) needs its reader back
+    serial_reader::start(&app);
+    let status = switched?;
+    session.record(SessionEventKind::Connected, format!("Switched to {}", id), None);
+    tray::show(&app, BenchState::of(&status));
+    let _ = app.emit(DEVICE_STATUS_EVENT, &status);
+    Ok(status)
+}
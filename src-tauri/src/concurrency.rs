@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+// How often a job waiting for its turn checks whether it was cancelled
+const WAIT_SLICE_MS: u64 = 100;
+const UNLIMITED: usize = usize::MAX;
+
+/// Kinds of device work the concurrency matrix arbitrates between
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// A status query from the UI's refresh
+    StatusPoll,
+    /// The background reader picking up unsolicited output
+    TerminalRead,
+    /// Run/stop/RPM and custom or extension commands
+    Command,
+    NvsWrite,
+    /// DTR/RTS reset, normal or into download mode
+    Reset,
+    ConfigUpload,
+    /// Upload to or download from the device filesystem
+    FileTransfer,
+    Firmware,
+    StepTest,
+    SoakSnapshot,
+}
+
+const EVERYTHING: &[Operation] = &[
+    Operation::StatusPoll,
+    Operation::TerminalRead,
+    Operation::Command,
+    Operation::NvsWrite,
+    Operation::Reset,
+    Operation::ConfigUpload,
+    Operation::FileTransfer,
+    Operation::Firmware,
+    Operation::StepTest,
+    Operation::SoakSnapshot,
+];
+
+/// One row of the matrix: how many of `operation` may run at once and what it may
+/// not overlap with. Exclusion is symmetric, so a pair only needs listing once.
+struct Rule {
+    operation: Operation,
+    limit: usize,
+    excludes: &'static [Operation],
+}
+
+/// Which operations may overlap. Anything not excluded here may interleave at the
+/// request level on the serial worker (e.g. a status poll between terminal reads).
+const MATRIX: &[Rule] = &[
+    Rule { operation: Operation::StatusPoll, limit: 1, excludes: &[] },
+    Rule { operation: Operation::TerminalRead, limit: 1, excludes: &[] },
+    Rule { operation: Operation::Command, limit: UNLIMITED, excludes: &[] },
+    Rule { operation: Operation::NvsWrite, limit: 1, excludes: &[Operation::StatusPoll, Operation::SoakSnapshot] },
+    // These hold or restart the device for their whole duration
+    Rule { operation: Operation::Reset, limit: 1, excludes: EVERYTHING },
+    Rule { operation: Operation::ConfigUpload, limit: 1, excludes: EVERYTHING },
+    Rule { operation: Operation::FileTransfer, limit: 1, excludes: EVERYTHING },
+    Rule { operation: Operation::Firmware, limit: 1, excludes: EVERYTHING },
+    // A step test owns the RPM; user commands and soak restarts would skew its readings
+    Rule { operation: Operation::StepTest, limit: 1, excludes: &[Operation::Command, Operation::SoakSnapshot] },
+    Rule { operation: Operation::SoakSnapshot, limit: 1, excludes: &[] },
+];
+
+impl Operation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Operation::StatusPoll => "status poll",
+            Operation::TerminalRead => "terminal read",
+            Operation::Command => "device command",
+            Operation::NvsWrite => "NVS write",
+            Operation::Reset => "device reset",
+            Operation::ConfigUpload => "config upload",
+            Operation::FileTransfer => "file transfer",
+            Operation::Firmware => "firmware update",
+            Operation::StepTest => "step test",
+            Operation::SoakSnapshot => "soak snapshot",
+        }
+    }
+
+    fn rule(&self) -> &'static Rule {
+        MATRIX
+            .iter()
+            .find(|r| r.operation == *self)
+            .expect("every operation has a row in the matrix")
+    }
+
+    fn conflicts_with(&self, other: Operation) -> bool {
+        self.rule().excludes.contains(&other) || other.rule().excludes.contains(self)
+    }
+}
+
+/// Counts running operations and admits new ones according to `MATRIX`.
+///
+/// Interactive commands and background polls ask with `try_begin` and give up (or
+/// skip a tick) when refused; jobs queue behind conflicting work with `begin_blocking`.
+#[derive(Clone, Default)]
+pub struct ConcurrencyGate(Arc<(Mutex<HashMap<Operation, usize>>, Condvar)>);
+
+/// Keeps an operation counted as running until dropped
+pub struct Admission {
+    gate: ConcurrencyGate,
+    operation: Operation,
+}
+
+impl ConcurrencyGate {
+    /// Why `operation` can't start right now, if it can't
+    fn refusal(running: &HashMap<Operation, usize>, operation: Operation) -> Option<String> {
+        let count = running.get(&operation).copied().unwrap_or(0);
+        if count >= operation.rule().limit {
+            return Some(format!("A {} is already in progress", operation.label()));
+        }
+        running
+            .iter()
+            .find(|(other, count)| **count > 0 && operation.conflicts_with(**other))
+            .map(|(other, _)| format!("Device busy: {} can't run during the {}", operation.label(), other.label()))
+    }
+
+    fn admit(&self, running: &mut HashMap<Operation, usize>, operation: Operation) -> Admission {
+        *running.entry(operation).or_insert(0) += 1;
+        Admission {
+            gate: self.clone(),
+            operation,
+        }
+    }
+
+    /// Start `operation` now, or say what it conflicts with
+    pub fn try_begin(&self, operation: Operation) -> Result<Admission, String> {
+        let (lock, _) = &*self.0;
+        let mut running = lock.lock().map_err(|_| "Concurrency gate poisoned".to_string())?;
+        match Self::refusal(&running, operation) {
+            Some(reason) => Err(reason),
+            None => Ok(self.admit(&mut running, operation)),
+        }
+    }
+
+    /// Wait until `operation` may start; gives up once `cancelled` returns true.
+    /// Blocking; for jobs and other work off the async runtime.
+    pub fn begin_blocking(&self, operation: Operation, cancelled: impl Fn() -> bool) -> Result<Admission, String> {
+        let (lock, turn) = &*self.0;
+        let mut running = lock.lock().map_err(|_| "Concurrency gate poisoned".to_string())?;
+        loop {
+            if Self::refusal(&running, operation).is_none() {
+                return Ok(self.admit(&mut running, operation));
+            }
+            if cancelled() {
+                return Err("Cancelled".into());
+            }
+            running = turn
+                .wait_timeout(running, Duration::from_millis(WAIT_SLICE_MS))
+                .map_err(|_| "Concurrency gate poisoned".to_string())?
+                .0;
+        }
+    }
+}
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        let (lock, turn) = &*self.gate.0;
+        if let Ok(mut running) = lock.lock() {
+            if let Some(count) = running.get_mut(&self.operation) {
+                *count = count.saturating_sub(1);
+            }
+        }
+        turn.notify_all();
+    }
+}
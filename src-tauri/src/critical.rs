@@ -0,0 +1,86 @@
+use crate::events::CRITICAL_SECTION_EVENT;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CriticalSectionChange {
+    pub active: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Default)]
+struct Sections {
+    depth: usize,
+    reason: Option<String>,
+}
+
+/// Tracks device operations that must not be interleaved with background traffic.
+///
+/// Status polling, soak snapshots and any other periodic work check `active()` and
+/// stand down while an upload, flash or NVS write is in progress.
+#[derive(Clone, Default)]
+pub struct CriticalSection(Arc<Mutex<Sections>>);
+
+impl CriticalSection {
+    /// Reason of the outermost critical section in progress, if any
+    pub fn active(&self) -> Option<String> {
+        let sections = self.0.lock().ok()?;
+        (sections.depth > 0).then(|| sections.reason.clone().unwrap_or_default())
+    }
+}
+
+/// Keeps background device traffic suspended until dropped
+pub struct CriticalGuard {
+    app: AppHandle,
+}
+
+/// Enter a critical section for the duration of the returned guard (sections nest)
+pub fn enter(app: &AppHandle, reason: &str) -> CriticalGuard {
+    let section = app.state::<CriticalSection>();
+    let started = match section.0.lock() {
+        Ok(mut sections) => {
+            sections.depth += 1;
+            if sections.depth == 1 {
+                sections.reason = Some(reason.to_string());
+            }
+            sections.depth == 1
+        }
+        Err(_) => false,
+    };
+    if started {
+        let _ = app.emit(
+            CRITICAL_SECTION_EVENT,
+            CriticalSectionChange {
+                active: true,
+                reason: Some(reason.to_string()),
+            },
+        );
+    }
+    CriticalGuard { app: app.clone() }
+}
+
+impl Drop for CriticalGuard {
+    fn drop(&mut self) {
+        let section = self.app.state::<CriticalSection>();
+        let ended = match section.0.lock() {
+            Ok(mut sections) => {
+                sections.depth = sections.depth.saturating_sub(1);
+                if sections.depth == 0 {
+                    sections.reason = None;
+                }
+                sections.depth == 0
+            }
+            Err(_) => false,
+        };
+        if ended {
+            let _ = self.app.emit(
+                CRITICAL_SECTION_EVENT,
+                CriticalSectionChange {
+                    active: false,
+                    reason: None,
+                },
+            );
+        }
+    }
+}
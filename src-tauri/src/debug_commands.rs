@@ -0,0 +1,139 @@
+use crate::device_command::{CommandOutcome, ExtensionCall};
+use crate::extensions::{ExtensionCommand, ExtensionParam, ParamKind};
+use crate::serial::SerialConnection;
+use crate::session::now_millis;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+// Every debug command sent, one JSON object per line
+const AUDIT_FILE: &str = "debug_audit.jsonl";
+
+/// Firmware diagnostic command that can disturb the device (memory peeks, task dumps,
+/// forced crashes). Declared in the protocol profile like an extension command and only
+/// sent in developer mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugCommand {
+    #[serde(flatten)]
+    pub command: ExtensionCommand,
+    /// The device restarts or stops answering afterwards; sending needs a confirmation
+    #[serde(default)]
+    pub disruptive: bool,
+}
+
+fn debug_command(name: &str, description: &str, send: &str, expect: &[&str], params: Vec<ExtensionParam>) -> DebugCommand {
+    DebugCommand {
+        command: ExtensionCommand {
+            name: name.to_string(),
+            description: description.to_string(),
+            send: send.to_string(),
+            expect: expect.iter().map(|s| s.to_string()).collect(),
+            reject: Vec::new(),
+            timeout_ms: 2000,
+            params,
+            source: String::new(),
+        },
+        disruptive: false,
+    }
+}
+
+fn param(name: &str, kind: ParamKind, min: Option<i64>, max: Option<i64>) -> ExtensionParam {
+    ExtensionParam {
+        name: name.to_string(),
+        kind,
+        min,
+        max,
+        choices: Vec::new(),
+        default: None,
+    }
+}
+
+/// Diagnostics of the stock firmware's debug build
+pub fn defaults() -> Vec<DebugCommand> {
+    vec![
+        debug_command(
+            "memory_peek",
+            "Hex dump of `length` bytes at `address` (e.g. 0x3FFB0000)",
+            "<PEEK {address} {length}>",
+            &["PEEK:"],
+            vec![
+                param("address", ParamKind::Text, None, None),
+                param("length", ParamKind::Integer, Some(1), Some(256)),
+            ],
+        ),
+        debug_command(
+            "task_list",
+            "FreeRTOS tasks with their state, priority and free stack",
+            "<TASKS>",
+            &["TASKS_END"],
+            Vec::new(),
+        ),
+        DebugCommand {
+            disruptive: true,
+            ..debug_command("forced_crash", "Abort the firmware to test the crash handler and core dump", "<CRASH>", &[], Vec::new())
+        },
+    ]
+}
+
+/// One line of the debug audit trail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugAuditEntry {
+    pub timestamp: u64,
+    pub command: String,
+    /// Line sent, parameters filled in
+    pub line: String,
+    pub port_name: Option<String>,
+    pub device_id: Option<String>,
+    pub accepted: bool,
+    /// Outcome summary, or why the command couldn't be sent
+    pub result: String,
+}
+
+fn audit_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join(AUDIT_FILE))
+}
+
+/// Append a sent debug command and its outcome to the audit trail
+pub fn audit(app: &AppHandle, connection: &SerialConnection, call: &ExtensionCall, result: &Result<CommandOutcome, String>) {
+    let entry = DebugAuditEntry {
+        timestamp: now_millis(),
+        command: call.name.clone(),
+        line: call.line.clone(),
+        port_name: connection.port_name().map(String::from),
+        device_id: connection.identity().device_id.clone(),
+        accepted: result.as_ref().is_ok_and(|o| o.is_accepted()),
+        result: match result {
+            Ok(outcome) => outcome.describe(),
+            Err(e) => e.clone(),
+        },
+    };
+    let written = serde_json::to_string(&entry).map_err(|e| e.to_string()).and_then(|json| {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(audit_path(app)?)
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", json).map_err(|e| e.to_string())
+    });
+    if let Err(e) = written {
+        eprintln!("[DEBUG] Failed to write the audit trail: {}", e);
+    }
+}
+
+/// The last `limit` audit entries, oldest first
+pub fn recent(app: &AppHandle, limit: usize) -> Result<Vec<DebugAuditEntry>, String> {
+    let path = audit_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let entries: Vec<DebugAuditEntry> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = entries.len().saturating_sub(limit);
+    Ok(entries.into_iter().skip(skip).collect())
+}
@@ -0,0 +1,79 @@
+use crate::serial::{SerialConnection, SerialError};
+use crate::session::now_millis;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+pub const BACKUP_DIR: &str = "device_backups";
+// Oldest backups are deleted beyond this many
+const MAX_BACKUPS: usize = 20;
+
+/// Config read back from a device before something overwrote or erased it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceBackup {
+    pub taken_at: u64,
+    /// The operation the backup was taken for, e.g. "Reset to defaults"
+    pub reason: String,
+    pub port_name: Option<String>,
+    pub usb_serial: Option<String>,
+    pub device_id: Option<String>,
+    /// Config exactly as the firmware printed it, ready to upload again
+    pub config: String,
+}
+
+fn backup_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = storage::data_dir(app)?.join(BACKUP_DIR);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// Backup files, oldest first (names are timestamps)
+fn backup_files(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<PathBuf> = fs::read_dir(backup_dir(app)?)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Read the device's config back and stash it in app data before a destructive
+/// operation. `Ok(None)` when the protocol profile has no readback command.
+pub fn take(app: &AppHandle, connection: &mut SerialConnection, reason: &str) -> Result<Option<DeviceBackup>, String> {
+    let config = match connection.read_config() {
+        Ok(config) => config,
+        Err(SerialError::Unsupported(_)) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+    let identity = connection.identity();
+    let backup = DeviceBackup {
+        taken_at: now_millis(),
+        reason: reason.to_string(),
+        port_name: connection.port_name().map(String::from),
+        usb_serial: identity.usb_serial.clone(),
+        device_id: identity.device_id.clone(),
+        config,
+    };
+
+    let json = serde_json::to_string_pretty(&backup).map_err(|e| e.to_string())?;
+    let path = backup_dir(app)?.join(format!("{:013}.json", backup.taken_at));
+    fs::write(&path, json).map_err(|e| e.to_string())?;
+
+    let files = backup_files(app)?;
+    for old in files.iter().take(files.len().saturating_sub(MAX_BACKUPS)) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(Some(backup))
+}
+
+/// Most recent backup, if any was taken
+pub fn latest(app: &AppHandle) -> Result<Option<DeviceBackup>, String> {
+    let Some(path) = backup_files(app)?.pop() else {
+        return Ok(None);
+    };
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map(Some).map_err(|e| e.to_string())
+}
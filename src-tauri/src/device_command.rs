@@ -0,0 +1,247 @@
+use crate::settings::CommandAliases;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// Status query; reserved, so it can't be remapped in settings
+const STATUS_COMMAND: char = '?';
+// Reply lines starting with these mean the firmware refused a command
+const FAILURE_PREFIXES: &[&str] = &["NAK:", "ERR:"];
+// How long run/stop/RPM changes get to show up in the reply
+const QUICK_REPLY_MS: u64 = 500;
+// NVS writes and resets touch flash, which takes a while on the ESP32
+const FLASH_REPLY_MS: u64 = 2000;
+const CUSTOM_REPLY_MS: u64 = 1000;
+
+/// What the reply to a command has to look like
+#[derive(Debug, Clone)]
+pub struct Expectation {
+    /// A reply line containing any of these confirms the command; empty if the
+    /// firmware has no confirmation for it
+    pub success: Vec<String>,
+    /// A reply line starting with any of these means the command was refused
+    pub failure: Vec<String>,
+    /// How long to wait for a confirmation
+    pub within: Duration,
+}
+
+impl Expectation {
+    fn new(success: &[&str], within_ms: u64) -> Self {
+        Expectation {
+            success: success.iter().map(|s| s.to_string()).collect(),
+            failure: FAILURE_PREFIXES.iter().map(|s| s.to_string()).collect(),
+            within: Duration::from_millis(within_ms),
+        }
+    }
+
+    /// Verdict on one complete reply line, if it decides anything
+    pub fn judge(&self, line: &str) -> Option<OutcomeStatus> {
+        let line = line.trim();
+        if self.failure.iter().any(|p| line.starts_with(p.as_str())) {
+            Some(OutcomeStatus::Rejected)
+        } else if self.success.iter().any(|p| line.contains(p.as_str())) {
+            Some(OutcomeStatus::Confirmed)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_checked(&self) -> bool {
+        !self.success.is_empty()
+    }
+}
+
+/// How a command went, as far as the device's reply tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeStatus {
+    /// The expected reply arrived in time
+    Confirmed,
+    /// The firmware answered with a failure line
+    Rejected,
+    /// Nothing conclusive arrived before the deadline
+    NoConfirmation,
+    /// The command has no expected reply (custom commands, extensions without one)
+    Unchecked,
+}
+
+/// Result of a device command, verified against its expectation
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandOutcome {
+    pub command: DeviceCommand,
+    pub status: OutcomeStatus,
+    /// The reply line that decided the status
+    pub matched: Option<String>,
+    pub response: String,
+    pub elapsed_ms: u64,
+}
+
+impl CommandOutcome {
+    /// Judge a complete reply against the command's expectation
+    pub fn evaluate(command: &DeviceCommand, response: String, elapsed: Duration) -> Self {
+        let expectation = command.expectation();
+        let verdicts: Vec<(OutcomeStatus, &str)> = response
+            .lines()
+            .filter_map(|l| expectation.judge(l).map(|status| (status, l.trim())))
+            .collect();
+        // A refusal anywhere in the reply wins over a confirmation-looking line
+        let decisive = verdicts
+            .iter()
+            .find(|(status, _)| *status == OutcomeStatus::Rejected)
+            .or(verdicts.first());
+        let (status, matched) = match decisive {
+            Some((status, line)) => (*status, Some(line.to_string())),
+            None if expectation.is_checked() => (OutcomeStatus::NoConfirmation, None),
+            None => (OutcomeStatus::Unchecked, None),
+        };
+        CommandOutcome {
+            command: command.clone(),
+            status,
+            matched,
+            response,
+            elapsed_ms: elapsed.as_millis() as u64,
+        }
+    }
+
+    /// The device didn't refuse the command (confirmed or nothing to confirm)
+    pub fn is_accepted(&self) -> bool {
+        matches!(self.status, OutcomeStatus::Confirmed | OutcomeStatus::Unchecked)
+    }
+
+    /// One-line summary for logs
+    pub fn describe(&self) -> String {
+        match (&self.status, &self.matched) {
+            (OutcomeStatus::Confirmed, _) => format!("{} confirmed in {} ms", self.command.label(), self.elapsed_ms),
+            (OutcomeStatus::Rejected, Some(line)) => format!("{} refused: {}", self.command.label(), line),
+            (OutcomeStatus::Rejected, None) => format!("{} refused", self.command.label()),
+            (OutcomeStatus::NoConfirmation, _) => format!("{} not confirmed within {} ms", self.command.label(), self.elapsed_ms),
+            (OutcomeStatus::Unchecked, _) => self.command.label(),
+        }
+    }
+}
+
+/// Everything the app sends to the firmware as a one-shot command.
+///
+/// Commands are turned into bytes in one place (`encode`), using the command
+/// characters from settings, so callers never deal in raw characters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "text", rename_all = "snake_case")]
+pub enum DeviceCommand {
+    Run,
+    Stop,
+    RpmUp,
+    RpmDown,
+    SaveNvs,
+    ResetDefaults,
+    Status,
+    /// A raw line for firmware commands the app has no button for
+    Custom(String),
+    /// A command declared in an extension descriptor (see `extensions`)
+    Extension(ExtensionCall),
+    /// A firmware diagnostic from the protocol profile, sent in developer mode only
+    /// (see `debug_commands`)
+    Debug(ExtensionCall),
+}
+
+/// An extension command with its parameters filled in, ready to send
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionCall {
+    pub name: String,
+    pub line: String,
+    /// Reply fragments confirming the command; empty leaves it unchecked
+    pub expect: Vec<String>,
+    /// Reply prefixes meaning it was refused; empty uses the usual `NAK:`/`ERR:`
+    pub reject: Vec<String>,
+    pub timeout_ms: u64,
+}
+
+impl DeviceCommand {
+    /// Bytes to write for this command under the given command aliases.
+    /// Single-character commands go out bare; custom commands as a full line.
+    pub fn encode(&self, aliases: &CommandAliases) -> Vec<u8> {
+        let c = match self {
+            DeviceCommand::Run => aliases.run,
+            DeviceCommand::Stop => aliases.stop,
+            DeviceCommand::RpmUp => aliases.increase_rpm,
+            DeviceCommand::RpmDown => aliases.decrease_rpm,
+            DeviceCommand::SaveNvs => aliases.save_nvs,
+            DeviceCommand::ResetDefaults => aliases.reset_defaults,
+            DeviceCommand::Status => STATUS_COMMAND,
+            DeviceCommand::Custom(text) => return format!("{}\n", text.trim_end()).into_bytes(),
+            DeviceCommand::Extension(call) | DeviceCommand::Debug(call) => {
+                return format!("{}\n", call.line.trim_end()).into_bytes()
+            }
+        };
+        c.to_string().into_bytes()
+    }
+
+    /// Reply the firmware gives when it carried the command out
+    pub fn expectation(&self) -> Expectation {
+        match self {
+            DeviceCommand::Run => Expectation::new(&["STATE:RUN"], QUICK_REPLY_MS),
+            DeviceCommand::Stop => Expectation::new(&["STATE:STOP"], QUICK_REPLY_MS),
+            DeviceCommand::RpmUp | DeviceCommand::RpmDown => Expectation::new(&["RPM:"], QUICK_REPLY_MS),
+            DeviceCommand::SaveNvs => Expectation::new(&["NVS:OK", "ACK"], FLASH_REPLY_MS),
+            DeviceCommand::ResetDefaults => Expectation::new(&["DEFAULTS:OK", "ACK"], FLASH_REPLY_MS),
+            DeviceCommand::Status => Expectation::new(&["RPM", "STATE:"], QUICK_REPLY_MS),
+            DeviceCommand::Custom(_) => Expectation::new(&[], CUSTOM_REPLY_MS),
+            DeviceCommand::Extension(call) | DeviceCommand::Debug(call) => {
+                let mut expectation = Expectation::new(&[], call.timeout_ms);
+                expectation.success = call.expect.clone();
+                if !call.reject.is_empty() {
+                    expectation.failure = call.reject.clone();
+                }
+                expectation
+            }
+        }
+    }
+
+    /// Key for this command's timing statistics; custom lines are lumped together
+    pub fn kind(&self) -> String {
+        match self {
+            DeviceCommand::Run => "run".into(),
+            DeviceCommand::Stop => "stop".into(),
+            DeviceCommand::RpmUp => "rpm_up".into(),
+            DeviceCommand::RpmDown => "rpm_down".into(),
+            DeviceCommand::SaveNvs => "save_nvs".into(),
+            DeviceCommand::ResetDefaults => "reset_defaults".into(),
+            DeviceCommand::Status => "status".into(),
+            DeviceCommand::Custom(_) => "custom".into(),
+            DeviceCommand::Extension(call) => format!("extension:{}", call.name),
+            DeviceCommand::Debug(call) => format!("debug:{}", call.name),
+        }
+    }
+
+    /// Name used in the session log and audit trail
+    pub fn label(&self) -> String {
+        match self {
+            DeviceCommand::Run => "Run signal".into(),
+            DeviceCommand::Stop => "Stop signal".into(),
+            DeviceCommand::RpmUp => "Increase RPM".into(),
+            DeviceCommand::RpmDown => "Decrease RPM".into(),
+            DeviceCommand::SaveNvs => "Save to NVS".into(),
+            DeviceCommand::ResetDefaults => "Reset to defaults".into(),
+            DeviceCommand::Status => "Status query".into(),
+            DeviceCommand::Custom(text) => format!("Custom command \"{}\"", text.trim()),
+            DeviceCommand::Extension(call) => format!("{} (extension)", call.name),
+            DeviceCommand::Debug(call) => format!("{} (debug)", call.name),
+        }
+    }
+
+    /// Check a custom, extension or debug command before sending it: one non-empty line
+    /// of printable ASCII
+    pub fn validate(&self) -> Result<(), String> {
+        let (kind, text) = match self {
+            DeviceCommand::Custom(text) => ("Custom command".to_string(), text.trim()),
+            DeviceCommand::Extension(call) => (format!("Extension command '{}'", call.name), call.line.trim()),
+            DeviceCommand::Debug(call) => (format!("Debug command '{}'", call.name), call.line.trim()),
+            _ => return Ok(()),
+        };
+        if text.is_empty() {
+            return Err(format!("{} is empty", kind));
+        }
+        if !text.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+            return Err(format!("{} must be a single line of printable ASCII", kind));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use crate::serial::{check_nak, SerialConnection, SerialError};
+use crate::storage;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::AppHandle;
+
+// Folder in app data that downloaded device files go to
+pub const DOWNLOADS_DIR: &str = "device_files";
+// Per-request timeout for filesystem commands
+const FS_TIMEOUT_MS: u64 = 3000;
+// Bytes requested per <READ> round trip (base64 line stays well under 1KB)
+const READ_CHUNK_SIZE: usize = 512;
+// Closes the base64 body of a <PUT> frame
+const PUT_END_MARKER: &str = "\n<END>\n";
+
+/// File stored on the device's LittleFS/SPIFFS partition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceFile {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Named signal stored on the device (NVS slot or SPIFFS file); `active` is the one
+/// the firmware generates on `<RUN>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSignal {
+    pub name: String,
+    pub size: u64,
+    pub active: bool,
+    /// CRC32 of the stored config JSON, for firmware that reports it
+    pub crc32: Option<u32>,
+}
+
+/// Progress of a chunked transfer to or from the device filesystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferProgress {
+    pub name: String,
+    pub bytes_done: u64,
+    pub total: u64,
+}
+
+fn timeout() -> Duration {
+    Duration::from_millis(FS_TIMEOUT_MS)
+}
+
+/// Remote names are sent inside space-separated commands, so keep them simple
+fn validate_remote_name(name: &str) -> Result<(), SerialError> {
+    if name.is_empty() || name.chars().any(|c| c.is_whitespace() || c == '<' || c == '>') {
+        return Err(SerialError::DeviceError(format!("Invalid device file name '{}'", name)));
+    }
+    Ok(())
+}
+
+/// Parse a listing line of the form "FILE:<name>:<size>"
+fn parse_file_entry(line: &str) -> Option<DeviceFile> {
+    let rest = line.strip_prefix("FILE:")?;
+    let (name, size) = rest.rsplit_once(':')?;
+    Some(DeviceFile {
+        name: name.to_string(),
+        size: size.trim().parse().ok()?,
+    })
+}
+
+/// List files on the device (`<LS>` → `FILE:` lines terminated by `LS_END`)
+pub fn list_files(conn: &mut SerialConnection) -> Result<Vec<DeviceFile>, SerialError> {
+    let lines = conn.transact("<LS>", timeout(), |l| l == "LS_END" || l.starts_with("NAK:"))?;
+    check_nak(&lines)?;
+    Ok(lines.iter().filter_map(|l| parse_file_entry(l)).collect())
+}
+
+/// Parse a catalog line of the form "SIG:<name>:<size>:<active 0|1>[:<CRC32 hex>]"
+fn parse_signal_entry(line: &str) -> Option<DeviceSignal> {
+    let mut rest = line.strip_prefix("SIG:")?;
+    let mut crc32 = None;
+    if let Some((head, last)) = rest.rsplit_once(':') {
+        if last.len() == 8 {
+            crc32 = Some(u32::from_str_radix(last, 16).ok()?);
+            rest = head;
+        }
+    }
+    let (rest, active) = rest.rsplit_once(':')?;
+    let (name, size) = rest.rsplit_once(':')?;
+    Some(DeviceSignal {
+        name: name.to_string(),
+        size: size.trim().parse().ok()?,
+        active: active.trim() == "1",
+        crc32,
+    })
+}
+
+/// List the signals stored on the device (`<SIGLS>` → `SIG:` lines terminated by `SIGLS_END`)
+pub fn list_signals(conn: &mut SerialConnection) -> Result<Vec<DeviceSignal>, SerialError> {
+    let lines = conn.transact("<SIGLS>", timeout(), |l| l == "SIGLS_END" || l.starts_with("NAK:"))?;
+    check_nak(&lines)?;
+    Ok(lines.iter().filter_map(|l| parse_signal_entry(l)).collect())
+}
+
+/// Make a stored signal the active one (`<SIGUSE name>` → `ACK`)
+pub fn activate_signal(conn: &mut SerialConnection, name: &str) -> Result<(), SerialError> {
+    validate_remote_name(name)?;
+    let lines = conn.transact(&format!("<SIGUSE {}>", name), timeout(), |l| {
+        l == "ACK" || l.starts_with("NAK:")
+    })?;
+    check_nak(&lines)
+}
+
+/// Download a file chunk by chunk (`<READ name offset len>` → `DATA:<base64>` or `EOF`)
+pub fn download_file<F>(
+    conn: &mut SerialConnection,
+    name: &str,
+    on_progress: F,
+) -> Result<Vec<u8>, SerialError>
+where
+    F: FnMut(TransferProgress),
+{
+    validate_remote_name(name)?;
+
+    let total = list_files(conn)?
+        .into_iter()
+        .find(|f| f.name == name)
+        .map(|f| f.size)
+        .ok_or_else(|| SerialError::DeviceError(format!("File '{}' not found on device", name)))?;
+    read_chunked(conn, "READ", name, total, on_progress)
+}
+
+/// Read a stored signal's config JSON chunk by chunk (`<SIGGET name offset len>`,
+/// answered like `<READ>`)
+pub fn read_signal(conn: &mut SerialConnection, name: &str) -> Result<String, SerialError> {
+    validate_remote_name(name)?;
+
+    let total = list_signals(conn)?
+        .into_iter()
+        .find(|s| s.name == name)
+        .map(|s| s.size)
+        .ok_or_else(|| SerialError::DeviceError(format!("Signal '{}' not found on device", name)))?;
+    let data = read_chunked(conn, "SIGGET", name, total, |_| {})?;
+    String::from_utf8(data).map_err(|_| SerialError::DeviceError(format!("Signal '{}' is not valid UTF-8", name)))
+}
+
+/// `<VERB name offset len>` round trips until `total` bytes are in
+fn read_chunked<F>(
+    conn: &mut SerialConnection,
+    verb: &str,
+    name: &str,
+    total: u64,
+    mut on_progress: F,
+) -> Result<Vec<u8>, SerialError>
+where
+    F: FnMut(TransferProgress),
+{
+    let mut data: Vec<u8> = Vec::with_capacity(total as usize);
+    while (data.len() as u64) < total {
+        let request = format!("<{} {} {} {}>", verb, name, data.len(), READ_CHUNK_SIZE);
+        let lines = conn.transact(&request, timeout(), |l| {
+            l.starts_with("DATA:") || l == "EOF" || l.starts_with("NAK:")
+        })?;
+        check_nak(&lines)?;
+
+        let last = lines.last().map(String::as_str).unwrap_or("EOF");
+        let Some(payload) = last.strip_prefix("DATA:") else {
+            break;
+        };
+        let bytes = STANDARD
+            .decode(payload.trim())
+            .map_err(|e| SerialError::DeviceError(format!("Corrupt chunk from device: {}", e)))?;
+        if bytes.is_empty() {
+            break;
+        }
+        data.extend_from_slice(&bytes);
+
+        on_progress(TransferProgress {
+            name: name.to_string(),
+            bytes_done: data.len() as u64,
+            total,
+        });
+    }
+
+    Ok(data)
+}
+
+/// Upload a file as one framed stream: `<PUT name size>`, the base64 body and `<END>`,
+/// acknowledged with `ACK` once the device has written and closed the file
+pub fn upload_file<F>(
+    conn: &mut SerialConnection,
+    name: &str,
+    data: &[u8],
+    on_progress: F,
+) -> Result<(), SerialError>
+where
+    F: FnMut(TransferProgress),
+{
+    validate_remote_name(name)?;
+    put_framed(conn, "PUT", name, data, on_progress)
+}
+
+/// Store a config JSON in the device's signal slot `name`, replacing what was there
+/// (`<SIGPUT name size>` framed like `<PUT>`)
+pub fn store_signal(conn: &mut SerialConnection, name: &str, config_json: &str) -> Result<(), SerialError> {
+    validate_remote_name(name)?;
+    put_framed(conn, "SIGPUT", name, config_json.as_bytes(), |_| {})
+}
+
+/// `<VERB name size>`, the base64 body and `<END>`, then wait for `ACK`
+fn put_framed<F>(
+    conn: &mut SerialConnection,
+    verb: &str,
+    name: &str,
+    data: &[u8],
+    mut on_progress: F,
+) -> Result<(), SerialError>
+where
+    F: FnMut(TransferProgress),
+{
+    let total = data.len() as u64;
+    let body = STANDARD.encode(data);
+    let start = format!("<{} {} {}>\n", verb, name, total);
+    let spec = conn.frame_spec(&start, PUT_END_MARKER);
+
+    let outcome = conn.send_framed(&spec, body.as_bytes(), |sent, frame_len| {
+        // Report progress in file bytes rather than encoded frame bytes
+        let bytes_done = (sent as u64 * total) / frame_len.max(1) as u64;
+        on_progress(TransferProgress {
+            name: name.to_string(),
+            bytes_done,
+            total,
+        });
+    })?;
+
+    if let Some(line) = outcome.nak_line {
+        return Err(SerialError::DeviceError(line));
+    }
+    if !outcome.saw_ack {
+        return Err(SerialError::Timeout);
+    }
+    Ok(())
+}
+
+/// Delete a file on the device (`<RM name>` → `ACK`)
+pub fn delete_file(conn: &mut SerialConnection, name: &str) -> Result<(), SerialError> {
+    validate_remote_name(name)?;
+    let lines = conn.transact(&format!("<RM {}>", name), timeout(), |l| {
+        l == "ACK" || l.starts_with("NAK:")
+    })?;
+    check_nak(&lines)
+}
+
+/// Local folder where downloaded device files are stored
+pub fn get_downloads_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = storage::data_dir(app)?.join(DOWNLOADS_DIR);
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+
+    Ok(dir)
+}
+
+/// Flatten a device path ("/logs/run.txt") into a local file name ("logs_run.txt")
+pub fn local_name(remote: &str) -> String {
+    remote.trim_start_matches('/').replace('/', "_")
+}
@@ -0,0 +1,255 @@
+use crate::recent_output::RecentOutput;
+use crate::serial::{Direction, LineSink, TrafficTap};
+use crate::session::now_millis;
+use crate::settings::SettingsState;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Bytes and chunks of a config upload sent so far, and its stage
+pub const UPLOAD_PROGRESS_EVENT: &str = "upload://progress";
+/// Progress lines reported by the device during an upload
+pub const DEVICE_PROGRESS_EVENT: &str = "upload://device-progress";
+/// Batches of other device output received during an upload
+pub const UPLOAD_LOG_EVENT: &str = "upload://device-log";
+/// State and progress of a long operation (upload, flash, file transfer), see `jobs`
+pub const JOB_PROGRESS_EVENT: &str = "job://progress";
+/// A library scan found problems
+pub const LIBRARY_ISSUES_EVENT: &str = "library://issues";
+/// A signal link opened from outside the app was imported (or failed to)
+pub const LINK_IMPORT_EVENT: &str = "library://link-import";
+/// Every soak snapshot as it is taken
+pub const SOAK_SNAPSHOT_EVENT: &str = "soak://snapshot";
+/// Anomalies spotted during a soak run
+pub const SOAK_ALERT_EVENT: &str = "soak://alert";
+/// Fresh device status, pushed after commands that change the run state or RPM
+pub const DEVICE_STATUS_EVENT: &str = "device://status";
+/// The device restarted while the signal was supposed to be running
+pub const DEVICE_RESET_EVENT: &str = "device://reset";
+/// A critical section started or the last one ended
+pub const CRITICAL_SECTION_EVENT: &str = "device://critical-section";
+/// Command traffic on the serial port, line by line, for the serial monitor
+pub const TERMINAL_EVENT: &str = "serial://terminal";
+/// Output the device printed on its own, outside any command's reply
+pub const SERIAL_RX_EVENT: &str = "serial://rx";
+/// Steps of a connect attempt, so the UI can show more than a spinner
+pub const CONNECTION_PROGRESS_EVENT: &str = "connection://progress";
+/// The port stopped working; says whether the app is trying to reopen it
+pub const CONNECTION_LOST_EVENT: &str = "connection://lost";
+/// A lost connection was opened again
+pub const CONNECTION_RESTORED_EVENT: &str = "connection://restored";
+/// A serial port appeared; carries its `PortInfo`
+pub const PORT_ADDED_EVENT: &str = "port://added";
+/// A serial port went away; carries its last `PortInfo`
+pub const PORT_REMOVED_EVENT: &str = "port://removed";
+/// An event the operator must notice (sound, screen flash, notification), see `attention`
+pub const ATTENTION_EVENT: &str = "app://attention";
+/// Settings changed; carries only the changed keys and the new revision
+pub const SETTINGS_CHANGED_EVENT: &str = "settings://changed";
+
+// Lines held back per batch; during a log storm the oldest are dropped
+const MAX_BATCH_LINES: usize = 500;
+
+/// Minimum spacing between emissions of one high-rate event, from settings (0 = unlimited)
+fn min_interval(app: &AppHandle) -> Duration {
+    let per_sec = app
+        .try_state::<SettingsState>()
+        .map(|s| s.get().max_events_per_sec)
+        .unwrap_or(0);
+    if per_sec == 0 {
+        Duration::ZERO
+    } else {
+        Duration::from_millis(1000 / per_sec as u64)
+    }
+}
+
+/// Rate-limited emitter for state-like payloads (progress, status).
+///
+/// Payloads arriving faster than the configured rate are coalesced, keeping only the
+/// latest; it is delivered with the next emission or when the throttle is dropped, so
+/// the final state always reaches the UI.
+pub struct Throttle<T: Serialize + Clone> {
+    app: AppHandle,
+    event: &'static str,
+    interval: Duration,
+    last_emit: Option<Instant>,
+    pending: Option<T>,
+}
+
+impl<T: Serialize + Clone> Throttle<T> {
+    pub fn new(app: &AppHandle, event: &'static str) -> Self {
+        Throttle {
+            app: app.clone(),
+            event,
+            interval: min_interval(app),
+            last_emit: None,
+            pending: None,
+        }
+    }
+
+    pub fn emit(&mut self, payload: T) {
+        let due = self.last_emit.is_none_or(|t| t.elapsed() >= self.interval);
+        if due {
+            self.pending = None;
+            self.last_emit = Some(Instant::now());
+            let _ = self.app.emit(self.event, payload);
+        } else {
+            self.pending = Some(payload);
+        }
+    }
+
+    /// Deliver a coalesced payload that is still waiting
+    pub fn flush(&mut self) {
+        if let Some(payload) = self.pending.take() {
+            self.last_emit = Some(Instant::now());
+            let _ = self.app.emit(self.event, payload);
+        }
+    }
+}
+
+impl<T: Serialize + Clone> Drop for Throttle<T> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Payload of batched line events
+#[derive(Debug, Clone, Serialize)]
+pub struct LineBatch {
+    pub lines: Vec<String>,
+    /// Lines dropped since the previous batch because the UI couldn't keep up
+    pub dropped: usize,
+}
+
+/// Collects text lines and emits them as arrays at the configured rate
+pub struct LineBatcher {
+    app: AppHandle,
+    event: &'static str,
+    interval: Duration,
+    last_flush: Instant,
+    lines: VecDeque<String>,
+    dropped: usize,
+}
+
+impl LineBatcher {
+    pub fn new(app: &AppHandle, event: &'static str) -> Self {
+        LineBatcher {
+            app: app.clone(),
+            event,
+            interval: min_interval(app),
+            last_flush: Instant::now(),
+            lines: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, line: impl Into<String>) {
+        self.lines.push_back(line.into());
+        if self.lines.len() > MAX_BATCH_LINES {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        if self.last_flush.elapsed() >= self.interval {
+            self.flush();
+        }
+    }
+
+    /// Emit whatever has been collected
+    pub fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if self.lines.is_empty() {
+            return;
+        }
+        let batch = LineBatch {
+            lines: std::mem::take(&mut self.lines).into(),
+            dropped: std::mem::take(&mut self.dropped),
+        };
+        let _ = self.app.emit(self.event, batch);
+    }
+}
+
+impl Drop for LineBatcher {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Payload of the terminal event
+#[derive(Debug, Clone, Serialize)]
+pub struct TerminalLine {
+    pub timestamp: u64,
+    pub direction: Direction,
+    pub text: String,
+}
+
+/// Traffic tap that forwards every line to all windows as terminal events and keeps
+/// what the device sent in the recent output
+pub fn terminal_tap(app: &AppHandle) -> TrafficTap {
+    let app = app.clone();
+    let recent = app.state::<RecentOutput>().inner().clone();
+    Box::new(move |direction, text| {
+        if direction == Direction::Rx {
+            recent.push(text);
+        }
+        let line = TerminalLine {
+            timestamp: now_millis(),
+            direction,
+            text: text.to_string(),
+        };
+        let _ = app.emit(TERMINAL_EVENT, line);
+    })
+}
+
+/// Payload of the `serial://rx` event
+#[derive(Debug, Clone, Serialize)]
+pub struct RxLine {
+    pub timestamp: u64,
+    pub text: String,
+}
+
+/// Sink for unsolicited device output that forwards each line as an rx event
+pub fn unsolicited_sink(app: &AppHandle) -> LineSink {
+    let app = app.clone();
+    Box::new(move |text| {
+        let line = RxLine {
+            timestamp: now_millis(),
+            text: text.to_string(),
+        };
+        let _ = app.emit(SERIAL_RX_EVENT, line);
+    })
+}
+
+/// Step a connect attempt has reached
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectPhase {
+    Opening,
+    Settling,
+    Handshaking,
+}
+
+/// Payload of the `connection://progress` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectProgress {
+    pub port: String,
+    pub phase: ConnectPhase,
+    /// Time since the attempt started
+    pub elapsed_ms: u64,
+}
+
+/// Payload of the `connection://lost` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionLost {
+    pub port: String,
+    pub reason: String,
+    /// Reconnect attempts follow; false when disabled or after the last one failed
+    pub reconnecting: bool,
+}
+
+/// Payload of the `connection://restored` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionRestored {
+    pub port: String,
+    pub attempt: u32,
+}
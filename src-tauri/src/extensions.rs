@@ -0,0 +1,253 @@
+use crate::device_command::ExtensionCall;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+
+// Folder in the app data dir holding the descriptor files
+const EXTENSIONS_DIR: &str = "extensions";
+const DEFAULT_TIMEOUT_MS: u64 = 1000;
+
+/// Kind of value a descriptor parameter takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamKind {
+    Integer,
+    Text,
+}
+
+/// A parameter of an extension command, substituted for `{name}` in its `send` line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionParam {
+    pub name: String,
+    pub kind: ParamKind,
+    /// Bounds for integers
+    #[serde(default)]
+    pub min: Option<i64>,
+    #[serde(default)]
+    pub max: Option<i64>,
+    /// Allowed values for text; empty accepts any single word
+    #[serde(default)]
+    pub choices: Vec<String>,
+    /// Used when the call leaves the parameter out; without one the parameter is required
+    #[serde(default)]
+    pub default: Option<Value>,
+}
+
+/// A device command declared by a descriptor file, e.g. in TOML:
+///
+/// ```toml
+/// [[commands]]
+/// name = "set_tooth_count"
+/// send = "<TEETH {count}>"
+/// expect = ["TEETH:OK"]
+/// params = [{ name = "count", kind = "integer", min = 1, max = 120 }]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionCommand {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Line sent to the firmware, with `{param}` placeholders
+    pub send: String,
+    /// Reply fragments confirming the command; empty leaves it unchecked
+    #[serde(default)]
+    pub expect: Vec<String>,
+    /// Reply prefixes meaning the firmware refused it; empty uses `NAK:`/`ERR:`
+    #[serde(default)]
+    pub reject: Vec<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub params: Vec<ExtensionParam>,
+    /// Descriptor file it came from, for the UI and error messages
+    #[serde(default, skip_deserializing)]
+    pub source: String,
+}
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+/// Layout of a descriptor file, JSON or TOML
+#[derive(Debug, Deserialize)]
+struct DescriptorFile {
+    commands: Vec<ExtensionCommand>,
+}
+
+impl ExtensionCommand {
+    /// Every placeholder has a parameter and every parameter is used
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("command without a name".into());
+        }
+        if self.timeout_ms == 0 {
+            return Err(format!("'{}': timeout_ms must be greater than zero", self.name));
+        }
+        let placeholders = placeholders(&self.send);
+        if let Some(unknown) = placeholders.iter().find(|p| !self.params.iter().any(|param| &param.name == *p)) {
+            return Err(format!("'{}': no parameter for placeholder {{{}}}", self.name, unknown));
+        }
+        if let Some(unused) = self.params.iter().find(|param| !placeholders.contains(&param.name.as_str())) {
+            return Err(format!("'{}': parameter '{}' is not used in the send line", self.name, unused.name));
+        }
+        Ok(())
+    }
+
+    /// Fill in the parameters from a call's arguments, checking each against its declaration
+    pub fn bind(&self, args: &serde_json::Map<String, Value>) -> Result<ExtensionCall, String> {
+        let mut line = self.send.clone();
+        for param in &self.params {
+            let value = args
+                .get(&param.name)
+                .or(param.default.as_ref())
+                .ok_or_else(|| format!("Missing parameter '{}'", param.name))?;
+            let text = param.format(value)?;
+            line = line.replace(&format!("{{{}}}", param.name), &text);
+        }
+        Ok(ExtensionCall {
+            name: self.name.clone(),
+            line,
+            expect: self.expect.clone(),
+            reject: self.reject.clone(),
+            timeout_ms: self.timeout_ms,
+        })
+    }
+}
+
+impl ExtensionParam {
+    fn format(&self, value: &Value) -> Result<String, String> {
+        match self.kind {
+            ParamKind::Integer => {
+                let n = value
+                    .as_i64()
+                    .ok_or_else(|| format!("Parameter '{}' must be an integer", self.name))?;
+                if self.min.is_some_and(|min| n < min) || self.max.is_some_and(|max| n > max) {
+                    return Err(format!(
+                        "Parameter '{}' must be between {} and {}",
+                        self.name,
+                        self.min.map_or("-".into(), |m| m.to_string()),
+                        self.max.map_or("-".into(), |m| m.to_string())
+                    ));
+                }
+                Ok(n.to_string())
+            }
+            ParamKind::Text => {
+                let text = value
+                    .as_str()
+                    .ok_or_else(|| format!("Parameter '{}' must be text", self.name))?;
+                if !self.choices.is_empty() && !self.choices.iter().any(|c| c == text) {
+                    return Err(format!("Parameter '{}' must be one of: {}", self.name, self.choices.join(", ")));
+                }
+                if text.is_empty() || !text.chars().all(|c| c.is_ascii_graphic()) {
+                    return Err(format!("Parameter '{}' must be a single word of printable ASCII", self.name));
+                }
+                Ok(text.to_string())
+            }
+        }
+    }
+}
+
+/// Names inside `{...}` in a send line
+fn placeholders(send: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = send;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start + 1..].find('}') else { break };
+        names.push(&rest[start + 1..start + 1 + len]);
+        rest = &rest[start + 1 + len + 1..];
+    }
+    names
+}
+
+fn read_descriptor(path: &Path) -> Result<Vec<ExtensionCommand>, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let file: DescriptorFile = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|e| e.to_string())?,
+        _ => serde_json::from_str(&content).map_err(|e| e.to_string())?,
+    };
+    let source = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    Ok(file
+        .commands
+        .into_iter()
+        .map(|command| ExtensionCommand {
+            source: source.clone(),
+            ..command
+        })
+        .collect())
+}
+
+/// Registered extension commands, with the problems found loading the descriptors
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExtensionList {
+    pub commands: Vec<ExtensionCommand>,
+    pub errors: Vec<String>,
+}
+
+/// Extension commands loaded from the descriptor folder
+#[derive(Clone, Default)]
+pub struct ExtensionRegistry(Arc<Mutex<ExtensionList>>);
+
+impl ExtensionRegistry {
+    pub fn list(&self) -> ExtensionList {
+        self.0.lock().map(|l| l.clone()).unwrap_or_default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<ExtensionCommand> {
+        let list = self.0.lock().ok()?;
+        list.commands.iter().find(|c| c.name == name).cloned()
+    }
+}
+
+/// Read every `.json` and `.toml` descriptor in the extensions folder and replace the
+/// registered commands. Invalid files and commands are skipped and reported; on a name
+/// clash the file that sorts first wins.
+pub fn load(app: &AppHandle) -> ExtensionList {
+    let mut loaded = ExtensionList::default();
+    match storage::data_dir(app) {
+        Ok(dir) => load_dir(&dir.join(EXTENSIONS_DIR), &mut loaded),
+        Err(e) => loaded.errors.push(e),
+    }
+    for error in &loaded.errors {
+        eprintln!("[EXTENSIONS] {}", error);
+    }
+    if let Ok(mut registry) = app.state::<ExtensionRegistry>().0.lock() {
+        *registry = loaded.clone();
+    }
+    loaded
+}
+
+fn load_dir(dir: &Path, loaded: &mut ExtensionList) {
+    // A missing folder just means no extensions
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json" || ext == "toml"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let file = path.display();
+        let commands = match read_descriptor(&path) {
+            Ok(commands) => commands,
+            Err(e) => {
+                loaded.errors.push(format!("{}: {}", file, e));
+                continue;
+            }
+        };
+        for command in commands {
+            if let Err(e) = command.validate() {
+                loaded.errors.push(format!("{}: {}", file, e));
+            } else if loaded.commands.iter().any(|c| c.name == command.name) {
+                loaded.errors.push(format!("{}: '{}' is already defined", file, command.name));
+            } else {
+                loaded.commands.push(command);
+            }
+        }
+    }
+}
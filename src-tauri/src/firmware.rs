@@ -0,0 +1,350 @@
+use serde::{Deserialize, Serialize};
+use serialport::SerialPort;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// Where the application image goes with the default ESP32 partition table;
+// merged images (bootloader + partitions + app) go at 0
+pub const APP_OFFSET: u32 = 0x10000;
+// The ROM bootloader always starts at this speed
+const ROM_BAUD: u32 = 115_200;
+// Line speed requested for the transfer; the ROM loader handles it on every ESP32 board
+const FLASH_BAUD: u32 = 460_800;
+// Flash write block the ROM loader accepts
+const BLOCK_SIZE: usize = 0x400;
+// Flash size assumed for the SPI parameters: the smallest of common ESP32 modules
+const FLASH_SIZE: u32 = 4 * 1024 * 1024;
+const SYNC_ATTEMPTS: usize = 7;
+const COMMAND_TIMEOUT_MS: u64 = 3000;
+// Erasing runs before the FLASH_BEGIN reply; it takes up to this long per MB
+const ERASE_TIMEOUT_PER_MB_MS: u64 = 30_000;
+// Value of the chip magic register on the original ESP32
+const ESP32_MAGIC: u32 = 0x00f0_1d83;
+const CHIP_MAGIC_REG: u32 = 0x4000_1000;
+
+// ROM loader commands (esptool serial protocol)
+const FLASH_BEGIN: u8 = 0x02;
+const FLASH_DATA: u8 = 0x03;
+const FLASH_END: u8 = 0x04;
+const SYNC: u8 = 0x08;
+const READ_REG: u8 = 0x0A;
+const SPI_SET_PARAMS: u8 = 0x0B;
+const SPI_ATTACH: u8 = 0x0D;
+const CHANGE_BAUDRATE: u8 = 0x0F;
+const SPI_FLASH_MD5: u8 = 0x13;
+
+// SLIP framing
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+#[derive(Error, Debug)]
+pub enum FlashError {
+    #[error("Failed to read firmware image: {0}")]
+    ImageError(String),
+    #[error("Port {0} not found")]
+    PortNotFound(String),
+    #[error("Flashing failed: {0}")]
+    FlashFailed(String),
+}
+
+impl Serialize for FlashError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl From<std::io::Error> for FlashError {
+    fn from(err: std::io::Error) -> Self {
+        FlashError::FlashFailed(err.to_string())
+    }
+}
+
+impl From<serialport::Error> for FlashError {
+    fn from(err: serialport::Error) -> Self {
+        FlashError::FlashFailed(err.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlashProgress {
+    pub phase: String,
+    pub percent: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlashResult {
+    pub port: String,
+    /// Chip the ROM loader identified as, e.g. "esp32"
+    pub chip: String,
+    pub address: u32,
+    pub bytes_written: u64,
+    pub duration_ms: u64,
+}
+
+fn progress(phase: &str, percent: u64) -> FlashProgress {
+    FlashProgress {
+        phase: phase.to_string(),
+        percent,
+    }
+}
+
+fn slip_encode(packet: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(packet.len() + 8);
+    out.push(SLIP_END);
+    for &byte in packet {
+        match byte {
+            SLIP_END => out.extend_from_slice(&[SLIP_ESC, SLIP_ESC_END]),
+            SLIP_ESC => out.extend_from_slice(&[SLIP_ESC, SLIP_ESC_ESC]),
+            _ => out.push(byte),
+        }
+    }
+    out.push(SLIP_END);
+    out
+}
+
+/// XOR checksum the ROM loader expects with flash data
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0xEF, |sum, &b| sum ^ b) as u32
+}
+
+fn words(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// The ESP32 ROM bootloader on an open port
+struct RomLoader {
+    port: Box<dyn SerialPort>,
+    // Bytes of a SLIP frame still being received
+    frame: Vec<u8>,
+    escaped: bool,
+}
+
+impl RomLoader {
+    /// Reset the board into its bootloader (DTR drives IO0, RTS drives EN, as on the
+    /// usual auto-reset circuit) and sync with it
+    fn connect(port_name: &str) -> Result<Self, FlashError> {
+        let port = serialport::new(port_name, ROM_BAUD)
+            .timeout(Duration::from_millis(100))
+            .open()?;
+        let mut loader = RomLoader {
+            port,
+            frame: Vec::new(),
+            escaped: false,
+        };
+        for _ in 0..SYNC_ATTEMPTS {
+            loader.enter_bootloader()?;
+            if loader.sync().is_ok() {
+                return Ok(loader);
+            }
+        }
+        Err(FlashError::FlashFailed(
+            "The bootloader didn't answer; hold BOOT while pressing EN and try again".into(),
+        ))
+    }
+
+    fn enter_bootloader(&mut self) -> Result<(), FlashError> {
+        self.port.write_data_terminal_ready(false)?;
+        self.port.write_request_to_send(true)?;
+        std::thread::sleep(Duration::from_millis(100));
+        self.port.write_data_terminal_ready(true)?;
+        self.port.write_request_to_send(false)?;
+        std::thread::sleep(Duration::from_millis(50));
+        self.port.write_data_terminal_ready(false)?;
+        // Let the boot message pass, then drop it
+        std::thread::sleep(Duration::from_millis(50));
+        self.port.clear(serialport::ClearBuffer::Input)?;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), FlashError> {
+        let mut data = vec![0x07, 0x07, 0x12, 0x20];
+        data.extend([0x55; 32]);
+        self.command(SYNC, &data, 0, Duration::from_millis(100))?;
+        // The ROM answers a sync several times; drop the extra replies
+        std::thread::sleep(Duration::from_millis(50));
+        self.port.clear(serialport::ClearBuffer::Input)?;
+        self.frame.clear();
+        Ok(())
+    }
+
+    /// Send a command and wait for its reply; returns the reply's value and data
+    fn command(&mut self, op: u8, data: &[u8], check: u32, timeout: Duration) -> Result<(u32, Vec<u8>), FlashError> {
+        let mut packet = vec![0x00, op];
+        packet.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        packet.extend_from_slice(&check.to_le_bytes());
+        packet.extend_from_slice(data);
+        self.port.write_all(&slip_encode(&packet))?;
+        self.port.flush()?;
+
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            let Some(reply) = self.read_frame()? else {
+                continue;
+            };
+            if reply.len() < 8 || reply[0] != 0x01 || reply[1] != op {
+                continue;
+            }
+            let value = u32::from_le_bytes([reply[4], reply[5], reply[6], reply[7]]);
+            let body = reply[8..].to_vec();
+            // The ROM loader ends every reply with 4 status bytes: status, error, reserved
+            let status = body.len().checked_sub(4).map(|i| (body[i], body[i + 1]));
+            return match status {
+                Some((0, _)) => Ok((value, body[..body.len() - 4].to_vec())),
+                Some((_, error)) => Err(FlashError::FlashFailed(format!("Command {:#04x} failed with error {:#04x}", op, error))),
+                None => Err(FlashError::FlashFailed(format!("Malformed reply to command {:#04x}", op))),
+            };
+        }
+        Err(FlashError::FlashFailed(format!("No reply to command {:#04x}", op)))
+    }
+
+    /// Next complete SLIP frame, or `None` when nothing finished within one read
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>, FlashError> {
+        let mut byte = [0u8; 1];
+        loop {
+            match self.port.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => {}
+                Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+            match (self.escaped, byte[0]) {
+                (false, SLIP_END) if self.frame.is_empty() => {}
+                (false, SLIP_END) => return Ok(Some(std::mem::take(&mut self.frame))),
+                (false, SLIP_ESC) => self.escaped = true,
+                (true, SLIP_ESC_END) => {
+                    self.frame.push(SLIP_END);
+                    self.escaped = false;
+                }
+                (true, SLIP_ESC_ESC) => {
+                    self.frame.push(SLIP_ESC);
+                    self.escaped = false;
+                }
+                (_, other) => {
+                    self.frame.push(other);
+                    self.escaped = false;
+                }
+            }
+        }
+    }
+
+    fn timeout() -> Duration {
+        Duration::from_millis(COMMAND_TIMEOUT_MS)
+    }
+
+    fn read_reg(&mut self, address: u32) -> Result<u32, FlashError> {
+        self.command(READ_REG, &address.to_le_bytes(), 0, Self::timeout()).map(|(value, _)| value)
+    }
+
+    fn change_baud(&mut self, baud: u32) -> Result<(), FlashError> {
+        self.command(CHANGE_BAUDRATE, &words(&[baud, 0]), 0, Self::timeout())?;
+        self.port.set_baud_rate(baud)?;
+        std::thread::sleep(Duration::from_millis(50));
+        self.port.clear(serialport::ClearBuffer::Input)?;
+        Ok(())
+    }
+
+    /// Make the ROM use the SPI flash chip with the default pins and size parameters
+    fn attach_flash(&mut self) -> Result<(), FlashError> {
+        self.command(SPI_ATTACH, &words(&[0, 0]), 0, Self::timeout())?;
+        let params = words(&[0, FLASH_SIZE, 64 * 1024, 4 * 1024, 256, 0xFFFF]);
+        self.command(SPI_SET_PARAMS, &params, 0, Self::timeout())?;
+        Ok(())
+    }
+
+    /// Erase the region and write `image` at `address`, block by block
+    fn write(&mut self, image: &[u8], address: u32, esp32: bool, on_progress: &mut dyn FnMut(FlashProgress)) -> Result<(), FlashError> {
+        let blocks = image.len().div_ceil(BLOCK_SIZE);
+        let mut begin = vec![image.len() as u32, blocks as u32, BLOCK_SIZE as u32, address];
+        // Later chips take a fifth word: don't encrypt
+        if !esp32 {
+            begin.push(0);
+        }
+        let megabytes = (image.len() as u64).div_ceil(1024 * 1024);
+        let erase_timeout = Duration::from_millis(COMMAND_TIMEOUT_MS.max(ERASE_TIMEOUT_PER_MB_MS * megabytes));
+        on_progress(progress("Erasing", 0));
+        self.command(FLASH_BEGIN, &words(&begin), 0, erase_timeout)?;
+
+        for (seq, chunk) in image.chunks(BLOCK_SIZE).enumerate() {
+            let mut block = chunk.to_vec();
+            block.resize(BLOCK_SIZE, 0xFF);
+            let mut data = words(&[BLOCK_SIZE as u32, seq as u32, 0, 0]);
+            data.extend_from_slice(&block);
+            self.command(FLASH_DATA, &data, checksum(&block), Self::timeout())?;
+            on_progress(progress("Writing", ((seq + 1) * 100 / blocks) as u64));
+        }
+        Ok(())
+    }
+
+    /// Compare the flash contents with `image` through the ROM's MD5 of the region
+    fn verify(&mut self, image: &[u8], address: u32) -> Result<(), FlashError> {
+        let request = words(&[address, image.len() as u32, 0, 0]);
+        let megabytes = (image.len() as u64).div_ceil(1024 * 1024);
+        let timeout = Duration::from_millis(COMMAND_TIMEOUT_MS * megabytes.max(1) * 2);
+        let (_, digest) = self.command(SPI_FLASH_MD5, &request, 0, timeout)?;
+        let flashed = String::from_utf8_lossy(&digest).to_lowercase();
+        let expected = format!("{:x}", md5::compute(image));
+        if flashed != expected {
+            return Err(FlashError::FlashFailed(format!(
+                "Verification failed: flash holds {}, image is {}",
+                flashed, expected
+            )));
+        }
+        Ok(())
+    }
+
+    /// Leave the loader and pulse EN so the board boots the new firmware
+    fn hard_reset(&mut self) -> Result<(), FlashError> {
+        // Stay in the loader rather than jump to the app without a clean reset
+        let _ = self.command(FLASH_END, &words(&[1]), 0, Self::timeout());
+        self.port.write_data_terminal_ready(false)?;
+        self.port.write_request_to_send(true)?;
+        std::thread::sleep(Duration::from_millis(100));
+        self.port.write_request_to_send(false)?;
+        Ok(())
+    }
+}
+
+/// Write `image` to the flash of the ESP32 on `port` at `address` over the ROM
+/// bootloader, the way esptool does without its stub, then hard-reset it into the
+/// new firmware. The port must not be open elsewhere. Blocking.
+pub fn flash(port: &str, image: &[u8], address: u32, mut on_progress: impl FnMut(FlashProgress)) -> Result<FlashResult, FlashError> {
+    if image.is_empty() {
+        return Err(FlashError::ImageError("Image is empty".into()));
+    }
+    let started = Instant::now();
+    let known = serialport::available_ports()?.into_iter().any(|p| p.port_name == port);
+    if !known {
+        return Err(FlashError::PortNotFound(port.to_string()));
+    }
+
+    on_progress(progress("Connecting", 0));
+    let mut loader = RomLoader::connect(port)?;
+    let magic = loader.read_reg(CHIP_MAGIC_REG)?;
+    let esp32 = magic == ESP32_MAGIC;
+    let chip = if esp32 {
+        "esp32".to_string()
+    } else {
+        format!("ESP32-family chip (magic {:#010x})", magic)
+    };
+    loader.change_baud(FLASH_BAUD)?;
+    loader.attach_flash()?;
+    loader.write(image, address, esp32, &mut on_progress)?;
+    on_progress(progress("Verifying", 100));
+    loader.verify(image, address)?;
+    on_progress(progress("Restarting", 100));
+    loader.hard_reset()?;
+
+    Ok(FlashResult {
+        port: port.to_string(),
+        chip,
+        address,
+        bytes_written: image.len() as u64,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
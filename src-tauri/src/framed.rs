@@ -0,0 +1,865 @@
+use crate::cobs::{self, Packet, PacketKind, PacketReader};
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Read, Write};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// Responses are capped to avoid unbounded growth
+const RESPONSE_CAP: usize = 16 * 1024;
+// Longest line the ACK scanner keeps; of a longer one only the most recent part is kept
+const MAX_LINE_LEN: usize = 1024;
+// Pause between reads while draining or when the stream reports no data
+const IDLE_POLL_MS: u64 = 20;
+
+#[derive(Error, Debug)]
+pub enum TransferError {
+    #[error("{0}")]
+    Write(std::io::Error),
+    #[error("{0}")]
+    Read(std::io::Error),
+    #[error("Receiver stopped acknowledging after {0} bytes")]
+    Stalled(usize),
+    /// Stopped on request; carries the bytes already sent
+    #[error("Cancelled after {0} bytes")]
+    Cancelled(usize),
+}
+
+/// How consecutive chunks are spaced
+#[derive(Debug, Clone, Copy)]
+pub enum Pacing {
+    /// Fixed pause after each chunk, letting a small receive buffer drain
+    Delay(Duration),
+    /// Wait for the receiver to answer each chunk before sending the next
+    AwaitReply,
+    /// Keep at most `window` bytes unacknowledged. The receiver reports its running
+    /// byte count in `credit` lines (e.g. "WIN:192"); sending pauses once the window
+    /// is full and gives up when no credit arrives within `timeout`.
+    Windowed {
+        window: usize,
+        credit: &'static str,
+        timeout: Duration,
+    },
+}
+
+/// How a transfer is delimited on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameEncoding {
+    /// Markers around the raw payload, answered with text lines
+    #[default]
+    Text,
+    /// Markers and payload go out as one length-prefixed COBS packet (see `cobs`), and
+    /// the receiver answers with packets until its verdict, then returns to text. A
+    /// payload can hold anything, end markers and ACK tokens included, and device output
+    /// can't pass for a verdict.
+    Cobs,
+}
+
+/// Shape of one framed transfer: markers around the payload, chunking and the replies
+/// that end it
+#[derive(Debug, Clone)]
+pub struct FrameSpec {
+    pub start_marker: String,
+    pub end_marker: String,
+    pub chunk_size: usize,
+    pub pacing: Pacing,
+    /// Lines accepted as success (exact match)
+    pub ack_tokens: Vec<String>,
+    /// Line prefixes that mean the transfer was rejected
+    pub nak_tokens: Vec<String>,
+    /// Also accept an unterminated line ending in an ACK token, for receivers that
+    /// never send a newline
+    pub match_partial: bool,
+    /// Maximum wait for ACK/NAK once everything is sent
+    pub response_timeout: Duration,
+    /// Keep reading this long after an ACK so trailing output doesn't leak into the
+    /// next exchange
+    pub drain: Duration,
+    /// A zero-byte read means the peer closed the stream (sockets), not "no data yet"
+    pub stop_on_eof: bool,
+    pub encoding: FrameEncoding,
+}
+
+/// What came back from a transfer
+#[derive(Debug, Clone)]
+pub struct TransferOutcome {
+    pub bytes_sent: usize,
+    pub chunks_sent: usize,
+    /// Last `RESPONSE_CAP` bytes received
+    pub response: String,
+    pub saw_ack: bool,
+    pub nak_line: Option<String>,
+    /// Frame bytes the receiver credited (windowed pacing only, else 0)
+    pub acked_bytes: usize,
+}
+
+/// Incremental scanner for transfer responses.
+///
+/// Bytes are fed as they arrive and only complete lines are inspected, each exactly
+/// once. The scanner keeps its own partial-line buffer, so tokens are never lost to
+/// the trimming of the capped raw response or split across read boundaries.
+#[derive(Debug)]
+pub struct AckScanner {
+    ack_tokens: Vec<String>,
+    nak_tokens: Vec<String>,
+    credit_prefix: Option<&'static str>,
+    acked_bytes: usize,
+    partial: String,
+    overflowed: bool,
+    saw_ack: bool,
+    nak_line: Option<String>,
+}
+
+impl AckScanner {
+    /// Scanner accepting any of `ack_tokens` (whole line) and `nak_tokens` (line prefix)
+    pub fn new(ack_tokens: &[String], nak_tokens: &[String]) -> Self {
+        AckScanner {
+            ack_tokens: ack_tokens.to_vec(),
+            nak_tokens: nak_tokens.to_vec(),
+            credit_prefix: None,
+            acked_bytes: 0,
+            partial: String::new(),
+            overflowed: false,
+            saw_ack: false,
+            nak_line: None,
+        }
+    }
+
+    /// Also track flow-control credits: lines of the form "<prefix><bytes received>".
+    /// They only move the acknowledged count and are not passed on as output.
+    pub fn with_credit(mut self, prefix: &'static str) -> Self {
+        self.credit_prefix = Some(prefix);
+        self
+    }
+
+    /// Feed newly received text, calling `on_line` for every line it completes
+    pub fn feed<F>(&mut self, chunk: &str, mut on_line: F)
+    where
+        F: FnMut(&str),
+    {
+        for c in chunk.chars() {
+            if c == '\n' {
+                let line = std::mem::take(&mut self.partial);
+                self.process_line(line.trim(), &mut on_line);
+            } else {
+                self.partial.push(c);
+                if self.partial.len() > MAX_LINE_LEN {
+                    self.trim_partial();
+                }
+            }
+        }
+    }
+
+    /// Cut an overlong line down to its most recent half. Receivers that never send a
+    /// newline (e.g. ArduinoOTA printing a count per chunk) still end it with their ACK,
+    /// which `check_partial` looks for at the end. A NAK is recognized by its start, so
+    /// that is checked before the start goes.
+    fn trim_partial(&mut self) {
+        if !self.overflowed {
+            self.overflowed = true;
+            let nak = self.nak_tokens.iter().any(|t| !t.is_empty() && self.partial.starts_with(t.as_str()));
+            if nak && self.nak_line.is_none() {
+                self.nak_line = Some(self.partial.clone());
+            }
+        }
+        let mut keep_from = self.partial.len() - MAX_LINE_LEN / 2;
+        while !self.partial.is_char_boundary(keep_from) {
+            keep_from += 1;
+        }
+        self.partial.drain(..keep_from);
+    }
+
+    fn process_line<F>(&mut self, line: &str, on_line: &mut F)
+    where
+        F: FnMut(&str),
+    {
+        // The rest of an overlong line is passed on but can't be a token line
+        if std::mem::take(&mut self.overflowed) {
+            if !line.is_empty() {
+                on_line(line);
+            }
+            return;
+        }
+        if line.is_empty() {
+            return;
+        }
+        if let Some(count) = self.credit_prefix.and_then(|p| line.strip_prefix(p)) {
+            if let Ok(count) = count.trim().parse::<usize>() {
+                self.acked_bytes = self.acked_bytes.max(count);
+                return;
+            }
+        }
+        if self.ack_tokens.iter().any(|t| line == t) {
+            self.saw_ack = true;
+        }
+        if self.nak_tokens.iter().any(|t| !t.is_empty() && line.starts_with(t.as_str())) {
+            self.nak_line = Some(line.to_string());
+        }
+        on_line(line);
+    }
+
+    /// Take a reply packet of a COBS transfer: the verdict and credits come from its kind,
+    /// never from the text of device output. Returns the packet as a response line.
+    pub fn process_packet<F>(&mut self, packet: &Packet, mut on_line: F) -> String
+    where
+        F: FnMut(&str),
+    {
+        match packet.kind {
+            PacketKind::Ack => {
+                self.saw_ack = true;
+                self.ack_tokens.first().cloned().unwrap_or_else(|| "ACK".into())
+            }
+            PacketKind::Nak => {
+                let prefix = self.nak_tokens.first().map_or("NAK:", String::as_str);
+                let line = format!("{}{}", prefix, packet.text());
+                self.nak_line = Some(line.clone());
+                line
+            }
+            PacketKind::Credit => {
+                if let Some(count) = packet.credit() {
+                    self.acked_bytes = self.acked_bytes.max(count);
+                }
+                String::new()
+            }
+            PacketKind::Line => {
+                let line = packet.text();
+                if !line.is_empty() {
+                    on_line(&line);
+                }
+                line
+            }
+            PacketKind::Data => String::new(),
+        }
+    }
+
+    /// Accept an ACK token at the end of the line still waiting for its newline
+    pub fn check_partial(&mut self) {
+        let partial = self.partial.trim_end();
+        if self.ack_tokens.iter().any(|t| !t.is_empty() && partial.ends_with(t.as_str())) {
+            self.saw_ack = true;
+        }
+    }
+
+    /// Process a trailing line that never received its newline
+    pub fn finish<F>(&mut self, mut on_line: F)
+    where
+        F: FnMut(&str),
+    {
+        let line = std::mem::take(&mut self.partial);
+        self.process_line(line.trim(), &mut on_line);
+    }
+
+    pub fn saw_ack(&self) -> bool {
+        self.saw_ack
+    }
+
+    pub fn nak_line(&self) -> Option<&str> {
+        self.nak_line.as_deref()
+    }
+
+    /// Highest byte count the receiver has credited
+    pub fn acked_bytes(&self) -> usize {
+        self.acked_bytes
+    }
+
+    /// True once the receiver has answered with ACK or NAK
+    pub fn is_complete(&self) -> bool {
+        self.saw_ack || self.nak_line.is_some()
+    }
+}
+
+/// Keep only the last `RESPONSE_CAP` bytes of a response, on a char boundary
+fn cap_response(response: &mut String) {
+    if response.len() <= RESPONSE_CAP {
+        return;
+    }
+    let mut keep_from = response.len() - RESPONSE_CAP;
+    while !response.is_char_boundary(keep_from) {
+        keep_from += 1;
+    }
+    response.drain(..keep_from);
+}
+
+/// Length of a UTF-8 sequence cut off at the end of `bytes`, to be completed by the next read
+fn incomplete_utf8_tail(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let needed = match byte {
+            0xF0.. => 4,
+            0xE0.. => 3,
+            0xC0.. => 2,
+            _ => 1,
+        };
+        return if needed > back { back } else { 0 };
+    }
+    0
+}
+
+fn is_idle(e: &std::io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock)
+}
+
+/// Chunked, paced transfer of one framed payload over a byte stream, followed by
+/// waiting for the receiver's ACK/NAK and draining trailing output.
+///
+/// The stream's own read timeout bounds each individual read.
+pub struct FramedTransfer<'a, S: Read + Write + ?Sized> {
+    stream: &'a mut S,
+    spec: &'a FrameSpec,
+    scanner: AckScanner,
+    response: String,
+    buffer: Vec<u8>,
+    // Start of a character split across reads
+    utf8_tail: Vec<u8>,
+    packets: PacketReader,
+    cancelled: Option<&'a dyn Fn() -> bool>,
+}
+
+impl<'a, S: Read + Write + ?Sized> FramedTransfer<'a, S> {
+    pub fn new(stream: &'a mut S, spec: &'a FrameSpec) -> Self {
+        let mut scanner = AckScanner::new(&spec.ack_tokens, &spec.nak_tokens);
+        if let Pacing::Windowed { credit, .. } = spec.pacing {
+            scanner = scanner.with_credit(credit);
+        }
+        FramedTransfer {
+            stream,
+            scanner,
+            spec,
+            response: String::new(),
+            buffer: vec![0u8; 4096],
+            utf8_tail: Vec::new(),
+            packets: PacketReader::default(),
+            cancelled: None,
+        }
+    }
+
+    /// Give up between chunks and while waiting for replies once `cancelled` returns true
+    pub fn cancel_when(mut self, cancelled: &'a dyn Fn() -> bool) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    fn check_cancelled(&self, bytes_sent: usize) -> Result<(), TransferError> {
+        match self.cancelled {
+            Some(cancelled) if cancelled() => Err(TransferError::Cancelled(bytes_sent)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Send `payload` wrapped in the spec's markers. `on_chunk` gets the running byte
+    /// count after each chunk, `on_line` every complete line received.
+    pub fn send<C, L>(
+        mut self,
+        payload: &[u8],
+        mut on_chunk: C,
+        mut on_line: L,
+    ) -> Result<TransferOutcome, TransferError>
+    where
+        C: FnMut(usize, usize),
+        L: FnMut(&str),
+    {
+        let mut frame = Vec::with_capacity(payload.len() + 32);
+        frame.extend_from_slice(self.spec.start_marker.as_bytes());
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(self.spec.end_marker.as_bytes());
+        if self.spec.encoding == FrameEncoding::Cobs {
+            frame = cobs::encode_packet(PacketKind::Data, &frame);
+        }
+
+        let total = frame.len();
+        let mut bytes_sent = 0;
+        let mut chunks_sent = 0;
+        for chunk in frame.chunks(self.spec.chunk_size.max(1)) {
+            self.check_cancelled(bytes_sent)?;
+            if let Pacing::Windowed { window, timeout, .. } = self.spec.pacing {
+                // Stop sending into a receiver that already answered (e.g. NAKed the start line)
+                if !self.await_credit(bytes_sent, chunk.len(), window, timeout, &mut on_line)? {
+                    break;
+                }
+            }
+            self.stream.write_all(chunk).map_err(TransferError::Write)?;
+            self.stream.flush().map_err(TransferError::Write)?;
+            bytes_sent += chunk.len();
+            chunks_sent += 1;
+            on_chunk(bytes_sent, total);
+
+            match self.spec.pacing {
+                Pacing::Delay(delay) => std::thread::sleep(delay),
+                Pacing::AwaitReply => {
+                    self.read_once(&mut on_line)?;
+                }
+                Pacing::Windowed { .. } => {}
+            }
+        }
+
+        self.await_response(bytes_sent, &mut on_line)?;
+        if self.scanner.saw_ack() && !self.spec.drain.is_zero() {
+            self.drain();
+        }
+
+        Ok(TransferOutcome {
+            bytes_sent,
+            chunks_sent,
+            saw_ack: self.scanner.saw_ack(),
+            nak_line: self.scanner.nak_line().map(String::from),
+            acked_bytes: self.scanner.acked_bytes(),
+            response: self.response,
+        })
+    }
+
+    /// One read into the response and scanner; `Ok(false)` when the peer closed the stream
+    fn read_once<L: FnMut(&str)>(&mut self, on_line: &mut L) -> Result<bool, TransferError> {
+        match self.stream.read(&mut self.buffer) {
+            Ok(n) if n > 0 && self.spec.encoding == FrameEncoding::Cobs => {
+                for packet in self.packets.feed(&self.buffer[..n]) {
+                    // Bytes outside any packet are kept for the response only
+                    let text = match packet {
+                        Ok(packet) => self.scanner.process_packet(&packet, |line| on_line(line)),
+                        Err(raw) => String::from_utf8_lossy(&raw).trim().to_string(),
+                    };
+                    if !text.is_empty() {
+                        self.response.push_str(&text);
+                        self.response.push('\n');
+                    }
+                }
+                cap_response(&mut self.response);
+                Ok(true)
+            }
+            Ok(n) if n > 0 => {
+                let chunk = self.take_text(n);
+                self.response.push_str(&chunk);
+                cap_response(&mut self.response);
+                self.scanner.feed(&chunk, |line| on_line(line));
+                if self.spec.match_partial {
+                    self.scanner.check_partial();
+                }
+                Ok(true)
+            }
+            Ok(_) if self.spec.stop_on_eof => Ok(false),
+            Ok(_) => {
+                std::thread::sleep(Duration::from_millis(IDLE_POLL_MS));
+                Ok(true)
+            }
+            Err(ref e) if is_idle(e) => Ok(true),
+            Err(e) => Err(TransferError::Read(e)),
+        }
+    }
+
+    /// Text of the `n` bytes just read, holding back a character that isn't complete yet
+    fn take_text(&mut self, n: usize) -> String {
+        self.utf8_tail.extend_from_slice(&self.buffer[..n]);
+        let complete = self.utf8_tail.len() - incomplete_utf8_tail(&self.utf8_tail);
+        let text = String::from_utf8_lossy(&self.utf8_tail[..complete]).to_string();
+        self.utf8_tail.drain(..complete);
+        text
+    }
+
+    /// Read until sending `next` more bytes after `sent` keeps within `window`
+    /// unacknowledged bytes; `Ok(false)` when the receiver answered or closed the stream instead
+    fn await_credit<L: FnMut(&str)>(
+        &mut self,
+        sent: usize,
+        next: usize,
+        window: usize,
+        timeout: Duration,
+        on_line: &mut L,
+    ) -> Result<bool, TransferError> {
+        let start = Instant::now();
+        while (sent + next).saturating_sub(self.scanner.acked_bytes()) > window {
+            self.check_cancelled(sent)?;
+            if self.scanner.is_complete() {
+                return Ok(false);
+            }
+            if start.elapsed() >= timeout {
+                return Err(TransferError::Stalled(self.scanner.acked_bytes()));
+            }
+            if !self.read_once(on_line)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn await_response<L: FnMut(&str)>(&mut self, bytes_sent: usize, on_line: &mut L) -> Result<(), TransferError> {
+        let start = Instant::now();
+        while !self.scanner.is_complete() && start.elapsed() < self.spec.response_timeout {
+            self.check_cancelled(bytes_sent)?;
+            if !self.read_once(on_line)? {
+                break;
+            }
+        }
+        if !self.scanner.is_complete() {
+            self.scanner.finish(|line| on_line(line));
+        }
+        Ok(())
+    }
+
+    /// Read briefly after an ACK so trailing logs don't pollute the next command
+    fn drain(&mut self) {
+        let start = Instant::now();
+        while start.elapsed() < self.spec.drain {
+            match self.stream.read(&mut self.buffer) {
+                Ok(n) if n > 0 => {
+                    let text = self.take_text(n);
+                    self.response.push_str(&text);
+                    cap_response(&mut self.response);
+                }
+                Ok(_) if self.spec.stop_on_eof => break,
+                Ok(_) => {}
+                Err(_) => break,
+            }
+            std::thread::sleep(Duration::from_millis(IDLE_POLL_MS));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::collections::VecDeque;
+
+    type Responder = Box<dyn FnMut(&[u8], usize) -> Vec<u8>>;
+
+    /// In-memory stream: reads come from a script, and a responder may queue a reply
+    /// to each write. With nothing queued a read times out, or ends the stream if `eof`.
+    #[derive(Default)]
+    struct MockStream {
+        written: Vec<u8>,
+        reads: VecDeque<Vec<u8>>,
+        responder: Option<Responder>,
+        eof: bool,
+    }
+
+    impl MockStream {
+        fn with_reads(reads: &[&[u8]]) -> Self {
+            MockStream { reads: reads.iter().map(|r| r.to_vec()).collect(), ..Default::default() }
+        }
+
+        fn responding(responder: impl FnMut(&[u8], usize) -> Vec<u8> + 'static) -> Self {
+            MockStream { responder: Some(Box::new(responder)), ..Default::default() }
+        }
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some(mut next) = self.reads.pop_front() else {
+                return if self.eof { Ok(0) } else { Err(ErrorKind::TimedOut.into()) };
+            };
+            if next.len() > buf.len() {
+                self.reads.push_front(next.split_off(buf.len()));
+            }
+            buf[..next.len()].copy_from_slice(&next);
+            Ok(next.len())
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            if let Some(responder) = self.responder.as_mut() {
+                let reply = responder(buf, self.written.len());
+                if !reply.is_empty() {
+                    self.reads.push_back(reply);
+                }
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn spec(pacing: Pacing) -> FrameSpec {
+        FrameSpec {
+            start_marker: "<S>".into(),
+            end_marker: "<E>".into(),
+            chunk_size: 4,
+            pacing,
+            ack_tokens: vec!["ACK".into()],
+            nak_tokens: vec!["NAK:".into()],
+            match_partial: false,
+            response_timeout: Duration::from_millis(200),
+            drain: Duration::ZERO,
+            stop_on_eof: false,
+            encoding: FrameEncoding::Text,
+        }
+    }
+
+    fn run(
+        stream: &mut MockStream,
+        spec: &FrameSpec,
+        payload: &[u8],
+    ) -> (Result<TransferOutcome, TransferError>, Vec<usize>, Vec<String>) {
+        let mut progress = Vec::new();
+        let mut lines = Vec::new();
+        let result = FramedTransfer::new(stream, spec).send(
+            payload,
+            |sent, _| progress.push(sent),
+            |line| lines.push(line.to_string()),
+        );
+        (result, progress, lines)
+    }
+
+    #[test]
+    fn splits_frame_into_chunks() {
+        let mut stream = MockStream::with_reads(&[b"ACK\n"]);
+        let (result, progress, lines) = run(&mut stream, &spec(Pacing::Delay(Duration::ZERO)), b"abcdefghij");
+        let outcome = result.expect("transfer succeeds");
+        assert_eq!(stream.written, b"<S>abcdefghij<E>");
+        assert_eq!(progress, vec![4, 8, 12, 16]);
+        assert_eq!(outcome.bytes_sent, 16);
+        assert_eq!(outcome.chunks_sent, 4);
+        assert!(outcome.saw_ack);
+        assert_eq!(lines, vec!["ACK"]);
+    }
+
+    #[test]
+    fn delay_pauses_after_each_chunk() {
+        let mut stream = MockStream::with_reads(&[b"ACK\n"]);
+        let started = Instant::now();
+        let (result, _, _) = run(&mut stream, &spec(Pacing::Delay(Duration::from_millis(10))), b"abcdef");
+        assert!(result.expect("transfer succeeds").saw_ack);
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn await_reply_reads_after_each_chunk() {
+        let mut stream = MockStream::responding(|_, total| {
+            if total == 12 {
+                b"got 12\nACK\n".to_vec()
+            } else {
+                format!("got {}\n", total).into_bytes()
+            }
+        });
+        let (result, _, lines) = run(&mut stream, &spec(Pacing::AwaitReply), b"abcdef");
+        assert!(result.expect("transfer succeeds").saw_ack);
+        assert_eq!(lines, vec!["got 4", "got 8", "got 12", "ACK"]);
+    }
+
+    #[test]
+    fn unterminated_counts_longer_than_a_line_still_end_in_ack() {
+        // ArduinoOTA answers every chunk with its size and no newline, then "OK"
+        let payload = vec![0xA5; 8000];
+        let mut stream = MockStream::responding(|chunk, total| {
+            let mut reply = chunk.len().to_string();
+            if total == 8000 {
+                reply.push_str("OK");
+            }
+            reply.into_bytes()
+        });
+        let spec = FrameSpec {
+            start_marker: String::new(),
+            end_marker: String::new(),
+            chunk_size: 10,
+            ack_tokens: vec!["OK".into()],
+            match_partial: true,
+            ..spec(Pacing::AwaitReply)
+        };
+        let (result, _, lines) = run(&mut stream, &spec, &payload);
+        let outcome = result.expect("transfer succeeds");
+        assert_eq!(outcome.chunks_sent, 800);
+        assert!(outcome.response.len() > MAX_LINE_LEN);
+        assert!(outcome.saw_ack);
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn windowed_waits_for_credit() {
+        // Credit only every second chunk, so the window of two chunks fills up
+        let mut stream = MockStream::responding(|_, total| match total {
+            16 => b"WIN:16\nACK\n".to_vec(),
+            t if t % 8 == 0 => format!("WIN:{}\n", t).into_bytes(),
+            _ => Vec::new(),
+        });
+        let pacing = Pacing::Windowed { window: 8, credit: "WIN:", timeout: Duration::from_millis(200) };
+        let (result, _, lines) = run(&mut stream, &spec(pacing), b"abcdefghij");
+        let outcome = result.expect("transfer succeeds");
+        assert_eq!(outcome.bytes_sent, 16);
+        assert_eq!(outcome.acked_bytes, 16);
+        assert!(outcome.saw_ack);
+        assert_eq!(lines, vec!["ACK"]);
+    }
+
+    #[test]
+    fn windowed_stalls_without_credit() {
+        let mut stream = MockStream::default();
+        let pacing = Pacing::Windowed { window: 8, credit: "WIN:", timeout: Duration::from_millis(50) };
+        let (result, progress, _) = run(&mut stream, &spec(pacing), b"abcdefghij");
+        assert!(matches!(result, Err(TransferError::Stalled(0))));
+        assert_eq!(progress, vec![4, 8]);
+        assert_eq!(stream.written, b"<S>abcde");
+    }
+
+    #[test]
+    fn cancel_stops_between_chunks() {
+        let chunks = Cell::new(0);
+        let cancelled = || chunks.get() >= 2;
+        let mut stream = MockStream::with_reads(&[b"ACK\n"]);
+        let spec = spec(Pacing::Delay(Duration::ZERO));
+        let result = FramedTransfer::new(&mut stream, &spec)
+            .cancel_when(&cancelled)
+            .send(b"abcdefghij", |_, _| chunks.set(chunks.get() + 1), |_| {});
+        assert!(matches!(result, Err(TransferError::Cancelled(8))));
+        assert_eq!(stream.written, b"<S>abcde");
+    }
+
+    #[test]
+    fn drain_keeps_output_after_ack_out_of_lines() {
+        let mut stream = MockStream::with_reads(&[b"ACK\n", b"saved 10 bytes\n"]);
+        let spec = FrameSpec { drain: Duration::from_millis(50), ..spec(Pacing::Delay(Duration::ZERO)) };
+        let (result, _, lines) = run(&mut stream, &spec, b"abcdefghij");
+        let outcome = result.expect("transfer succeeds");
+        assert_eq!(outcome.response, "ACK\nsaved 10 bytes\n");
+        assert_eq!(lines, vec!["ACK"]);
+        assert!(stream.reads.is_empty());
+    }
+
+    #[test]
+    fn cap_response_cuts_on_a_char_boundary() {
+        let mut response = "é".repeat(RESPONSE_CAP / 2) + "a";
+        cap_response(&mut response);
+        assert_eq!(response.len(), RESPONSE_CAP - 1);
+        assert!(response.starts_with('é'));
+        assert!(response.ends_with('a'));
+
+        let mut short = String::from("ACK\n");
+        cap_response(&mut short);
+        assert_eq!(short, "ACK\n");
+    }
+
+    #[test]
+    fn stop_on_eof_ends_the_wait() {
+        let mut stream = MockStream { eof: true, ..Default::default() };
+        let spec = FrameSpec {
+            response_timeout: Duration::from_secs(10),
+            stop_on_eof: true,
+            ..spec(Pacing::Delay(Duration::ZERO))
+        };
+        let started = Instant::now();
+        let (result, _, _) = run(&mut stream, &spec, b"abcdefghij");
+        let outcome = result.expect("transfer returns");
+        assert!(!outcome.saw_ack);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn characters_split_across_reads_survive() {
+        let degree = "°".as_bytes();
+        let mut first = b"temp 2".to_vec();
+        first.push(degree[0]);
+        let mut second = degree[1..].to_vec();
+        second.extend_from_slice(b"C\nACK\n");
+        let mut stream = MockStream::with_reads(&[&first, &second]);
+        let (result, _, lines) = run(&mut stream, &spec(Pacing::Delay(Duration::ZERO)), b"abcdefghij");
+        let outcome = result.expect("transfer succeeds");
+        assert_eq!(outcome.response, "temp 2°C\nACK\n");
+        assert_eq!(lines, vec!["temp 2°C", "ACK"]);
+    }
+
+    #[test]
+    fn ack_after_capped_output_is_found() {
+        let log = "log line that keeps the receiver busy\n".repeat(RESPONSE_CAP / 16);
+        let mut stream = MockStream::with_reads(&[log.as_bytes(), b"AC", b"K\n"]);
+        let (result, _, lines) = run(&mut stream, &spec(Pacing::Delay(Duration::ZERO)), b"abcdefghij");
+        let outcome = result.expect("transfer succeeds");
+        assert!(outcome.saw_ack);
+        assert!(outcome.response.len() <= RESPONSE_CAP);
+        assert!(outcome.response.ends_with("ACK\n"));
+        assert_eq!(lines.last().map(String::as_str), Some("ACK"));
+    }
+
+    fn new_scanner() -> AckScanner {
+        AckScanner::new(&["ACK".into()], &["NAK:".into()])
+    }
+
+    fn feed(scanner: &mut AckScanner, chunk: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        scanner.feed(chunk, |line| lines.push(line.to_string()));
+        lines
+    }
+
+    #[test]
+    fn scanner_joins_a_token_split_across_feeds() {
+        let mut scanner = new_scanner();
+        assert!(feed(&mut scanner, "A").is_empty());
+        assert!(feed(&mut scanner, "C").is_empty());
+        assert!(!scanner.saw_ack());
+        assert_eq!(feed(&mut scanner, "K\r\n"), vec!["ACK"]);
+        assert!(scanner.saw_ack());
+    }
+
+    #[test]
+    fn scanner_matches_whole_lines_only() {
+        let mut scanner = new_scanner();
+        feed(&mut scanner, "waiting for ACK\nACKNOWLEDGED\n");
+        assert!(!scanner.saw_ack());
+        assert!(!scanner.is_complete());
+    }
+
+    #[test]
+    fn scanner_checks_the_unterminated_line() {
+        let mut scanner = new_scanner();
+        feed(&mut scanner, "stored 16 bytes ACK");
+        assert!(!scanner.saw_ack());
+        scanner.check_partial();
+        assert!(scanner.saw_ack());
+
+        let mut scanner = new_scanner();
+        feed(&mut scanner, "ACK pending");
+        scanner.check_partial();
+        assert!(!scanner.saw_ack());
+    }
+
+    #[test]
+    fn scanner_survives_an_overflowing_line() {
+        let mut scanner = new_scanner();
+        assert!(feed(&mut scanner, &"x".repeat(3 * MAX_LINE_LEN)).is_empty());
+        let lines = feed(&mut scanner, "ACK\nACK\n");
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].len() <= MAX_LINE_LEN);
+        assert!(lines[0].ends_with("xACK"));
+        assert_eq!(lines[1], "ACK");
+        assert!(scanner.saw_ack());
+
+        let mut scanner = new_scanner();
+        feed(&mut scanner, &"x".repeat(3 * MAX_LINE_LEN));
+        feed(&mut scanner, "\nACK");
+        scanner.finish(|_| {});
+        assert!(scanner.saw_ack());
+    }
+
+    #[test]
+    fn scanner_recognizes_nak_prefixes() {
+        let mut scanner = new_scanner();
+        assert_eq!(feed(&mut scanner, "NAK: bad checksum\n"), vec!["NAK: bad checksum"]);
+        assert_eq!(scanner.nak_line(), Some("NAK: bad checksum"));
+        assert!(scanner.is_complete());
+        assert!(!scanner.saw_ack());
+
+        let mut scanner = new_scanner();
+        feed(&mut scanner, &format!("NAK: {}", "y".repeat(2 * MAX_LINE_LEN)));
+        assert!(scanner.nak_line().is_some_and(|line| line.starts_with("NAK: ")));
+    }
+
+    #[test]
+    fn scanner_tracks_credits_without_passing_them_on() {
+        let mut scanner = new_scanner().with_credit("WIN:");
+        assert_eq!(feed(&mut scanner, "WIN:64\nWIN:32\nWIN:x\n"), vec!["WIN:x"]);
+        assert_eq!(scanner.acked_bytes(), 64);
+    }
+
+    #[test]
+    fn incomplete_utf8_tail_finds_cut_sequences() {
+        assert_eq!(incomplete_utf8_tail(b"abc"), 0);
+        assert_eq!(incomplete_utf8_tail("é".as_bytes()), 0);
+        assert_eq!(incomplete_utf8_tail(&"é".as_bytes()[..1]), 1);
+        assert_eq!(incomplete_utf8_tail(&"€".as_bytes()[..2]), 2);
+        assert_eq!(incomplete_utf8_tail(&"😀".as_bytes()[..3]), 3);
+        assert_eq!(incomplete_utf8_tail(&[b'a', 0x80]), 0);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::scheduler::{self, Scheduler, Scope};
+use crate::serial::{SerialConnection, SerialState};
+use crate::session::now_millis;
+use crate::settings::{AppSettings, SettingsState};
+use crate::signals::SIGNALS_DIR;
+use crate::storage;
+use crate::supervisor::{ConnectionSupervisor, TaskRole};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+const SETTINGS_FILE: &str = "settings.json";
+// Written and removed again to prove the library folder takes writes
+const PROBE_FILE: &str = ".health-probe";
+// Free space below which logs and reports soon stop being written
+const LOW_DISK_MB: u64 = 500;
+const CRITICAL_DISK_MB: u64 = 50;
+// App-scoped tasks started at launch that should run for the whole session
+const BACKGROUND_TASKS: &[&str] = &["library scan", "port watch", "resume watch", "retention cleanup", "standby heartbeat"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Ok,
+    Warning,
+    Failed,
+}
+
+/// Outcome of checking one subsystem
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheck {
+    pub subsystem: String,
+    pub state: HealthState,
+    pub detail: String,
+}
+
+/// Result of `health_check` (and the CLI's `doctor`); `overall` is the worst check
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub checked_at: u64,
+    pub overall: HealthState,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn new(checks: Vec<HealthCheck>) -> Self {
+        HealthReport {
+            checked_at: now_millis(),
+            overall: checks.iter().map(|c| c.state).max().unwrap_or(HealthState::Ok),
+            checks,
+        }
+    }
+}
+
+fn check(subsystem: &str, state: HealthState, detail: impl Into<String>) -> HealthCheck {
+    HealthCheck {
+        subsystem: subsystem.to_string(),
+        state,
+        detail: detail.into(),
+    }
+}
+
+/// The signal library folder exists or can be created, and takes writes
+pub fn library_storage(dir: &Path) -> HealthCheck {
+    let library = dir.join(SIGNALS_DIR);
+    let probe = library.join(PROBE_FILE);
+    let written = fs::create_dir_all(&library)
+        .and_then(|_| fs::write(&probe, b"ok"))
+        .and_then(|_| fs::remove_file(&probe));
+    match written {
+        Ok(()) => check("library storage", HealthState::Ok, format!("{} is writable", library.display())),
+        Err(e) => check("library storage", HealthState::Failed, format!("{} is not writable: {}", library.display(), e)),
+    }
+}
+
+/// The settings file parses; a missing one means defaults are in use
+pub fn settings_file(dir: &Path) -> HealthCheck {
+    let path = dir.join(SETTINGS_FILE);
+    match fs::read_to_string(&path) {
+        Err(_) => check("settings", HealthState::Warning, "No settings file yet, defaults in use"),
+        Ok(content) => match serde_json::from_str::<AppSettings>(&content) {
+            Ok(_) => check("settings", HealthState::Ok, format!("{} loaded", path.display())),
+            Err(e) => check("settings", HealthState::Failed, format!("{} is unreadable, defaults in use: {}", path.display(), e)),
+        },
+    }
+}
+
+/// Room left on the disk holding the data folder, where logs and reports go
+pub fn disk_space(dir: &Path) -> HealthCheck {
+    match fs2::available_space(dir) {
+        Ok(bytes) => {
+            let mb = bytes / (1024 * 1024);
+            let state = if mb < CRITICAL_DISK_MB {
+                HealthState::Failed
+            } else if mb < LOW_DISK_MB {
+                HealthState::Warning
+            } else {
+                HealthState::Ok
+            };
+            check("disk space", state, format!("{} MB free", mb))
+        }
+        Err(e) => check("disk space", HealthState::Warning, format!("Couldn't read free space: {}", e)),
+    }
+}
+
+/// Serial ports can be enumerated (drivers and permissions are in order)
+pub fn port_enumeration() -> HealthCheck {
+    match SerialConnection::list_ports() {
+        Ok(ports) => check("serial ports", HealthState::Ok, format!("{} port(s) found", ports.len())),
+        Err(e) => check("serial ports", HealthState::Failed, e.to_string()),
+    }
+}
+
+/// Checks that need the running app: the serial worker and link, loaded settings and
+/// background tasks, on top of the file-level checks the CLI runs too
+pub async fn run(app: &AppHandle) -> HealthReport {
+    let (link, connected) = serial_link(app).await;
+    let mut checks = vec![link];
+
+    let revision = app.state::<SettingsState>().revision();
+    checks.push(check("settings loaded", HealthState::Ok, format!("Revision {}", revision)));
+    checks.extend(background_tasks(app, connected));
+
+    // File probes and port enumeration can be slow; keep them off the runtime
+    let data_dir = storage::data_dir(app);
+    let blocking_checks = scheduler::blocking(move || {
+        let mut checks = match data_dir {
+            Ok(dir) => vec![library_storage(&dir), settings_file(&dir), disk_space(&dir)],
+            Err(e) => vec![check("storage", HealthState::Failed, e)],
+        };
+        checks.push(port_enumeration());
+        checks
+    });
+    checks.extend(blocking_checks.await.unwrap_or_default());
+    HealthReport::new(checks)
+}
+
+/// The serial worker answers, and whether a device is connected
+async fn serial_link(app: &AppHandle) -> (HealthCheck, bool) {
+    let state = app.state::<SerialState>();
+    match state.with(|c| c.port_name().map(String::from)).await {
+        Ok(Some(port)) => (check("serial link", HealthState::Ok, format!("Connected to {}", port)), true),
+        Ok(None) => (check("serial link", HealthState::Ok, "Not connected"), false),
+        Err(e) => (check("serial link", HealthState::Failed, format!("Serial worker not responding: {}", e)), false),
+    }
+}
+
+/// Launch-time background tasks are still scheduled, and the port reader runs while connected
+fn background_tasks(app: &AppHandle, connected: bool) -> Vec<HealthCheck> {
+    let running = app.state::<Scheduler>().running(Scope::App);
+    let missing: Vec<&str> = BACKGROUND_TASKS
+        .iter()
+        .copied()
+        .filter(|name| !running.iter().any(|r| r.as_str() == *name))
+        .collect();
+    let tasks = if missing.is_empty() {
+        check("background tasks", HealthState::Ok, format!("{} running", running.len()))
+    } else {
+        check("background tasks", HealthState::Failed, format!("Stopped: {}", missing.join(", ")))
+    };
+
+    let reader = app.state::<ConnectionSupervisor>().get(TaskRole::Reader);
+    let reader = match (connected, reader) {
+        (true, Some(_)) => check("port reader", HealthState::Ok, "Running"),
+        (true, None) => check("port reader", HealthState::Failed, "Connected but the port reader isn't running"),
+        (false, _) => check("port reader", HealthState::Ok, "Idle while disconnected"),
+    };
+    vec![tasks, reader]
+}
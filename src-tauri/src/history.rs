@@ -0,0 +1,80 @@
+use crate::recent_output::CapturedLine;
+use crate::serial::UploadResult;
+use crate::session::now_millis;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+const HISTORY_FILE: &str = "upload_history.json";
+// Oldest records are dropped beyond this many entries
+const MAX_RECORDS: usize = 1000;
+
+/// One config upload attempt, kept across app restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadRecord {
+    pub timestamp: u64,
+    pub signal_name: Option<String>,
+    pub port_name: Option<String>,
+    /// USB serial number of the unit the config went to
+    #[serde(default)]
+    pub usb_serial: Option<String>,
+    pub success: bool,
+    pub bytes_sent: usize,
+    pub error_message: Option<String>,
+    /// Operator note explaining why this config was flashed
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Failed uploads only: what the device printed in the seconds up to the failure
+    #[serde(default)]
+    pub device_log: Vec<CapturedLine>,
+}
+
+impl UploadRecord {
+    pub fn new(
+        signal_name: Option<String>,
+        port_name: Option<String>,
+        usb_serial: Option<String>,
+        result: &UploadResult,
+        note: Option<String>,
+    ) -> Self {
+        UploadRecord {
+            timestamp: now_millis(),
+            signal_name,
+            port_name,
+            usb_serial,
+            success: result.success,
+            bytes_sent: result.bytes_sent,
+            error_message: result.error_message.clone(),
+            note,
+            device_log: Vec::new(),
+        }
+    }
+}
+
+fn history_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join(HISTORY_FILE))
+}
+
+/// Load the upload history, oldest first
+pub fn load_history(app: &AppHandle) -> Result<Vec<UploadRecord>, String> {
+    let path = history_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Append a record to the upload history
+pub fn append_record(app: &AppHandle, record: UploadRecord) -> Result<(), String> {
+    let mut records = load_history(app).unwrap_or_default();
+    records.push(record);
+    if records.len() > MAX_RECORDS {
+        let excess = records.len() - MAX_RECORDS;
+        records.drain(..excess);
+    }
+    let json = serde_json::to_string_pretty(&records).map_err(|e| e.to_string())?;
+    fs::write(history_path(app)?, json).map_err(|e| e.to_string())
+}
@@ -0,0 +1,81 @@
+use crate::attention;
+use crate::session::now_millis;
+use crate::settings::{HookEvent, SettingsState};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const WEBHOOK_TIMEOUT_MS: u64 = 5000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HookPayload {
+    pub event: HookEvent,
+    pub timestamp: u64,
+    pub port_name: Option<String>,
+    pub message: String,
+}
+
+/// Tracks whether the signal is supposed to be running, so a status report of
+/// "stopped" can be told apart from an operator-requested stop
+#[derive(Clone, Default)]
+pub struct HookState {
+    expected_running: Arc<AtomicBool>,
+    last_fault: Arc<Mutex<Option<String>>>,
+}
+
+impl HookState {
+    pub fn set_expected_running(&self, running: bool) {
+        self.expected_running.store(running, Ordering::SeqCst);
+    }
+
+    /// Whether the signal is supposed to be running right now
+    pub fn expects_running(&self) -> bool {
+        self.expected_running.load(Ordering::SeqCst)
+    }
+
+    /// Returns true once when the device reports stopped while it should be running
+    pub fn check_unexpected_stop(&self, running: bool) -> bool {
+        !running && self.expected_running.swap(false, Ordering::SeqCst)
+    }
+
+    /// Returns true when a fault is reported that differs from the last one seen
+    pub fn check_new_fault(&self, fault: Option<&str>) -> bool {
+        let Ok(mut last) = self.last_fault.lock() else {
+            return false;
+        };
+        let changed = fault.is_some() && last.as_deref() != fault;
+        *last = fault.map(String::from);
+        changed
+    }
+}
+
+/// Raise the attention cues for the event and POST it to the configured webhook in the
+/// background, each as enabled in settings
+pub fn fire(app: &AppHandle, event: HookEvent, port_name: Option<String>, message: impl Into<String>) {
+    let settings = app.state::<SettingsState>().get();
+    let message = message.into();
+    attention::raise(app, &settings.attention, event, &message);
+
+    let webhooks = settings.webhooks;
+    if !webhooks.enabled || webhooks.url.is_empty() || !webhooks.events.contains(&event) {
+        return;
+    }
+
+    let payload = HookPayload {
+        event,
+        timestamp: now_millis(),
+        port_name,
+        message,
+    };
+
+    std::thread::spawn(move || {
+        let result = ureq::post(&webhooks.url)
+            .timeout(Duration::from_millis(WEBHOOK_TIMEOUT_MS))
+            .send_json(&payload);
+        if let Err(e) = result {
+            eprintln!("[HOOKS] Webhook for {:?} failed: {}", payload.event, e);
+        }
+    });
+}
@@ -0,0 +1,135 @@
+use crate::events::LIBRARY_ISSUES_EVENT;
+use crate::scheduler::{self, Scheduler, Scope};
+use crate::session::now_millis;
+use crate::settings::{SettingsState, ValidationRules};
+use crate::signals::{self, SignalConfig, MAX_CKP_EDGES, MAX_CMP_EDGES};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// One problem found in a stored signal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryIssue {
+    pub filename: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSummary {
+    pub finished_at: u64,
+    pub scanned: usize,
+    pub healthy: usize,
+    pub issues: Vec<LibraryIssue>,
+}
+
+// Result of the most recent scan
+#[derive(Clone, Default)]
+pub struct LibraryScanState(pub Arc<Mutex<Option<ScanSummary>>>);
+
+impl LibraryScanState {
+    pub fn last(&self) -> Option<ScanSummary> {
+        self.0.lock().ok().and_then(|s| s.clone())
+    }
+}
+
+fn check_channel(label: &str, blob: &str, max_edges: usize) -> Result<(), String> {
+    let count = signals::parse_sig1(blob).map_err(|e| format!("{}: {}", label, e))?;
+    if count > max_edges {
+        return Err(format!("{}: {} edges exceeds firmware limit of {}", label, count, max_edges));
+    }
+    Ok(())
+}
+
+/// Schema, validation (including the user `rules`) and SIG1 decode/CRC checks for one stored file
+pub fn check_file(content: &str, rules: &ValidationRules) -> Vec<String> {
+    let config: SignalConfig = match serde_json::from_str(content) {
+        Ok(config) => config,
+        Err(e) => return vec![format!("Unreadable signal file: {}", e)],
+    };
+
+    let mut problems = Vec::new();
+    if let Err(e) = signals::check_rules(&config, rules) {
+        problems.push(e.to_string());
+    }
+    if let Err(e) = check_channel("CKP", &config.ckp, MAX_CKP_EDGES) {
+        problems.push(e);
+    }
+    for (label, blob) in [("CMP1", &config.cmp1), ("CMP2", &config.cmp2)] {
+        if let Some(blob) = blob {
+            if let Err(e) = check_channel(label, blob, MAX_CMP_EDGES) {
+                problems.push(e);
+            }
+        }
+    }
+    problems
+}
+
+/// Validate every stored signal, remember the summary and emit issues if any
+pub fn scan(app: &AppHandle) -> Result<ScanSummary, String> {
+    let dir = signals::get_signals_dir(app).map_err(|e| e.to_string())?;
+    let rules = signals::validation_rules(app);
+    let mut scanned = 0;
+    let mut issues = Vec::new();
+
+    let entries = fs::read_dir(&dir).map_err(|e| e.to_string())?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        scanned += 1;
+
+        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+        let problems = match fs::read_to_string(&path) {
+            Ok(content) => check_file(&content, &rules),
+            Err(e) => vec![format!("Cannot read file: {}", e)],
+        };
+        issues.extend(problems.into_iter().map(|message| LibraryIssue {
+            filename: filename.clone(),
+            message,
+        }));
+    }
+
+    let unhealthy = issues
+        .iter()
+        .map(|i| i.filename.as_str())
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+    let summary = ScanSummary {
+        finished_at: now_millis(),
+        scanned,
+        healthy: scanned - unhealthy,
+        issues,
+    };
+
+    if let Ok(mut last) = app.state::<LibraryScanState>().0.lock() {
+        *last = Some(summary.clone());
+    }
+    if !summary.issues.is_empty() {
+        let _ = app.emit(LIBRARY_ISSUES_EVENT, &summary);
+    }
+
+    Ok(summary)
+}
+
+/// Rescan the library periodically according to settings (0 minutes disables it)
+pub fn start_periodic_scan(app: AppHandle) {
+    let tasks = app.state::<Scheduler>().inner().clone();
+    tasks.spawn("library scan", Scope::App, move |mut token| async move {
+        loop {
+            let minutes = app.state::<SettingsState>().get().library_scan_interval_mins;
+            if minutes > 0 {
+                let scan_app = app.clone();
+                if let Some(Err(e)) = scheduler::blocking(move || scan(&scan_app)).await {
+                    eprintln!("[LIBRARY] Integrity scan failed: {}", e);
+                }
+            }
+            // While disabled, look at the setting again once a minute
+            if !token.sleep(Duration::from_secs(60 * minutes.max(1) as u64)).await {
+                break;
+            }
+        }
+    });
+}
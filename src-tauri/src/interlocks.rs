@@ -0,0 +1,57 @@
+use crate::serial::{is_recognizable_reply, SerialConnection};
+use crate::settings::InterlockSettings;
+use serde::Serialize;
+
+/// One pre-run check that did not pass
+#[derive(Debug, Clone, Serialize)]
+pub struct InterlockFailure {
+    pub interlock: &'static str,
+    pub message: String,
+}
+
+/// Run the enabled pre-run checks against the device; empty when running is allowed.
+/// `confirmed` is the operator's explicit go-ahead, for the confirmation interlock.
+pub fn check(connection: &mut SerialConnection, settings: &InterlockSettings, confirmed: bool) -> Vec<InterlockFailure> {
+    let mut failures = Vec::new();
+    let needs_status = settings.handshake || settings.signal_loaded || settings.rpm_ceiling.is_some();
+
+    if needs_status {
+        match connection.get_status() {
+            Ok(status) => {
+                if settings.handshake && !(status.connected && is_recognizable_reply(&status.raw_response)) {
+                    failures.push(InterlockFailure {
+                        interlock: "handshake",
+                        message: "Device did not answer the status query".into(),
+                    });
+                }
+                if settings.signal_loaded && !connection.config_uploaded() && status.loaded_signal.is_none() {
+                    failures.push(InterlockFailure {
+                        interlock: "signal_loaded",
+                        message: "No signal uploaded this session and the device reports none loaded".into(),
+                    });
+                }
+                if let Some(ceiling) = settings.rpm_ceiling {
+                    if status.rpm > ceiling {
+                        failures.push(InterlockFailure {
+                            interlock: "rpm_ceiling",
+                            message: format!("RPM {} is above the {} ceiling", status.rpm, ceiling),
+                        });
+                    }
+                }
+            }
+            Err(e) => failures.push(InterlockFailure {
+                interlock: "handshake",
+                message: format!("Status query failed: {}", e),
+            }),
+        }
+    }
+
+    if settings.require_confirmation && !confirmed {
+        failures.push(InterlockFailure {
+            interlock: "confirmation",
+            message: "Operator confirmation required before running".into(),
+        });
+    }
+
+    failures
+}
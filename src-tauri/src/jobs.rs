@@ -0,0 +1,255 @@
+use crate::events::{Throttle, JOB_PROGRESS_EVENT};
+use crate::scheduler::{self, CancelToken, Scheduler, Scope, TaskHandle};
+use crate::session::now_millis;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+// Finished jobs kept around for get_job after their last event
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// Long operations that run as jobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    ConfigUpload,
+    DeviceFileUpload,
+    DeviceFileDownload,
+    Firmware,
+    StepTest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// A long operation as reported to the UI, in `job://progress` and by `get_job`
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub kind: JobKind,
+    pub label: String,
+    pub state: JobState,
+    /// Progress in operation-specific units (bytes, percent) out of `total`; 0/0 if unknown
+    pub done: u64,
+    pub total: u64,
+    pub message: Option<String>,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    /// What the operation returned (e.g. an upload result), once succeeded
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+struct Entry {
+    job: Job,
+    task: Option<TaskHandle>,
+    // Cancelled before its task was stored; the task is cancelled as soon as it is
+    cancel_requested: bool,
+}
+
+/// Registry of running and recently finished jobs.
+///
+/// Every long operation is started through `start`, gets an ID the UI can follow,
+/// and is cancelled the same way through its scheduler task.
+#[derive(Clone, Default)]
+pub struct JobManager {
+    jobs: Arc<Mutex<BTreeMap<u64, Entry>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+/// Handed to a job's work for reporting progress and noticing cancellation
+pub struct JobContext {
+    id: u64,
+    jobs: JobManager,
+    token: CancelToken,
+    events: Throttle<Job>,
+}
+
+impl JobContext {
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// The job's cancellation, for blocking work that runs elsewhere (e.g. on the serial worker)
+    pub fn token(&self) -> CancelToken {
+        self.token.clone()
+    }
+
+    /// Stop point for the work: an error once the job was cancelled
+    pub fn checkpoint(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err("Cancelled".into());
+        }
+        Ok(())
+    }
+
+    pub fn progress(&mut self, done: u64, total: u64, message: Option<String>) {
+        let job = self.jobs.update(self.id, |job| {
+            job.done = done;
+            job.total = total;
+            if message.is_some() {
+                job.message = message;
+            }
+        });
+        if let Some(job) = job {
+            self.events.emit(job);
+        }
+    }
+}
+
+impl JobManager {
+    /// Run `work` off the async runtime as a new job and return its ID right away.
+    /// The outcome arrives as the job's final `job://progress` event.
+    pub fn start<T, F>(&self, app: &AppHandle, kind: JobKind, label: impl Into<String>, work: F) -> u64
+    where
+        T: Serialize + 'static,
+        F: FnOnce(&mut JobContext) -> Result<T, String> + Send + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let job = Job {
+            id,
+            kind,
+            label: label.into(),
+            state: JobState::Running,
+            done: 0,
+            total: 0,
+            message: None,
+            started_at: now_millis(),
+            finished_at: None,
+            result: None,
+            error: None,
+        };
+        let name = format!("job {} ({})", id, job.label);
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.insert(
+                id,
+                Entry {
+                    job: job.clone(),
+                    task: None,
+                    cancel_requested: false,
+                },
+            );
+        }
+        let _ = app.emit(JOB_PROGRESS_EVENT, job);
+
+        let scheduler = app.state::<Scheduler>().inner().clone();
+        let jobs = self.clone();
+        let app = app.clone();
+        let task = scheduler.spawn(&name, Scope::App, move |token| async move {
+            let mut context = JobContext {
+                id,
+                jobs: jobs.clone(),
+                token: token.clone(),
+                events: Throttle::new(&app, JOB_PROGRESS_EVENT),
+            };
+            let outcome = scheduler::blocking(move || {
+                work(&mut context).and_then(|value| serde_json::to_value(value).map_err(|e| e.to_string()))
+            })
+            .await;
+            jobs.finish(&app, id, outcome, token.is_cancelled());
+        });
+
+        if let Ok(mut jobs) = self.jobs.lock() {
+            if let Some(entry) = jobs.get_mut(&id).filter(|e| e.job.state == JobState::Running) {
+                if entry.cancel_requested {
+                    task.cancel();
+                }
+                entry.task = Some(task);
+            }
+        }
+        id
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.jobs.lock().ok()?.get(&id).map(|e| e.job.clone())
+    }
+
+    /// Running and recently finished jobs, oldest first
+    pub fn list(&self) -> Vec<Job> {
+        self.jobs
+            .lock()
+            .map(|jobs| jobs.values().map(|e| e.job.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn active(&self) -> Vec<Job> {
+        self.list()
+            .into_iter()
+            .filter(|j| j.state == JobState::Running)
+            .collect()
+    }
+
+    /// Ask a running job to stop; it ends as cancelled at its next checkpoint
+    pub fn cancel(&self, id: u64) -> Result<(), String> {
+        let task = {
+            let mut jobs = self.jobs.lock().map_err(|e| e.to_string())?;
+            let entry = jobs.get_mut(&id).ok_or_else(|| format!("No job {}", id))?;
+            if entry.job.state != JobState::Running {
+                return Err(format!("Job {} has already finished", id));
+            }
+            entry.job.message = Some("Cancelling...".into());
+            // Still being spawned: `start` cancels the task once it has it
+            entry.cancel_requested = true;
+            entry.task.clone()
+        };
+        if let Some(task) = task {
+            task.cancel();
+        }
+        Ok(())
+    }
+
+    fn update<U: FnOnce(&mut Job)>(&self, id: u64, apply: U) -> Option<Job> {
+        let mut jobs = self.jobs.lock().ok()?;
+        let entry = jobs.get_mut(&id)?;
+        apply(&mut entry.job);
+        Some(entry.job.clone())
+    }
+
+    fn finish(&self, app: &AppHandle, id: u64, outcome: Option<Result<serde_json::Value, String>>, cancelled: bool) {
+        let job = self.update(id, |job| {
+            job.finished_at = Some(now_millis());
+            match outcome {
+                // Work that completed despite a late cancel still did its thing
+                Some(Ok(value)) => {
+                    job.state = JobState::Succeeded;
+                    job.result = Some(value);
+                }
+                Some(Err(e)) => {
+                    job.state = if cancelled { JobState::Cancelled } else { JobState::Failed };
+                    job.error = Some(e);
+                }
+                None => {
+                    job.state = JobState::Failed;
+                    job.error = Some("Job panicked".into());
+                }
+            }
+        });
+        if let Ok(mut jobs) = self.jobs.lock() {
+            if let Some(entry) = jobs.get_mut(&id) {
+                entry.task = None;
+            }
+            let finished: Vec<u64> = jobs
+                .iter()
+                .filter(|(_, e)| e.job.state != JobState::Running)
+                .map(|(&id, _)| id)
+                .collect();
+            for old in finished.iter().take(finished.len().saturating_sub(MAX_FINISHED_JOBS)) {
+                jobs.remove(old);
+            }
+        }
+        if let Some(job) = job {
+            if job.state != JobState::Succeeded {
+                eprintln!("[JOBS] {} ended {:?}: {}", job.label, job.state, job.error.as_deref().unwrap_or(""));
+            }
+            let _ = app.emit(JOB_PROGRESS_EVENT, job);
+        }
+    }
+}
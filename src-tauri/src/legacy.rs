@@ -0,0 +1,102 @@
+use crate::signals::{self, SignalConfig, SignalError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+const LEGACY_EXTENSION: &str = "sgn";
+
+/// Per-file outcome of a legacy migration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegacyImportResult {
+    pub source: String,
+    pub success: bool,
+    /// Library filename the signal was saved as
+    pub filename: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Parse an INI-style `.sgn` file from the old desktop tool:
+///
+/// ```text
+/// [Signal]
+/// Name=VW 60-2
+/// CKP=SIG1...
+/// CMP1=SIG1...
+/// ```
+///
+/// Keys are case-insensitive, `;` and `#` start comments, and empty CMP values
+/// mean the channel is unused.
+pub fn parse_sgn(content: &str) -> Result<SignalConfig, SignalError> {
+    let mut name = None;
+    let mut ckp = None;
+    let mut cmp1 = None;
+    let mut cmp2 = None;
+
+    for raw in content.lines() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        let value = (!value.is_empty()).then_some(value);
+
+        match key.trim().to_ascii_lowercase().as_str() {
+            "name" => name = value,
+            "ckp" => ckp = value,
+            "cmp1" => cmp1 = value,
+            "cmp2" => cmp2 = value,
+            _ => {}
+        }
+    }
+
+    Ok(SignalConfig {
+        name: name.ok_or_else(|| SignalError::ParseError("Missing Name key".into()))?,
+        ckp: ckp.ok_or_else(|| SignalError::ParseError("Missing CKP key".into()))?,
+        cmp1,
+        cmp2,
+    })
+}
+
+fn migrate_file(app: &AppHandle, path: &Path) -> Result<String, SignalError> {
+    let content = fs::read_to_string(path)?;
+    let config = parse_sgn(&content)?;
+    signals::save_signal(app, &config)
+}
+
+/// Convert every `.sgn` file in `dir` and save it to the library
+pub fn migrate_dir(app: &AppHandle, dir: &Path) -> Result<Vec<LegacyImportResult>, SignalError> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(LEGACY_EXTENSION))
+        })
+        .collect();
+    paths.sort();
+
+    Ok(paths
+        .iter()
+        .map(|path| {
+            let source = path.to_string_lossy().to_string();
+            match migrate_file(app, path) {
+                Ok(filename) => LegacyImportResult {
+                    source,
+                    success: true,
+                    filename: Some(filename),
+                    error: None,
+                },
+                Err(e) => LegacyImportResult {
+                    source,
+                    success: false,
+                    filename: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .collect())
+}
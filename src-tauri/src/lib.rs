@@ -1,35 +1,156 @@
+mod attention;
+mod claim;
+mod cobs;
+pub mod cli;
 mod commands;
+mod concurrency;
+mod critical;
+mod debug_commands;
+mod device_backup;
+mod device_command;
+mod device_fs;
+mod events;
+mod extensions;
+mod firmware;
+mod framed;
+mod health;
+mod history;
+mod hooks;
+mod integrity;
+mod interlocks;
+mod jobs;
+mod legacy;
+mod ota;
+mod port_cache;
+mod port_history;
+mod port_watch;
+mod preview;
+mod profiles;
+mod recent_output;
+mod reconnect;
+mod resume_watch;
+mod retention;
+mod running_guard;
+mod scheduler;
 mod serial;
+mod serial_reader;
+mod session;
+mod settings;
+mod setup;
+mod signal_index;
+mod signal_link;
+mod signal_qr;
+mod signal_sync;
 pub mod signals;
+mod sigpack;
+mod snapshot;
+mod soak;
+mod standby;
+mod status_history;
+mod step_test;
+mod storage;
+mod supervisor;
+mod timing;
+mod tray;
+mod upload_queue;
 
-use commands::*;
+use concurrency::ConcurrencyGate;
+use critical::CriticalSection;
+use extensions::ExtensionRegistry;
+use hooks::HookState;
+use integrity::LibraryScanState;
+use jobs::JobManager;
+use port_cache::PortCache;
+use recent_output::RecentOutput;
+use scheduler::Scheduler;
 use serial::SerialState;
+use session::SessionLog;
+use signal_qr::QrAssembly;
+use settings::SettingsState;
+use soak::SoakState;
+use standby::StandbyPool;
+use storage::ActiveStorage;
+use status_history::StatusHistory;
+use supervisor::ConnectionSupervisor;
+use tray::TrayState;
+use upload_queue::UploadQueue;
+use tauri::Manager;
+use tauri_plugin_deep_link::DeepLinkExt;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let scheduler = Scheduler::default();
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_deep_link::init())
         .manage(SerialState::default())
-        .invoke_handler(tauri::generate_handler![
-            list_ports,
-            connect,
-            disconnect,
-            run_signal,
-            stop_signal,
-            increase_rpm,
-            decrease_rpm,
-            save_to_nvs,
-            reset_defaults,
-            get_status,
-            upload_config,
-            is_connected,
-            // Signal library commands
-            import_signal,
-            list_saved_signals,
-            load_saved_signal,
-            delete_saved_signal,
-            upload_saved_signal
-        ])
+        .manage(ConnectionSupervisor::new(scheduler.clone()))
+        .manage(scheduler)
+        .manage(CriticalSection::default())
+        .manage(ConcurrencyGate::default())
+        .manage(PortCache::default())
+        .manage(SessionLog::default())
+        .manage(RecentOutput::default())
+        .manage(HookState::default())
+        .manage(LibraryScanState::default())
+        .manage(SoakState::default())
+        .manage(StatusHistory::default())
+        .manage(QrAssembly::default())
+        .manage(TrayState::default())
+        .manage(ExtensionRegistry::default())
+        .manage(StandbyPool::default())
+        .manage(JobManager::default())
+        .manage(UploadQueue::default())
+        .setup(|app| {
+            // Settings decide the storage fallback order, so read them from the
+            // default location first, then settle on the final storage
+            let handle = app.handle();
+            let configured = settings::load_settings(handle).storage;
+            let custom_dir = configured.custom_dir.as_deref();
+            let order = storage::effective_order(&configured.fallback_order, custom_dir);
+            app.manage(ActiveStorage::new(storage::resolve(handle, &order, custom_dir)?));
+            let mut loaded = settings::load_settings(handle);
+            if claim::ensure_owner_id(&mut loaded) {
+                if let Err(e) = settings::save_settings(handle, &loaded) {
+                    eprintln!("[STORAGE] Failed to save the new owner ID: {}", e);
+                }
+            }
+            let aliases = loaded.command_aliases.validate().is_ok().then(|| loaded.command_aliases.clone());
+            let (tap, sink) = (events::terminal_tap(handle), events::unsolicited_sink(handle));
+            app.state::<SerialState>().post(move |connection| {
+                if let Some(aliases) = aliases {
+                    connection.set_command_aliases(aliases);
+                }
+                connection.set_traffic_tap(tap);
+                connection.set_unsolicited_sink(sink);
+            });
+            app.manage(SettingsState::new(loaded));
+            app.state::<UploadQueue>().restore(handle);
+            extensions::load(handle);
+            integrity::start_periodic_scan(handle.clone());
+            resume_watch::start(handle.clone());
+            retention::start(handle.clone());
+            port_watch::start(handle.clone());
+            standby::start_heartbeat(handle);
+            tray::init(handle);
+
+            // esp32sig:// links, both the one the app was launched with and later ones
+            #[cfg(any(windows, target_os = "linux"))]
+            if let Err(e) = app.deep_link().register_all() {
+                eprintln!("[LIBRARY] Failed to register signal link scheme: {}", e);
+            }
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                let urls: Vec<String> = urls.iter().map(|u| u.to_string()).collect();
+                signal_link::handle_opened(handle, &urls);
+            }
+            let link_handle = handle.clone();
+            app.deep_link().on_open_url(move |event| {
+                let urls: Vec<String> = event.urls().iter().map(|u| u.to_string()).collect();
+                signal_link::handle_opened(&link_handle, &urls);
+            });
+            Ok(())
+        })
+        .invoke_handler(commands::handler())
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
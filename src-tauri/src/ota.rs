@@ -0,0 +1,263 @@
+use crate::framed::{FrameEncoding, FrameSpec, FramedTransfer, Pacing, TransferError};
+use serde::{Deserialize, Serialize};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// ArduinoOTA (espota) defaults used by the ESP32 firmware
+const OTA_PORT: u16 = 3232;
+const OTA_FLASH_COMMAND: u8 = 0;
+const INVITE_TIMEOUT_MS: u64 = 5000;
+const CONNECT_TIMEOUT_MS: u64 = 10000;
+const CHUNK_SIZE: usize = 1024;
+const FINISH_TIMEOUT_MS: u64 = 30000;
+// How long to wait for the device to come back after rebooting into the new image
+const REBOOT_WAIT_MS: u64 = 20000;
+
+#[derive(Error, Debug)]
+pub enum OtaError {
+    #[error("Failed to read firmware image: {0}")]
+    ImageError(String),
+    #[error("Network error: {0}")]
+    NetworkError(String),
+    #[error("Device rejected update: {0}")]
+    Rejected(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+}
+
+impl Serialize for OtaError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl From<std::io::Error> for OtaError {
+    fn from(err: std::io::Error) -> Self {
+        OtaError::NetworkError(err.to_string())
+    }
+}
+
+impl From<TransferError> for OtaError {
+    fn from(err: TransferError) -> Self {
+        OtaError::NetworkError(err.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OtaProgress {
+    pub phase: String,
+    pub bytes_sent: u64,
+    pub total: u64,
+}
+
+/// What the device said about the image it runs after rebooting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageCheck {
+    /// Reported the MD5 of the image just sent
+    Running,
+    /// Reported another image: the bootloader fell back to the previous one
+    RolledBack,
+    /// The protocol profile has no image probe, or the device never answered it
+    Unverified,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OtaResult {
+    pub success: bool,
+    pub bytes_sent: u64,
+    pub md5: String,
+    pub image_check: ImageCheck,
+    pub message: String,
+    /// Set when the update could not be verified: a rollback after reboot would go
+    /// unnoticed, so the device may still run the previous image
+    pub warning: Option<String>,
+}
+
+fn progress(phase: &str, bytes_sent: u64, total: u64) -> OtaProgress {
+    OtaProgress {
+        phase: phase.to_string(),
+        bytes_sent,
+        total,
+    }
+}
+
+/// Invite the device to start an update; it answers "OK" and then connects back to us
+fn invite(host: &str, local_port: u16, size: usize, md5: &str) -> Result<(), OtaError> {
+    let udp = UdpSocket::bind("0.0.0.0:0")?;
+    udp.set_read_timeout(Some(Duration::from_millis(INVITE_TIMEOUT_MS)))?;
+
+    let invitation = format!("{} {} {} {}\n", OTA_FLASH_COMMAND, local_port, size, md5);
+    udp.send_to(invitation.as_bytes(), (host, OTA_PORT))?;
+
+    let mut buffer = [0u8; 128];
+    let n = match udp.recv(&mut buffer) {
+        Ok(n) => n,
+        Err(ref e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+            return Err(OtaError::Timeout(format!("no answer from {}:{}", host, OTA_PORT)));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let reply = String::from_utf8_lossy(&buffer[..n]).trim().to_string();
+    if reply.starts_with("AUTH") {
+        return Err(OtaError::Rejected("password-protected OTA is not supported".into()));
+    }
+    if reply != "OK" {
+        return Err(OtaError::Rejected(reply));
+    }
+    Ok(())
+}
+
+fn accept_device(listener: &TcpListener) -> Result<TcpStream, OtaError> {
+    listener.set_nonblocking(true)?;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(CONNECT_TIMEOUT_MS) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(stream);
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(OtaError::Timeout("device never connected back for the transfer".into()))
+}
+
+/// Ask the rebooted device which image it runs, with the profile's `ota_image_probe`
+/// request answered by `FW:<md5>`
+fn probe_running_md5(host: &str, probe: &str) -> Option<String> {
+    let udp = UdpSocket::bind("0.0.0.0:0").ok()?;
+    udp.set_read_timeout(Some(Duration::from_millis(1000))).ok()?;
+
+    let request = format!("{}\n", probe);
+    let mut buffer = [0u8; 128];
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(REBOOT_WAIT_MS) {
+        if udp.send_to(request.as_bytes(), (host, OTA_PORT)).is_ok() {
+            if let Ok(n) = udp.recv(&mut buffer) {
+                let reply = String::from_utf8_lossy(&buffer[..n]);
+                if let Some(md5) = reply.trim().strip_prefix("FW:") {
+                    return Some(md5.trim().to_lowercase());
+                }
+            }
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+    None
+}
+
+/// Push a firmware image to `host` using the ESP32 OTA protocol. This is a standalone
+/// ArduinoOTA client talking to the device's WiFi address, not a transport for the
+/// app's connection, which stays serial. `probe` is the protocol profile's
+/// `ota_image_probe`; without it a rollback can't be detected and the result says so.
+pub fn update<F>(host: &str, path: &Path, probe: Option<&str>, mut on_progress: F) -> Result<OtaResult, OtaError>
+where
+    F: FnMut(OtaProgress),
+{
+    let image = std::fs::read(path).map_err(|e| OtaError::ImageError(e.to_string()))?;
+    if image.is_empty() {
+        return Err(OtaError::ImageError("image is empty".into()));
+    }
+    let total = image.len() as u64;
+    let md5 = format!("{:x}", md5::compute(&image));
+
+    let listener = TcpListener::bind("0.0.0.0:0")?;
+    let local_port = listener.local_addr()?.port();
+
+    on_progress(progress("inviting", 0, total));
+    invite(host, local_port, image.len(), &md5)?;
+
+    let mut stream = accept_device(&listener)?;
+    stream.set_read_timeout(Some(Duration::from_millis(INVITE_TIMEOUT_MS)))?;
+    stream.set_write_timeout(Some(Duration::from_millis(INVITE_TIMEOUT_MS)))?;
+
+    // The device answers each chunk with the number of bytes it took from that chunk,
+    // with no newline, then "OK" once Update.end() succeeded
+    let spec = FrameSpec {
+        start_marker: String::new(),
+        end_marker: String::new(),
+        chunk_size: CHUNK_SIZE,
+        pacing: Pacing::AwaitReply,
+        ack_tokens: vec!["OK".to_string()],
+        nak_tokens: Vec::new(),
+        match_partial: true,
+        response_timeout: Duration::from_millis(FINISH_TIMEOUT_MS),
+        drain: Duration::ZERO,
+        stop_on_eof: true,
+        encoding: FrameEncoding::Text,
+    };
+    let outcome = FramedTransfer::new(&mut stream, &spec).send(
+        &image,
+        |sent, _| on_progress(progress("transferring", sent as u64, total)),
+        |_| {},
+    )?;
+    let bytes_sent = outcome.bytes_sent as u64;
+    on_progress(progress("verifying", bytes_sent, total));
+
+    if !outcome.saw_ack {
+        let last = outcome.response.lines().last().unwrap_or("").trim().to_string();
+        if last.is_empty() {
+            return Err(OtaError::Timeout("no final confirmation from device".into()));
+        }
+        return Ok(OtaResult {
+            success: false,
+            bytes_sent,
+            md5,
+            image_check: ImageCheck::Unverified,
+            message: format!("Device did not confirm the image: {}", last),
+            warning: None,
+        });
+    }
+
+    // Rollback detection: the bootloader falls back to the previous image if the new one fails
+    let image_check = match probe {
+        Some(probe) => {
+            on_progress(progress("rebooting", bytes_sent, total));
+            match probe_running_md5(host, probe) {
+                Some(running) if running == md5 => ImageCheck::Running,
+                Some(_) => ImageCheck::RolledBack,
+                None => ImageCheck::Unverified,
+            }
+        }
+        None => ImageCheck::Unverified,
+    };
+    let message = match (image_check, probe) {
+        (ImageCheck::Running, _) => "Update applied, device is running the new image",
+        (ImageCheck::RolledBack, _) => "Device rolled back to the previous image after reboot",
+        (ImageCheck::Unverified, Some(_)) => "Update sent, but the device did not report its image after reboot",
+        (ImageCheck::Unverified, None) => "Update sent, but not verified",
+    };
+    let warning = match (image_check, probe) {
+        (ImageCheck::Unverified, Some(_)) => Some(
+            "The device did not report its running image after reboot; it may have rolled back to the previous firmware"
+                .to_string(),
+        ),
+        (ImageCheck::Unverified, None) => Some(
+            "The protocol profile has no OTA image probe, so a rollback to the previous firmware goes unnoticed; set ota_image_probe to verify updates"
+                .to_string(),
+        ),
+        _ => None,
+    };
+    if let Some(ref warning) = warning {
+        eprintln!("[OTA] {}: {}", host, warning);
+        on_progress(progress("unverified", bytes_sent, total));
+    }
+
+    Ok(OtaResult {
+        success: image_check != ImageCheck::RolledBack,
+        bytes_sent,
+        md5,
+        image_check,
+        message: message.to_string(),
+        warning,
+    })
+}
@@ -0,0 +1,91 @@
+use crate::serial::{PortInfo, PortKind, SerialConnection, SerialError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Name fragments of OS-provided ports that are never an ESP32 (macOS debug consoles,
+// Bluetooth serial services)
+const IRRELEVANT_NAME_PARTS: [&str; 4] = ["Bluetooth", "debug-console", "wlan-debug", "BLTH"];
+
+// USB-serial bridges found on ESP32 boards, as (VID, PID); `None` matches any product
+const ESP32_BRIDGE_IDS: [(u16, Option<u16>); 10] = [
+    // Silicon Labs CP210x
+    (0x10C4, Some(0xEA60)),
+    (0x10C4, Some(0xEA70)),
+    // WCH CH340, CH343, CH9102
+    (0x1A86, Some(0x7523)),
+    (0x1A86, Some(0x55D3)),
+    (0x1A86, Some(0x55D4)),
+    // FTDI FT232R, FT2232, FT232H, FT231X
+    (0x0403, Some(0x6001)),
+    (0x0403, Some(0x6010)),
+    (0x0403, Some(0x6014)),
+    (0x0403, Some(0x6015)),
+    // Espressif native USB (USB-Serial/JTAG on S3/C3/C6, CDC on S2)
+    (0x303A, None),
+];
+
+// Enumeration is slow on Windows with many Bluetooth COM ports; results this fresh are reused
+const PORT_CACHE_TTL_MS: u64 = 2000;
+
+/// Cached port enumeration.
+///
+/// The lock is held while enumerating, so a burst of calls runs the OS query once and
+/// the callers queued behind it get the fresh result.
+#[derive(Clone, Default)]
+pub struct PortCache(Arc<Mutex<Option<CachedPorts>>>);
+
+// When the ports were enumerated, and what was found
+type CachedPorts = (Instant, Vec<PortInfo>);
+
+impl PortCache {
+    /// Cached ports if still fresh, otherwise a new enumeration; `refresh` forces one
+    pub fn list(&self, refresh: bool) -> Result<Vec<PortInfo>, SerialError> {
+        let mut cached = self
+            .0
+            .lock()
+            .map_err(|e| SerialError::OpenError(e.to_string()))?;
+
+        if !refresh {
+            if let Some((at, ports)) = cached.as_ref() {
+                if at.elapsed() < Duration::from_millis(PORT_CACHE_TTL_MS) {
+                    return Ok(ports.clone());
+                }
+            }
+        }
+
+        let ports = SerialConnection::list_ports()?;
+        *cached = Some((Instant::now(), ports.clone()));
+        Ok(ports)
+    }
+
+    /// Drop the cached list, e.g. when ports appear or disappear
+    pub fn invalidate(&self) {
+        if let Ok(mut cached) = self.0.lock() {
+            *cached = None;
+        }
+    }
+}
+
+/// Whether a port can't plausibly be the ESP32: Bluetooth links, built-in UARTs with no
+/// USB device behind them, and OS debug consoles
+pub fn is_irrelevant(port: &PortInfo) -> bool {
+    if matches!(port.port_type, PortKind::Bluetooth | PortKind::Pci) {
+        return true;
+    }
+    // Legacy on-board UARTs Linux always lists, whether or not anything is attached
+    if port.port_type == PortKind::Unknown && port.name.starts_with("/dev/ttyS") {
+        return true;
+    }
+    IRRELEVANT_NAME_PARTS.iter().any(|part| port.name.contains(part))
+}
+
+/// Whether a port's USB IDs belong to a bridge chip ESP32 boards use (CP210x, CH34x,
+/// FTDI) or to Espressif's native USB
+pub fn is_esp32_bridge(port: &PortInfo) -> bool {
+    let (Some(vid), Some(pid)) = (port.vid, port.pid) else {
+        return false;
+    };
+    ESP32_BRIDGE_IDS
+        .iter()
+        .any(|&(known_vid, known_pid)| known_vid == vid && known_pid.is_none_or(|p| p == pid))
+}
@@ -0,0 +1,56 @@
+use crate::serial::PortInfo;
+use crate::session::now_millis;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+const PORT_HISTORY_FILE: &str = "port_history.json";
+
+/// Successful connections made through one port
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortUsage {
+    pub last_connected: u64,
+    pub connect_count: u32,
+}
+
+fn history_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join(PORT_HISTORY_FILE))
+}
+
+/// Port usage keyed by port name (empty if nothing was recorded yet)
+pub fn load(app: &AppHandle) -> Result<HashMap<String, PortUsage>, String> {
+    let path = history_path(app)?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Note a completed connection on `port_name`
+pub fn record_connection(app: &AppHandle, port_name: &str) -> Result<(), String> {
+    let mut history = load(app).unwrap_or_default();
+    let usage = history.entry(port_name.to_string()).or_insert(PortUsage {
+        last_connected: 0,
+        connect_count: 0,
+    });
+    usage.last_connected = now_millis();
+    usage.connect_count += 1;
+
+    let json = serde_json::to_string_pretty(&history).map_err(|e| e.to_string())?;
+    fs::write(history_path(app)?, json).map_err(|e| e.to_string())
+}
+
+/// Fill in `previously_used`/`last_connected` on listed ports
+pub fn annotate(app: &AppHandle, ports: &mut [PortInfo]) {
+    let history = load(app).unwrap_or_default();
+    for port in ports {
+        if let Some(usage) = history.get(&port.name) {
+            port.previously_used = true;
+            port.last_connected = Some(usage.last_connected);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::events::{PORT_ADDED_EVENT, PORT_REMOVED_EVENT};
+use crate::port_cache::{self, PortCache};
+use crate::port_history;
+use crate::scheduler::{self, Scheduler, Scope};
+use crate::serial::PortInfo;
+use crate::settings::SettingsState;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// How often ports are enumerated to spot adapters being plugged in or out
+const WATCH_INTERVAL_MS: u64 = 1500;
+
+/// Enumerate ports in the background and emit `port://added` / `port://removed` as
+/// adapters come and go, so the port list stays current without a manual refresh.
+/// Ports hidden from `list_ports` by settings are left out here too.
+pub fn start(app: AppHandle) {
+    let tasks = app.state::<Scheduler>().inner().clone();
+    tasks.spawn("port watch", Scope::App, move |mut token| async move {
+        let mut known: Option<Vec<PortInfo>> = None;
+        while token.sleep(Duration::from_millis(WATCH_INTERVAL_MS)).await {
+            let cache = app.state::<PortCache>().inner().clone();
+            // Slow on Windows with many Bluetooth ports; keep it off the runtime
+            let ports = match scheduler::blocking(move || cache.list(true)).await {
+                Some(Ok(ports)) => visible(&app, ports),
+                Some(Err(e)) => {
+                    eprintln!("[SERIAL] Port enumeration failed: {}", e);
+                    continue;
+                }
+                None => continue,
+            };
+            // The first enumeration is what the UI already has
+            if let Some(previous) = known.replace(ports.clone()) {
+                for port in ports.iter().filter(|p| !previous.iter().any(|q| q.name == p.name)) {
+                    let _ = app.emit(PORT_ADDED_EVENT, port);
+                }
+                for port in previous.iter().filter(|p| !ports.iter().any(|q| q.name == p.name)) {
+                    let _ = app.emit(PORT_REMOVED_EVENT, port);
+                }
+            }
+        }
+    });
+}
+
+/// The ports `list_ports` would show
+fn visible(app: &AppHandle, mut ports: Vec<PortInfo>) -> Vec<PortInfo> {
+    if app.state::<SettingsState>().get().hide_irrelevant_ports {
+        ports.retain(|p| !port_cache::is_irrelevant(p));
+    }
+    port_history::annotate(app, &mut ports);
+    ports
+}
@@ -0,0 +1,95 @@
+use crate::integrity;
+use crate::serial::frame_config;
+use crate::settings::ValidationRules;
+use crate::signals;
+use crate::timing::UploadEstimate;
+use serde::{Deserialize, Serialize};
+
+// Longest pretty-printed JSON kept in a preview
+const PREVIEW_MAX_CHARS: usize = 500;
+// Channel keys in the order the firmware reads them
+const CHANNELS: [&str; 3] = ["CKP", "CMP1", "CMP2"];
+
+/// Size of one channel blob in a config
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChannelSize {
+    pub channel: String,
+    pub bytes: usize,
+    /// Decoded edge count, `None` when the blob does not decode
+    pub edges: Option<usize>,
+}
+
+/// Human-readable summary of a config about to be (or just) uploaded
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigPreview {
+    pub name: Option<String>,
+    /// Pretty-printed JSON, cut to `PREVIEW_MAX_CHARS`
+    pub pretty_json: String,
+    pub truncated: bool,
+    pub channels: Vec<ChannelSize>,
+    /// Size of the config JSON itself
+    pub payload_bytes: usize,
+    /// Size on the wire, including the `<CFG>`/`<END>` markers
+    pub frame_bytes: usize,
+}
+
+/// What an upload would send, and anything that would make the device reject it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PreflightReport {
+    pub preview: ConfigPreview,
+    pub problems: Vec<String>,
+    /// How long the upload should take over the current connection
+    pub estimate: Option<UploadEstimate>,
+}
+
+/// Build a preview of a config JSON string; invalid JSON still gets sizes and a raw excerpt
+pub fn build_preview(config: &str) -> ConfigPreview {
+    let value = serde_json::from_str::<serde_json::Value>(config).ok();
+
+    let full = value
+        .as_ref()
+        .and_then(|v| serde_json::to_string_pretty(v).ok())
+        .unwrap_or_else(|| config.to_string());
+    let truncated = full.chars().count() > PREVIEW_MAX_CHARS;
+    let pretty_json = if truncated {
+        format!("{}…", full.chars().take(PREVIEW_MAX_CHARS).collect::<String>())
+    } else {
+        full
+    };
+
+    let name = value
+        .as_ref()
+        .and_then(|v| v.get("name"))
+        .and_then(|n| n.as_str())
+        .map(String::from);
+
+    let channels = CHANNELS
+        .iter()
+        .filter_map(|channel| {
+            let blob = value.as_ref()?.get(*channel)?.as_str()?;
+            Some(ChannelSize {
+                channel: channel.to_string(),
+                bytes: blob.len(),
+                edges: signals::parse_sig1(blob).ok(),
+            })
+        })
+        .collect();
+
+    ConfigPreview {
+        name,
+        pretty_json,
+        truncated,
+        channels,
+        payload_bytes: config.len(),
+        frame_bytes: frame_config(config).len(),
+    }
+}
+
+/// Preview plus the same checks the library scan applies to stored signals
+pub fn preflight(config: &str) -> PreflightReport {
+    PreflightReport {
+        preview: build_preview(config),
+        problems: integrity::check_file(config, &ValidationRules::default()),
+        estimate: None,
+    }
+}
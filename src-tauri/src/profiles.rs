@@ -0,0 +1,302 @@
+use crate::debug_commands::{self, DebugCommand};
+use crate::framed::FrameEncoding;
+use crate::serial::SerialParams;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use thiserror::Error;
+
+const PROFILES_FILE: &str = "profiles.json";
+// Bumped whenever the bundle layout changes incompatibly
+const BUNDLE_VERSION: u32 = 1;
+// First firmware release speaking the protocol the default profile describes
+const MIN_FIRMWARE_VERSION: &str = "1.0.0";
+
+#[derive(Error, Debug)]
+pub enum ProfileError {
+    #[error("IO Error: {0}")]
+    IoError(String),
+    #[error("Parse Error: {0}")]
+    ParseError(String),
+    #[error("Not Found: {0}")]
+    NotFound(String),
+    #[error("Unsupported profile bundle version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Invalid profile: {0}")]
+    Invalid(String),
+}
+
+impl From<std::io::Error> for ProfileError {
+    fn from(err: std::io::Error) -> Self {
+        ProfileError::IoError(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ProfileError {
+    fn from(err: serde_json::Error) -> Self {
+        ProfileError::ParseError(err.to_string())
+    }
+}
+
+/// Verbosity of the firmware's serial logging
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DeviceLogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl DeviceLogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceLogLevel::Error => "ERROR",
+            DeviceLogLevel::Warn => "WARN",
+            DeviceLogLevel::Info => "INFO",
+            DeviceLogLevel::Debug => "DEBUG",
+        }
+    }
+}
+
+/// Protocol parameters for a particular firmware build
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProtocolProfile {
+    pub name: String,
+    /// Delay between sending a command and reading its response
+    pub response_delay_ms: u64,
+    /// Maximum wait for ACK/NAK after a config upload
+    pub upload_timeout_ms: u64,
+    /// Log level command with a `{level}` placeholder, e.g. `<LOG {level}>`;
+    /// `None` when the firmware has no log level control
+    pub log_level_command: Option<String>,
+    /// Level applied while a config upload is in progress
+    pub upload_log_level: DeviceLogLevel,
+    /// Level restored once the upload finishes
+    pub default_log_level: DeviceLogLevel,
+    /// Quiet request with a `{seconds}` placeholder, e.g. `<QUIET {seconds}>`, asking the
+    /// firmware to suppress periodic status prints; `None` when unsupported
+    pub quiet_command: Option<String>,
+    /// Length of the quiet window requested before a config upload
+    pub quiet_window_secs: u32,
+    /// Request that makes the firmware print its stored config in a `<CFG>`...`<END>`
+    /// frame, e.g. `<GETCFG>`; `None` when the firmware can't read its config back
+    pub config_readback_command: Option<String>,
+    /// Prompt printed by the firmware after each command (e.g. `>`); when set,
+    /// command responses end as soon as it arrives instead of on read timeout
+    pub prompt: Option<String>,
+    /// Silence after connecting that means the boot output is over (0 skips the settle phase)
+    pub settle_quiet_ms: u64,
+    /// Longest wait for the boot output to end before talking to the device anyway
+    pub settle_max_ms: u64,
+    /// Text the firmware prints once it has booted (e.g. `READY`); when set, settling
+    /// ends as soon as a line containing it arrives
+    pub ready_banner: Option<String>,
+    /// Lines accepted as a successful config upload (exact match, e.g. `ACK`, `OK`, `CFG_OK`)
+    pub ack_tokens: Vec<String>,
+    /// Line prefixes that mean the upload was rejected; the rest of the line is the reason
+    pub nak_tokens: Vec<String>,
+    /// Request answered with a `FW:<version> BUILD:<build>` line, sent right after
+    /// connecting; `None` when the firmware only reports its version in the status
+    pub version_command: Option<String>,
+    /// Oldest firmware version that speaks this protocol; older builds still connect,
+    /// with a warning
+    pub min_firmware_version: Option<String>,
+    /// Highest config framing version offered before an upload; 1 skips the offer and
+    /// always sends the legacy `<CFG>` frame
+    pub config_protocol_version: u32,
+    /// Send configs zlib-compressed (`ENC=DEFLATE` on the start line) from framing v2 on;
+    /// only for firmware built with inflate support
+    pub compress_uploads: bool,
+    /// Framing of config uploads and device file transfers; `cobs` for firmware that
+    /// takes binary packets, so payloads holding `<END>` or `ACK` can't end a frame early
+    pub framing: FrameEncoding,
+    /// Firmware diagnostics offered in developer mode (memory peek, task dump, crash)
+    pub debug_commands: Vec<DebugCommand>,
+    /// UDP request sent to the ArduinoOTA port after a WiFi update, answered with
+    /// `FW:<md5>` of the running image; `None` when the firmware has no such reply, and
+    /// updates then end unverified with a warning
+    pub ota_image_probe: Option<String>,
+}
+
+impl Default for ProtocolProfile {
+    fn default() -> Self {
+        ProtocolProfile {
+            name: "default".to_string(),
+            response_delay_ms: 30,
+            upload_timeout_ms: 15000,
+            log_level_command: None,
+            upload_log_level: DeviceLogLevel::Error,
+            default_log_level: DeviceLogLevel::Info,
+            quiet_command: None,
+            quiet_window_secs: 20,
+            config_readback_command: None,
+            prompt: None,
+            settle_quiet_ms: 300,
+            settle_max_ms: 3000,
+            ready_banner: None,
+            ack_tokens: vec!["ACK".to_string()],
+            nak_tokens: vec!["NAK:".to_string()],
+            version_command: Some("v".to_string()),
+            min_firmware_version: Some(MIN_FIRMWARE_VERSION.to_string()),
+            config_protocol_version: 4,
+            compress_uploads: false,
+            framing: FrameEncoding::Text,
+            debug_commands: debug_commands::defaults(),
+            ota_image_probe: None,
+        }
+    }
+}
+
+impl ProtocolProfile {
+    /// Catch parameters that would leave a live connection unable to talk to the device
+    pub fn validate(&self) -> Result<(), String> {
+        if self.upload_timeout_ms == 0 {
+            return Err("Upload timeout must be greater than zero".into());
+        }
+        if self.ack_tokens.iter().all(|t| t.trim().is_empty()) {
+            return Err("At least one ACK token is required".into());
+        }
+        if self.nak_tokens.iter().any(|t| t.trim().is_empty()) {
+            return Err("NAK tokens can't be empty".into());
+        }
+        for (i, debug) in self.debug_commands.iter().enumerate() {
+            debug.command.validate().map_err(|e| format!("Debug command {}", e))?;
+            if self.debug_commands[..i].iter().any(|d| d.command.name == debug.command.name) {
+                return Err(format!("Debug command '{}' is declared twice", debug.command.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Saved connection: which port, what the device is called, and how to talk to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionProfile {
+    pub name: String,
+    pub port_name: String,
+    #[serde(default)]
+    pub nickname: Option<String>,
+    #[serde(default)]
+    pub protocol: ProtocolProfile,
+    /// Baud rate and framing for this board's firmware build
+    #[serde(default)]
+    pub serial: SerialParams,
+}
+
+/// On-disk and export format for profiles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileBundle {
+    pub version: u32,
+    pub profiles: Vec<ConnectionProfile>,
+}
+
+/// Outcome of merging an imported bundle into the local profiles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileImportSummary {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+}
+
+fn profiles_path(app: &AppHandle) -> Result<PathBuf, ProfileError> {
+    let dir = storage::data_dir(app).map_err(ProfileError::IoError)?;
+    Ok(dir.join(PROFILES_FILE))
+}
+
+fn read_bundle(path: &Path) -> Result<ProfileBundle, ProfileError> {
+    let content = fs::read_to_string(path)?;
+    let bundle: ProfileBundle = serde_json::from_str(&content)?;
+    if bundle.version > BUNDLE_VERSION {
+        return Err(ProfileError::UnsupportedVersion(bundle.version));
+    }
+    Ok(bundle)
+}
+
+fn write_bundle(path: &Path, profiles: Vec<ConnectionProfile>) -> Result<(), ProfileError> {
+    let bundle = ProfileBundle {
+        version: BUNDLE_VERSION,
+        profiles,
+    };
+    fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+    Ok(())
+}
+
+/// Load all saved profiles (empty if none were saved yet)
+pub fn load_profiles(app: &AppHandle) -> Result<Vec<ConnectionProfile>, ProfileError> {
+    let path = profiles_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(read_bundle(&path)?.profiles)
+}
+
+/// Find a profile by name
+pub fn get_profile(app: &AppHandle, name: &str) -> Result<ConnectionProfile, ProfileError> {
+    load_profiles(app)?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| ProfileError::NotFound(format!("Profile '{}' not found", name)))
+}
+
+/// Insert or replace a profile by name
+pub fn save_profile(app: &AppHandle, profile: ConnectionProfile) -> Result<(), ProfileError> {
+    profile.protocol.validate().map_err(ProfileError::Invalid)?;
+    let mut profiles = load_profiles(app)?;
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+    write_bundle(&profiles_path(app)?, profiles)
+}
+
+/// Delete a profile by name
+pub fn delete_profile(app: &AppHandle, name: &str) -> Result<(), ProfileError> {
+    let mut profiles = load_profiles(app)?;
+    let before = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == before {
+        return Err(ProfileError::NotFound(format!("Profile '{}' not found", name)));
+    }
+    write_bundle(&profiles_path(app)?, profiles)
+}
+
+/// Write every profile to a shareable JSON bundle
+pub fn export_profiles(app: &AppHandle, dest: &Path) -> Result<usize, ProfileError> {
+    let profiles = load_profiles(app)?;
+    let count = profiles.len();
+    write_bundle(dest, profiles)?;
+    Ok(count)
+}
+
+/// Merge a bundle into the local profiles; same-name profiles are replaced
+pub fn import_profiles(app: &AppHandle, src: &Path) -> Result<ProfileImportSummary, ProfileError> {
+    let incoming = read_bundle(src)?.profiles;
+    let mut profiles = load_profiles(app)?;
+    let mut summary = ProfileImportSummary {
+        added: Vec::new(),
+        updated: Vec::new(),
+    };
+
+    for profile in incoming {
+        profile
+            .protocol
+            .validate()
+            .map_err(|e| ProfileError::Invalid(format!("{}: {}", profile.name, e)))?;
+        match profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => {
+                summary.updated.push(profile.name.clone());
+                *existing = profile;
+            }
+            None => {
+                summary.added.push(profile.name.clone());
+                profiles.push(profile);
+            }
+        }
+    }
+
+    write_bundle(&profiles_path(app)?, profiles)?;
+    Ok(summary)
+}
@@ -0,0 +1,51 @@
+use crate::session::now_millis;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+// How long device output is kept, comfortably more than any capture window
+const RETAIN_MS: u64 = 30_000;
+// Cap for a device that floods the port
+const MAX_LINES: usize = 2000;
+
+/// A line the device printed, as attached to a failure record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedLine {
+    pub timestamp: u64,
+    pub text: String,
+}
+
+/// The last few seconds of device output: command replies, unsolicited prints and
+/// log lines sent during uploads. Lets a failure be recorded together with what the
+/// device said about it.
+#[derive(Clone, Default)]
+pub struct RecentOutput(Arc<Mutex<VecDeque<CapturedLine>>>);
+
+impl RecentOutput {
+    pub fn push(&self, text: &str) {
+        let Ok(mut lines) = self.0.lock() else {
+            return;
+        };
+        let now = now_millis();
+        lines.push_back(CapturedLine {
+            timestamp: now,
+            text: text.to_string(),
+        });
+        let cutoff = now.saturating_sub(RETAIN_MS);
+        while lines
+            .front()
+            .is_some_and(|l| l.timestamp < cutoff || lines.len() > MAX_LINES)
+        {
+            lines.pop_front();
+        }
+    }
+
+    /// Lines printed within the last `window_ms`
+    pub fn last(&self, window_ms: u64) -> Vec<CapturedLine> {
+        let from = now_millis().saturating_sub(window_ms);
+        self.0
+            .lock()
+            .map(|lines| lines.iter().filter(|l| l.timestamp >= from).cloned().collect())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,75 @@
+use crate::events::{ConnectionLost, ConnectionRestored, CONNECTION_LOST_EVENT, CONNECTION_RESTORED_EVENT};
+use crate::hooks;
+use crate::serial::{LostPort, SerialState};
+use crate::serial_reader;
+use crate::session::{SessionEventKind, SessionLog};
+use crate::settings::{HookEvent, SettingsState};
+use crate::supervisor::{ConnectionSupervisor, TaskRole};
+use crate::tray::{self, BenchState};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// The port stopped working: drop it so the app no longer reports it connected, tell
+/// the UI, and keep trying to open it again when settings allow
+pub async fn connection_lost(app: &AppHandle, reason: String) {
+    let state = app.state::<SerialState>().inner().clone();
+    let Ok(Some(lost)) = state.with(|connection| connection.drop_dead_port()).await else {
+        return;
+    };
+    let settings = app.state::<SettingsState>();
+    let reconnect = settings.get().reconnect;
+    let reconnecting = reconnect.enabled && reconnect.attempts > 0;
+
+    let message = format!("Lost connection to {}: {}", lost.port_name, reason);
+    eprintln!("[SERIAL] {}", message);
+    app.state::<SessionLog>().record(SessionEventKind::Disconnected, message.clone(), None);
+    hooks::fire(app, HookEvent::ConnectionLost, Some(lost.port_name.clone()), message);
+    emit_lost(app, &lost, reason, reconnecting);
+    tray::show(app, BenchState::Disconnected);
+
+    if reconnecting {
+        let interval = Duration::from_secs(reconnect.interval_secs.max(1) as u64);
+        let task_app = app.clone();
+        app.state::<ConnectionSupervisor>().start(TaskRole::Reconnect, move |mut token| async move {
+            for attempt in 1..=reconnect.attempts {
+                if !token.sleep(interval).await {
+                    return;
+                }
+                let port = lost.clone();
+                // Someone connected by hand meanwhile; leave that connection alone
+                let reopened = state
+                    .with(move |connection| (!connection.is_connected()).then(|| connection.reopen(&port)))
+                    .await;
+                match reopened {
+                    Ok(Some(Ok(()))) => return restored(&task_app, &lost, attempt),
+                    Ok(None) => return,
+                    Ok(Some(Err(e))) => eprintln!("[SERIAL] Reconnect attempt {} to {} failed: {}", attempt, lost.port_name, e),
+                    Err(e) => eprintln!("[SERIAL] Reconnect attempt {} to {} failed: {}", attempt, lost.port_name, e),
+                }
+            }
+            let reason = format!("Gave up after {} reconnect attempts", reconnect.attempts);
+            emit_lost(&task_app, &lost, reason, false);
+        });
+    }
+}
+
+fn restored(app: &AppHandle, lost: &LostPort, attempt: u32) {
+    let message = format!("Reconnected to {}", lost.port_name);
+    app.state::<SessionLog>().record(SessionEventKind::Connected, message, None);
+    let payload = ConnectionRestored {
+        port: lost.port_name.clone(),
+        attempt,
+    };
+    let _ = app.emit(CONNECTION_RESTORED_EVENT, payload);
+    tray::show(app, BenchState::Idle);
+    serial_reader::start(app);
+}
+
+fn emit_lost(app: &AppHandle, lost: &LostPort, reason: String, reconnecting: bool) {
+    let payload = ConnectionLost {
+        port: lost.port_name.clone(),
+        reason,
+        reconnecting,
+    };
+    let _ = app.emit(CONNECTION_LOST_EVENT, payload);
+}
@@ -0,0 +1,43 @@
+use crate::reconnect;
+use crate::scheduler::{Scheduler, Scope};
+use crate::serial::SerialState;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+// How often the wall clock is compared against the timer
+const TICK_SECS: u64 = 5;
+// Delay beyond a tick that can't be a busy runtime, only a suspended host
+const SLEEP_THRESHOLD_SECS: u64 = 15;
+
+/// Check the connection as soon as the host wakes from sleep, when the port handle is
+/// often dead even though it still looks open, instead of waiting for a command to fail.
+///
+/// A tick that arrives much later by the wall clock than it was scheduled means the
+/// host was suspended in between.
+pub fn start(app: AppHandle) {
+    let tasks = app.state::<Scheduler>().inner().clone();
+    tasks.spawn("resume watch", Scope::App, move |mut token| async move {
+        let tick = Duration::from_secs(TICK_SECS);
+        let mut last = SystemTime::now();
+        while token.sleep(tick).await {
+            let now = SystemTime::now();
+            let gap = now.duration_since(last).unwrap_or_default();
+            last = now;
+            if gap > tick + Duration::from_secs(SLEEP_THRESHOLD_SECS) {
+                eprintln!("[SERIAL] Host resumed after {} s, checking the connection", gap.as_secs());
+                validate(&app).await;
+            }
+        }
+    });
+}
+
+/// Ask the device to identify itself; a port that doesn't answer is treated as lost
+async fn validate(app: &AppHandle) {
+    let state = app.state::<SerialState>().inner().clone();
+    let checked = state
+        .with(|connection| connection.is_connected().then(|| connection.identify(None)))
+        .await;
+    if let Ok(Some(Err(e))) = checked {
+        reconnect::connection_lost(app, format!("No answer after the host resumed: {}", e)).await;
+    }
+}
@@ -0,0 +1,210 @@
+use crate::device_backup::BACKUP_DIR;
+use crate::device_fs::DOWNLOADS_DIR;
+use crate::scheduler::{self, Scheduler, Scope};
+use crate::session::now_millis;
+use crate::settings::{RetentionPolicy, RetentionSettings, SettingsState};
+use crate::soak::{REPORT_BACKUP_FILE, REPORT_FILE};
+use crate::storage;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Kinds of generated files with their own retention policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionCategory {
+    Logs,
+    Telemetry,
+    Backups,
+}
+
+const CATEGORIES: [RetentionCategory; 3] = [
+    RetentionCategory::Logs,
+    RetentionCategory::Telemetry,
+    RetentionCategory::Backups,
+];
+
+impl RetentionCategory {
+    fn policy<'a>(&self, settings: &'a RetentionSettings) -> &'a RetentionPolicy {
+        match self {
+            RetentionCategory::Logs => &settings.logs,
+            RetentionCategory::Telemetry => &settings.telemetry,
+            RetentionCategory::Backups => &settings.backups,
+        }
+    }
+
+    /// Files of this category below the data folder
+    fn files(&self, data_dir: &Path) -> Vec<PathBuf> {
+        match self {
+            RetentionCategory::Logs => files_in(&data_dir.join(DOWNLOADS_DIR), |_| true),
+            RetentionCategory::Telemetry => [REPORT_FILE, REPORT_BACKUP_FILE]
+                .iter()
+                .map(|name| data_dir.join(name))
+                .filter(|path| path.is_file())
+                .collect(),
+            RetentionCategory::Backups => {
+                files_in(&data_dir.join(BACKUP_DIR), |p| p.extension().is_some_and(|ext| ext == "json"))
+            }
+        }
+    }
+}
+
+fn files_in(dir: &Path, keep: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && keep(p))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Which limit a file was deleted for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupReason {
+    Age,
+    Size,
+}
+
+/// A file the cleanup deleted, or would delete in a dry run
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanupItem {
+    pub category: RetentionCategory,
+    /// Path relative to the data folder
+    pub path: String,
+    pub bytes: u64,
+    pub modified: u64,
+    pub reason: CleanupReason,
+}
+
+/// Result of `run_cleanup_now` and the scheduled cleanup
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanupReport {
+    pub ran_at: u64,
+    pub dry_run: bool,
+    pub items: Vec<CleanupItem>,
+    pub freed_bytes: u64,
+    /// Files that couldn't be deleted; they are not in `items`
+    pub errors: Vec<String>,
+}
+
+struct Candidate {
+    path: PathBuf,
+    bytes: u64,
+    modified: u64,
+}
+
+/// Files of `category` its policy removes, oldest first: everything past the age limit,
+/// then the oldest of the rest until the category fits the size limit
+fn select(data_dir: &Path, category: RetentionCategory, policy: &RetentionPolicy, now: u64) -> Vec<CleanupItem> {
+    let mut files: Vec<Candidate> = category
+        .files(data_dir)
+        .into_iter()
+        .filter_map(|path| {
+            let meta = fs::metadata(&path).ok()?;
+            // A file without a usable timestamp counts as new rather than risk deleting it
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(now, |d| d.as_millis() as u64);
+            Some(Candidate {
+                path,
+                bytes: meta.len(),
+                modified,
+            })
+        })
+        .collect();
+    files.sort_by_key(|f| f.modified);
+
+    let mut total: u64 = files.iter().map(|f| f.bytes).sum();
+    let cutoff = policy.max_age_days.map(|days| now.saturating_sub(days as u64 * DAY_MS));
+    let cap = policy.max_total_mb.map(|mb| mb * 1024 * 1024);
+    // The newest file always stays, e.g. the backup `restore_last_device_backup` uses
+    files.pop();
+
+    let mut items = Vec::new();
+    for file in files {
+        let reason = if cutoff.is_some_and(|c| file.modified < c) {
+            CleanupReason::Age
+        } else if cap.is_some_and(|c| total > c) {
+            CleanupReason::Size
+        } else {
+            break;
+        };
+        total -= file.bytes;
+        let path = file.path.strip_prefix(data_dir).unwrap_or(&file.path);
+        items.push(CleanupItem {
+            category,
+            path: path.to_string_lossy().to_string(),
+            bytes: file.bytes,
+            modified: file.modified,
+            reason,
+        });
+    }
+    items
+}
+
+/// Apply the retention policies to the files below `data_dir`. A dry run only lists
+/// what would be deleted. Blocking.
+pub fn run(data_dir: &Path, settings: &RetentionSettings, dry_run: bool) -> CleanupReport {
+    let now = now_millis();
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    for category in CATEGORIES {
+        for item in select(data_dir, category, category.policy(settings), now) {
+            if dry_run {
+                items.push(item);
+                continue;
+            }
+            match fs::remove_file(data_dir.join(&item.path)) {
+                Ok(()) => items.push(item),
+                Err(e) => errors.push(format!("{}: {}", item.path, e)),
+            }
+        }
+    }
+    CleanupReport {
+        ran_at: now,
+        dry_run,
+        freed_bytes: items.iter().map(|i| i.bytes).sum(),
+        items,
+        errors,
+    }
+}
+
+/// Clean up at startup and then once a day, while enabled in settings
+pub fn start(app: AppHandle) {
+    let tasks = app.state::<Scheduler>().inner().clone();
+    tasks.spawn("retention cleanup", Scope::App, move |mut token| async move {
+        loop {
+            let settings = app.state::<SettingsState>().get().retention;
+            if settings.enabled {
+                match storage::data_dir(&app) {
+                    Ok(dir) => {
+                        if let Some(report) = scheduler::blocking(move || run(&dir, &settings, false)).await {
+                            if !report.items.is_empty() || !report.errors.is_empty() {
+                                eprintln!(
+                                    "[RETENTION] Removed {} files ({} bytes), {} failed",
+                                    report.items.len(),
+                                    report.freed_bytes,
+                                    report.errors.len()
+                                );
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("[RETENTION] No data folder to clean up: {}", e),
+                }
+            }
+            if !token.sleep(CLEANUP_INTERVAL).await {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,103 @@
+use crate::device_command::{DeviceCommand, OutcomeStatus};
+use crate::serial::{ChunkPacing, SerialConnection, UploadEvent, UploadResult};
+use crate::settings::RunningUploadPolicy;
+use serde::{Deserialize, Serialize};
+
+/// Step of a config upload to a device that may be running the signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadPhaseKind {
+    CheckRunning,
+    Stop,
+    Upload,
+    Restart,
+}
+
+/// How one step of the sequence went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadPhase {
+    pub phase: UploadPhaseKind,
+    pub ok: bool,
+    pub detail: Option<String>,
+}
+
+impl UploadPhase {
+    fn new(phase: UploadPhaseKind, ok: bool, detail: Option<String>) -> Self {
+        UploadPhase { phase, ok, detail }
+    }
+}
+
+/// Upload `config`, first asking whether the signal is running and handling that per
+/// `policy`. Refusing, or failing to stop the signal, is an error and nothing is sent.
+/// A failed restart doesn't fail the upload; it shows up in the result's phases.
+/// Once `cancelled` returns true the upload stops, restarting the signal if it was stopped for it.
+pub fn upload<F>(
+    connection: &mut SerialConnection,
+    config: &str,
+    policy: RunningUploadPolicy,
+    pacing: ChunkPacing,
+    cancelled: &dyn Fn() -> bool,
+    on_event: F,
+) -> Result<UploadResult, String>
+where
+    F: FnMut(UploadEvent),
+{
+    if policy == RunningUploadPolicy::Ignore {
+        return connection
+            .send_config_cancellable(config, pacing, cancelled, on_event)
+            .map_err(|e| e.to_string());
+    }
+
+    let mut phases = Vec::new();
+    let running = match connection.get_status() {
+        Ok(status) => {
+            let detail = if status.running { "Signal running" } else { "Signal stopped" };
+            phases.push(UploadPhase::new(UploadPhaseKind::CheckRunning, true, Some(detail.into())));
+            status.running
+        }
+        Err(e) => {
+            // Firmware without a readable status can still take the config
+            phases.push(UploadPhase::new(UploadPhaseKind::CheckRunning, false, Some(e.to_string())));
+            false
+        }
+    };
+
+    if running && policy == RunningUploadPolicy::Refuse {
+        return Err("The device is running the signal; stop it before uploading a config".into());
+    }
+    if running {
+        let outcome = connection.send_command(&DeviceCommand::Stop).map_err(|e| e.to_string())?;
+        if outcome.status == OutcomeStatus::Rejected {
+            return Err(format!("Couldn't stop the signal, upload not sent: {}", outcome.describe()));
+        }
+        phases.push(UploadPhase::new(UploadPhaseKind::Stop, true, None));
+    }
+
+    let mut result = match connection.send_config_cancellable(config, pacing, cancelled, on_event) {
+        Ok(result) => result,
+        Err(e) => {
+            // Don't leave the signal stopped because of an upload that never finished
+            if running {
+                if let Err(restart) = connection.send_command(&DeviceCommand::Run) {
+                    eprintln!("[UPLOAD] Couldn't restart the signal after the upload stopped: {}", restart);
+                }
+            }
+            return Err(e.to_string());
+        }
+    };
+    phases.push(UploadPhase::new(UploadPhaseKind::Upload, result.success, result.error_message.clone()));
+
+    if running {
+        let restart = match connection.send_command(&DeviceCommand::Run) {
+            Ok(outcome) if outcome.status == OutcomeStatus::Rejected => {
+                UploadPhase::new(UploadPhaseKind::Restart, false, Some(outcome.describe()))
+            }
+            Ok(_) => UploadPhase::new(UploadPhaseKind::Restart, true, None),
+            Err(e) => UploadPhase::new(UploadPhaseKind::Restart, false, Some(e.to_string())),
+        };
+        phases.push(restart);
+    }
+
+    result.phases = phases;
+    Ok(result)
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// What a background task's lifetime is tied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Cancelled when the device is disconnected
+    Connection,
+    /// Runs until it finishes or is cancelled explicitly
+    App,
+}
+
+/// Cooperative cancellation signal handed to every scheduled task
+#[derive(Clone)]
+pub struct CancelToken(watch::Receiver<bool>);
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolves once the task is cancelled (or its scheduler entry is gone)
+    pub async fn cancelled(&mut self) {
+        let _ = self.0.wait_for(|cancelled| *cancelled).await;
+    }
+
+    /// Sleep for `duration`, waking early on cancellation; `false` if cancelled
+    pub async fn sleep(&mut self, duration: Duration) -> bool {
+        tokio::time::timeout(duration, self.cancelled()).await.is_err() && !self.is_cancelled()
+    }
+}
+
+struct Task {
+    name: String,
+    scope: Scope,
+    cancel: watch::Sender<bool>,
+}
+
+/// Owner of the app's timed background work (periodic scans, soak snapshots, ramps).
+///
+/// Tasks run on the async runtime and wait with `CancelToken::sleep` instead of
+/// parking a thread each, so every timing loop can be cancelled the same way.
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    tasks: Arc<Mutex<HashMap<u64, Task>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+/// Handle to one scheduled task
+#[derive(Clone)]
+pub struct TaskHandle {
+    id: u64,
+    scheduler: Scheduler,
+    done: watch::Receiver<bool>,
+}
+
+impl TaskHandle {
+    pub fn cancel(&self) {
+        self.scheduler.cancel_where(|id, _| id == self.id);
+    }
+
+    /// Whether the task is still running
+    pub fn is_active(&self) -> bool {
+        self.scheduler
+            .tasks
+            .lock()
+            .is_ok_and(|tasks| tasks.contains_key(&self.id))
+    }
+
+    /// Wait until the task has returned
+    pub async fn join(&self) {
+        let mut done = self.done.clone();
+        let _ = done.wait_for(|done| *done).await;
+    }
+}
+
+impl Scheduler {
+    /// Run `task` in the background; it should return once its token is cancelled
+    pub fn spawn<F, Fut>(&self, name: &str, scope: Scope, task: F) -> TaskHandle
+    where
+        F: FnOnce(CancelToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (cancel, token) = watch::channel(false);
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.insert(
+                id,
+                Task {
+                    name: name.to_string(),
+                    scope,
+                    cancel,
+                },
+            );
+        }
+
+        let (done_tx, done) = watch::channel(false);
+        let future = task(CancelToken(token));
+        let scheduler = self.clone();
+        tauri::async_runtime::spawn(async move {
+            future.await;
+            if let Ok(mut tasks) = scheduler.tasks.lock() {
+                tasks.remove(&id);
+            }
+            let _ = done_tx.send(true);
+        });
+
+        TaskHandle {
+            id,
+            scheduler: self.clone(),
+            done,
+        }
+    }
+
+    /// Names of the tasks of `scope` still running
+    pub fn running(&self, scope: Scope) -> Vec<String> {
+        self.tasks
+            .lock()
+            .map(|tasks| {
+                tasks
+                    .values()
+                    .filter(|t| t.scope == scope)
+                    .map(|t| t.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Cancel every task of `scope`, e.g. all connection-bound work on disconnect
+    pub fn cancel_scope(&self, scope: Scope) {
+        self.cancel_where(|_, task| task.scope == scope);
+    }
+
+    fn cancel_where<P: Fn(u64, &Task) -> bool>(&self, predicate: P) {
+        let Ok(mut tasks) = self.tasks.lock() else {
+            return;
+        };
+        tasks.retain(|&id, task| {
+            if !predicate(id, task) {
+                return true;
+            }
+            eprintln!("[SCHEDULER] Cancelling {}", task.name);
+            let _ = task.cancel.send(true);
+            false
+        });
+    }
+}
+
+/// Run blocking work (serial I/O, file scans) off the async runtime; `None` if it panicked
+pub async fn blocking<T, F>(work: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(work).await.ok()
+}
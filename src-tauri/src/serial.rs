@@ -1,12 +1,74 @@
+use crate::device_command::{CommandOutcome, DeviceCommand, OutcomeStatus};
+use crate::framed::{FrameEncoding, FrameSpec, FramedTransfer, Pacing, TransferError, TransferOutcome};
+use crate::preview::{self, ConfigPreview};
+use crate::profiles::{DeviceLogLevel, ProtocolProfile};
+use crate::running_guard::UploadPhase;
+use crate::settings::CommandAliases;
+use crate::timing::{LinkThroughput, TimingRecorder, TimingStats, UploadEstimate};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
 use serde::{Deserialize, Serialize};
 use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::cell::RefCell;
 use std::io::{Read, Write};
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 use thiserror::Error;
+use tokio::sync::oneshot;
 
+// Line speed of the stock firmware
 const BAUD_RATE: u32 = 115200;
 const TIMEOUT_MS: u64 = 1000;
+// Minimum spacing between progress callbacks (send and device) during an upload
+const UPLOAD_PROGRESS_INTERVAL_MS: u64 = 200;
+// Config frame markers understood by the firmware
+const CONFIG_START_MARKER: &str = "<CFG>\n";
+const CONFIG_END_MARKER: &str = "\n<END>\n";
+// Config framing versions: 1 is the bare `<CFG>` frame, 2 adds length and CRC32 to its start line,
+// 3 adds per-chunk flow control, 4 lets an interrupted frame be resumed
+const LEGACY_CONFIG_PROTOCOL: u32 = 1;
+const WINDOWED_CONFIG_PROTOCOL: u32 = 3;
+const RESUMABLE_CONFIG_PROTOCOL: u32 = 4;
+// A v4 device that no longer holds the partial payload NAKs a resume with this reason
+const RESUME_REFUSED: &str = "RESUME";
+// Firmware that understands the version offer answers it right away
+const CONFIG_NEGOTIATE_TIMEOUT_MS: u64 = 300;
+// Uploads go out in small paced chunks so the ESP32's 256-byte RX buffer keeps up;
+// callers may ask for other pacing within the limits below
+const UPLOAD_CHUNK_SIZE: usize = 64;
+const UPLOAD_CHUNK_DELAY_MS: u64 = 2;
+// Native-USB boards (ESP32-S2/S3) buffer far more than a UART bridge
+const MAX_UPLOAD_CHUNK_SIZE: usize = 16 * 1024;
+const MAX_UPLOAD_CHUNK_DELAY_MS: u64 = 1000;
+// With flow control the device reports "WIN:<bytes received>" after every chunk it has
+// consumed; three chunks in flight keep the line busy without overrunning its buffer
+const UPLOAD_WINDOW_CHUNKS: usize = 3;
+const UPLOAD_CREDIT_PREFIX: &str = "WIN:";
+const UPLOAD_CREDIT_TIMEOUT_MS: u64 = 2000;
+// How long to keep reading after an ACK so trailing logs don't reach the next command
+const UPLOAD_DRAIN_MS: u64 = 250;
+// Short query answered with a single "RPM:<rpm> RUN|STOP" line, for high-rate gauges
+const FAST_RPM_QUERY: &str = "<RPM>";
+const FAST_RPM_TIMEOUT_MS: u64 = 250;
+// The version reply is one short line, sent right away
+const VERSION_TIMEOUT_MS: u64 = 500;
+// ESP32 ROM bootloader banner, printed only after a reset
+const RESET_MARKER: &str = "rst:";
+// Opening the port resets most dev boards, so the first queries may land during boot
+const IDENTIFY_ATTEMPTS: u32 = 3;
+const IDENTIFY_RETRY_DELAY_MS: u64 = 500;
+// Bytes of an unrecognized identify reply kept for the error
+const IDENTIFY_RAW_CAP: usize = 512;
+// How often the port is checked for boot output while settling after connect
+const SETTLE_POLL_MS: u64 = 20;
+// How long EN is held low for a hard reset (esptool uses 100 ms)
+const HARD_RESET_PULSE_MS: u64 = 100;
+// How long IO0 stays low after EN is released, for the ROM to sample it (esptool: 50 ms)
+const BOOTLOADER_HOLD_MS: u64 = 50;
+// ROM banner text meaning the chip is waiting in download mode
+const DOWNLOAD_MODE_MARKERS: [&str; 2] = ["DOWNLOAD_BOOT", "waiting for download"];
 
 #[derive(Error, Debug)]
 pub enum SerialError {
@@ -20,6 +82,27 @@ pub enum SerialError {
     WriteError(String),
     #[error("Failed to read from port: {0}")]
     ReadError(String),
+    #[error("Timed out waiting for device response")]
+    Timeout,
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("Device error: {0}")]
+    DeviceError(String),
+    #[error("Not supported by the current protocol profile: {0}")]
+    Unsupported(String),
+    #[error("Port opened but no ESP32 signal generator answered ({} bytes received)", .0.len())]
+    NotOurDevice(Vec<u8>),
+}
+
+impl From<TransferError> for SerialError {
+    fn from(err: TransferError) -> Self {
+        match err {
+            TransferError::Write(e) => SerialError::WriteError(e.to_string()),
+            TransferError::Read(e) => SerialError::ReadError(e.to_string()),
+            TransferError::Stalled(_) => SerialError::DeviceError(err.to_string()),
+            TransferError::Cancelled(_) => SerialError::Cancelled,
+        }
+    }
 }
 
 impl Serialize for SerialError {
@@ -31,10 +114,56 @@ impl Serialize for SerialError {
     }
 }
 
+/// Bus a serial port sits on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortKind {
+    Usb,
+    Bluetooth,
+    Pci,
+    Unknown,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PortInfo {
     pub name: String,
-    pub port_type: String,
+    pub port_type: PortKind,
+    /// USB vendor and product ID, for USB ports
+    #[serde(default)]
+    pub vid: Option<u16>,
+    #[serde(default)]
+    pub pid: Option<u16>,
+    /// USB serial number, stable across reboots unlike the port name
+    #[serde(default)]
+    pub serial_number: Option<String>,
+    /// USB descriptor strings, when the device reports them
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+    #[serde(default)]
+    pub product: Option<String>,
+    /// A connection through this port succeeded before
+    #[serde(default)]
+    pub previously_used: bool,
+    #[serde(default)]
+    pub last_connected: Option<u64>,
+}
+
+impl PortInfo {
+    /// One-line description for lists, e.g. `USB Silicon Labs CP2102 (10C4:EA60)`
+    pub fn describe(&self) -> String {
+        let mut parts = vec![match self.port_type {
+            PortKind::Usb => "USB".to_string(),
+            PortKind::Bluetooth => "Bluetooth".to_string(),
+            PortKind::Pci => "PCI".to_string(),
+            PortKind::Unknown => "Unknown".to_string(),
+        }];
+        parts.extend(self.manufacturer.clone());
+        parts.extend(self.product.clone());
+        if let (Some(vid), Some(pid)) = (self.vid, self.pid) {
+            parts.push(format!("({:04X}:{:04X})", vid, pid));
+        }
+        parts.join(" ")
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -43,22 +172,498 @@ pub struct DeviceStatus {
     pub port_name: Option<String>,
     pub running: bool,
     pub rpm: u16,
+    pub fault: Option<String>,
+    /// Identity lines ("ID:", "FW:") from the status response, when the firmware prints them
+    pub device_id: Option<String>,
+    pub firmware_version: Option<String>,
+    pub firmware_build: Option<String>,
+    /// Set when the firmware is older than the protocol profile expects
+    pub firmware_warning: Option<String>,
+    /// Name of the signal the firmware has loaded, from a "SIG:<name>" line
+    pub loaded_signal: Option<String>,
+    /// Device uptime from an "UPTIME:<ms>" line
+    pub uptime_ms: Option<u64>,
+    /// Resets seen since connecting (uptime went backwards between two status reads)
+    pub reset_count: u32,
+    /// This status is the first one after a reset
+    pub reset_detected: bool,
     pub raw_response: String,
 }
 
+/// Minimal status for gauges polled several times a second
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct RpmReading {
+    pub rpm: u16,
+    pub running: bool,
+}
+
+/// Parse a fast RPM reply of the form "RPM:1200 RUN"
+fn parse_rpm_reading(line: &str) -> Option<RpmReading> {
+    let mut parts = line.strip_prefix("RPM:")?.split_whitespace();
+    let rpm = parts.next()?.parse().ok()?;
+    let running = parts.next() == Some("RUN");
+    Some(RpmReading { rpm, running })
+}
+
+/// What the board printed after a reset through the modem control lines
+#[derive(Debug, Serialize, Clone)]
+pub struct BootReport {
+    /// The reset asked for download mode
+    pub bootloader: bool,
+    /// Reset cause from the ROM banner, e.g. `POWERON_RESET`
+    pub reset_reason: Option<String>,
+    /// The ROM says it is waiting in download mode
+    pub download_mode: bool,
+    pub banner: Vec<String>,
+}
+
+/// Reset cause from a ROM banner line like `rst:0x1 (POWERON_RESET),boot:0x13 (...)`
+fn parse_reset_reason(line: &str) -> Option<String> {
+    let rest = &line[line.find(RESET_MARKER)?..];
+    let start = rest.find('(')? + 1;
+    let end = start + rest[start..].find(')')?;
+    Some(rest[start..end].to_string())
+}
+
+/// Which board and firmware build is on the other end of the port
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct DeviceIdentity {
+    /// Serial number of the USB bridge, read from the OS when connecting
+    pub usb_serial: Option<String>,
+    pub device_id: Option<String>,
+    pub firmware_version: Option<String>,
+    /// Build tag from the version reply (e.g. a date or commit), when the firmware sends one
+    pub firmware_build: Option<String>,
+}
+
+/// Split the text after "FW:" into version and optional build, e.g. "1.2.0 BUILD:a1b2c3"
+fn parse_firmware_line(rest: &str) -> (String, Option<String>) {
+    match rest.split_once("BUILD:") {
+        Some((version, build)) => (version.trim().to_string(), Some(build.trim().to_string()).filter(|b| !b.is_empty())),
+        None => (rest.trim().to_string(), None),
+    }
+}
+
+/// Whether dotted version `version` is older than `minimum` ("v1.2" < "1.10.0");
+/// non-numeric suffixes are ignored and missing parts count as 0
+pub fn version_older(version: &str, minimum: &str) -> bool {
+    let parts = |v: &str| -> Vec<u32> {
+        v.trim()
+            .trim_start_matches(['v', 'V'])
+            .split('.')
+            .map(|p| {
+                let digits: String = p.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().unwrap_or(0)
+            })
+            .collect()
+    };
+    let (mut version, mut minimum) = (parts(version), parts(minimum));
+    let len = version.len().max(minimum.len());
+    version.resize(len, 0);
+    minimum.resize(len, 0);
+    version < minimum
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UploadResult {
     pub success: bool,
     pub bytes_sent: usize,
     pub chunks_sent: usize,
     pub raw_response: String,
-    pub config_preview: String,
+    pub config_preview: ConfigPreview,
     pub error_message: Option<String>,
+    /// Steps taken around the upload because of the running-signal policy
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<UploadPhase>,
+    /// Config framing version agreed with the firmware (1 = legacy `<CFG>` frame)
+    #[serde(default)]
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub checksum: ChecksumStatus,
+    /// Payload offset the upload continued from after an interrupted attempt
+    #[serde(default)]
+    pub resumed_from: Option<usize>,
+    /// Size of the deflated payload, when the config was sent compressed
+    #[serde(default)]
+    pub compressed_bytes: Option<usize>,
+}
+
+/// Progress reported by the ESP32 itself while it ingests a config (e.g. "CFG: 40%")
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeviceProgress {
+    pub percent: u8,
+    pub line: String,
+    /// Time left until the upload completes, from the device's pace so far or the
+    /// upload estimate before it reports any progress
+    #[serde(default)]
+    pub eta_ms: Option<u64>,
+}
+
+/// Parity bit setting of the serial line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineParity {
+    None,
+    Odd,
+    Even,
+}
+
+/// Flow control of the serial line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineFlowControl {
+    None,
+    Software,
+    Hardware,
+}
+
+/// Line settings used to open the port; the defaults (115200 8N1, no flow control)
+/// match the stock firmware
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerialParams {
+    pub baud_rate: u32,
+    pub data_bits: u8,
+    pub parity: LineParity,
+    pub stop_bits: u8,
+    pub flow_control: LineFlowControl,
+    /// Keep DTR and RTS deasserted when opening the port, so boards with the usual
+    /// auto-reset circuit keep running. Whether it works depends on the board and
+    /// its USB bridge.
+    pub suppress_reset: bool,
+}
+
+impl Default for SerialParams {
+    fn default() -> Self {
+        SerialParams {
+            baud_rate: BAUD_RATE,
+            data_bits: 8,
+            parity: LineParity::None,
+            stop_bits: 1,
+            flow_control: LineFlowControl::None,
+            suppress_reset: false,
+        }
+    }
+}
+
+impl SerialParams {
+    /// Refuse settings no serial port can be opened with
+    pub fn validate(&self) -> Result<(), String> {
+        if self.baud_rate == 0 {
+            return Err("Baud rate must be greater than zero".into());
+        }
+        if !(5..=8).contains(&self.data_bits) {
+            return Err(format!("Data bits must be 5 to 8, not {}", self.data_bits));
+        }
+        if !matches!(self.stop_bits, 1 | 2) {
+            return Err(format!("Stop bits must be 1 or 2, not {}", self.stop_bits));
+        }
+        Ok(())
+    }
+
+    fn data_bits(&self) -> DataBits {
+        match self.data_bits {
+            5 => DataBits::Five,
+            6 => DataBits::Six,
+            7 => DataBits::Seven,
+            _ => DataBits::Eight,
+        }
+    }
+
+    fn parity(&self) -> Parity {
+        match self.parity {
+            LineParity::None => Parity::None,
+            LineParity::Odd => Parity::Odd,
+            LineParity::Even => Parity::Even,
+        }
+    }
+
+    fn stop_bits(&self) -> StopBits {
+        match self.stop_bits {
+            2 => StopBits::Two,
+            _ => StopBits::One,
+        }
+    }
+
+    fn flow_control(&self) -> FlowControl {
+        match self.flow_control {
+            LineFlowControl::None => FlowControl::None,
+            LineFlowControl::Software => FlowControl::Software,
+            LineFlowControl::Hardware => FlowControl::Hardware,
+        }
+    }
+}
+
+/// A connection whose port went away, with what's needed to reopen it
+#[derive(Debug, Clone)]
+pub struct LostPort {
+    pub port_name: String,
+    pub params: SerialParams,
+    pub profile: Option<(String, ProtocolProfile)>,
+}
+
+/// Where a config upload is, as seen from the app
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadStage {
+    /// Quiet window, log level and framing negotiation before the frame goes out
+    Preparing,
+    Sending,
+    /// Everything is sent; the device is parsing and storing the config
+    AwaitingAck,
+}
+
+/// Bytes of the config frame sent so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadProgress {
+    pub stage: UploadStage,
+    pub bytes_sent: usize,
+    pub chunks_sent: usize,
+    pub total_bytes: usize,
+    pub percent: u8,
+}
+
+impl UploadProgress {
+    fn new(stage: UploadStage, bytes_sent: usize, chunks_sent: usize, total_bytes: usize) -> Self {
+        UploadProgress {
+            stage,
+            bytes_sent,
+            chunks_sent,
+            total_bytes,
+            percent: (bytes_sent * 100 / total_bytes.max(1)).min(100) as u8,
+        }
+    }
+}
+
+/// What happens during a config upload: the app's own send progress and device output
+#[derive(Debug, Clone)]
+pub enum UploadEvent {
+    /// Rate-limited; every stage change and the last chunk are always delivered
+    Sent(UploadProgress),
+    /// Rate-limited progress report; 100% is always delivered
+    Progress(DeviceProgress),
+    /// Any other non-empty line (logs, ACK/NAK)
+    Line(String),
+}
+
+/// Parse a device progress line of the form "CFG: 40%"
+pub fn parse_device_progress(line: &str) -> Option<u8> {
+    let rest = line.trim().strip_prefix("CFG:")?;
+    let percent = rest.trim().strip_suffix('%')?.trim().parse::<u8>().ok()?;
+    if percent > 100 {
+        return None;
+    }
+    Some(percent)
+}
+
+/// USB serial number of a port, when it is a USB device that reports one
+fn usb_serial_number(port_name: &str) -> Option<String> {
+    serialport::available_ports()
+        .ok()?
+        .into_iter()
+        .find(|p| p.port_name == port_name)
+        .and_then(|p| match p.port_type {
+            serialport::SerialPortType::UsbPort(info) => info.serial_number,
+            _ => None,
+        })
+}
+
+/// Timing key for a request line: its keyword, e.g. `rpm` for `<RPM>`, `log` for `<LOG DEBUG>`
+fn request_kind(request: &str) -> String {
+    let keyword = request
+        .trim()
+        .trim_start_matches('<')
+        .split(|c: char| c.is_whitespace() || c == '>')
+        .next()
+        .unwrap_or_default();
+    keyword.to_ascii_lowercase()
+}
+
+/// Whether a status reply looks like it came from our firmware
+pub fn is_recognizable_reply(reply: &str) -> bool {
+    reply.lines().map(str::trim).any(|line| {
+        line.contains("RPM")
+            || line.contains("STATE:")
+            || line.starts_with("ID:")
+            || line.starts_with("FW:")
+    })
+}
+
+/// Whether device output contains the boot banner printed after a reset
+pub fn shows_reset_banner(response: &str) -> bool {
+    response.contains(RESET_MARKER)
+}
+
+/// Wrap a config in the `<CFG>`...`<END>` markers the firmware expects
+pub fn frame_config(config: &str) -> String {
+    format!("{}{}{}", CONFIG_START_MARKER, config, CONFIG_END_MARKER)
+}
+
+/// CRC32 (IEEE) of a config payload, as the firmware computes it over the received bytes
+pub fn config_crc32(config: &str) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(config.as_bytes());
+    crc.sum()
+}
+
+/// Start line of a config frame in framing `version`: from v2 on it carries the
+/// payload length and CRC32, and the firmware NAKs a frame that doesn't match them
+fn config_start_marker(version: u32, config: &str) -> String {
+    if version <= LEGACY_CONFIG_PROTOCOL {
+        return CONFIG_START_MARKER.to_string();
+    }
+    config_marker_with(version, config, "")
+}
+
+/// v2+ start line with extra `fields` after LEN and CRC32, which always describe the
+/// config itself:
+/// - ` ENC=DEFLATE ZLEN=<n>`: the payload is the zlib-compressed config, `n` bytes long.
+///   The firmware reads exactly `n` bytes, as compressed data may contain the end marker.
+/// - ` AT=<offset>` (v4): the frame continues the payload at `offset`. The firmware
+///   checks it holds the start of the same one; `offset` may be below what it received,
+///   never above.
+fn config_marker_with(version: u32, config: &str, fields: &str) -> String {
+    format!(
+        "<CFG v{} LEN={} CRC32={:08X}{}>\n",
+        version,
+        config.len(),
+        config_crc32(config),
+        fields
+    )
+}
+
+/// zlib-compress a config for firmware that inflates uploads
+pub fn deflate_config(config: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(config.as_bytes())?;
+    encoder.finish()
+}
+
+/// A config as one upload puts it on the wire
+struct UploadFrame<'a> {
+    config: &'a str,
+    /// The config, or its deflated form when `compressed`
+    payload: Vec<u8>,
+    compressed: bool,
+    version: u32,
+    pacing: ChunkPacing,
+}
+
+/// A v4 upload that stopped after the device credited part of its payload
+struct PartialUpload {
+    crc32: u32,
+    len: usize,
+    compressed: bool,
+    /// Payload bytes the device acknowledged
+    offset: usize,
+}
+
+/// How a config upload is cut up on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkPacing {
+    pub chunk_size: usize,
+    /// Pause after each chunk; from framing v3 on the device's credits pace the upload
+    /// instead
+    pub chunk_delay_ms: u64,
 }
 
+impl Default for ChunkPacing {
+    fn default() -> Self {
+        ChunkPacing {
+            chunk_size: UPLOAD_CHUNK_SIZE,
+            chunk_delay_ms: UPLOAD_CHUNK_DELAY_MS,
+        }
+    }
+}
+
+impl ChunkPacing {
+    /// Pacing asked for by a caller, with the defaults for whatever it leaves out
+    pub fn new(chunk_size: Option<usize>, chunk_delay_ms: Option<u64>) -> Result<Self, String> {
+        let defaults = ChunkPacing::default();
+        let pacing = ChunkPacing {
+            chunk_size: chunk_size.unwrap_or(defaults.chunk_size),
+            chunk_delay_ms: chunk_delay_ms.unwrap_or(defaults.chunk_delay_ms),
+        };
+        if pacing.chunk_size == 0 || pacing.chunk_size > MAX_UPLOAD_CHUNK_SIZE {
+            return Err(format!("Chunk size must be between 1 and {} bytes", MAX_UPLOAD_CHUNK_SIZE));
+        }
+        if pacing.chunk_delay_ms > MAX_UPLOAD_CHUNK_DELAY_MS {
+            return Err(format!("Chunk delay can be at most {} ms", MAX_UPLOAD_CHUNK_DELAY_MS));
+        }
+        Ok(pacing)
+    }
+
+    /// Bytes the device may owe credits for under flow control
+    fn window(&self) -> usize {
+        UPLOAD_WINDOW_CHUNKS * self.chunk_size
+    }
+}
+
+/// Whether the firmware checked the config against its CRC32
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumStatus {
+    /// Legacy framing, or no verdict from the device
+    #[default]
+    Unchecked,
+    /// Accepted with the checksum in the frame
+    Verified,
+    /// The device reported a CRC mismatch: bytes were corrupted on the way
+    Mismatch,
+}
+
+/// Turn a `NAK:` line among the response lines into a device error
+pub fn check_nak(lines: &[String]) -> Result<(), SerialError> {
+    match lines.iter().find(|l| l.starts_with("NAK:")) {
+        Some(nak) => Err(SerialError::DeviceError(nak.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Which way a line travelled over the port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Tx,
+    Rx,
+}
+
+/// Observer of command traffic, fed every line sent or received by commands and queries
+pub type TrafficTap = Box<dyn Fn(Direction, &str) + Send>;
+
+/// Receiver of device output that arrived outside any command's reply
+pub type LineSink = Box<dyn Fn(&str) + Send>;
+
 pub struct SerialConnection {
     port: Option<Box<dyn SerialPort>>,
     port_name: Option<String>,
+    protocol: ProtocolProfile,
+    // USB serial from the OS plus the last identity seen in a status response
+    identity: DeviceIdentity,
+    // Uptime of the previous status, to spot resets
+    last_uptime_ms: Option<u64>,
+    reset_count: u32,
+    // Command characters from settings; they outlive connections
+    aliases: CommandAliases,
+    config_uploaded: bool,
+    // Config framing agreed with the firmware, negotiated on the first upload
+    config_protocol: Option<u32>,
+    tap: Option<TrafficTap>,
+    unsolicited: Option<LineSink>,
+    // Unsolicited output received so far that doesn't end in a newline yet
+    rx_pending: String,
+    // Connection profile in use, if connected through one
+    profile_name: Option<String>,
+    // Line settings the port was opened with, to open it the same way again
+    params: SerialParams,
+    // Reply times of commands and requests, for tuning timeouts
+    timings: TimingRecorder,
+    // Measured config upload speed over the open port, for ETAs
+    throughput: LinkThroughput,
+    // Interrupted v4 upload to resume; kept across reconnects since the device may
+    // still hold the payload, and it refuses the resume otherwise
+    partial_upload: Option<PartialUpload>,
 }
 
 impl SerialConnection {
@@ -66,7 +671,208 @@ impl SerialConnection {
         SerialConnection {
             port: None,
             port_name: None,
+            protocol: ProtocolProfile::default(),
+            identity: DeviceIdentity::default(),
+            last_uptime_ms: None,
+            reset_count: 0,
+            aliases: CommandAliases::default(),
+            config_uploaded: false,
+            config_protocol: None,
+            tap: None,
+            unsolicited: None,
+            rx_pending: String::new(),
+            profile_name: None,
+            params: SerialParams::default(),
+            timings: TimingRecorder::default(),
+            throughput: LinkThroughput::default(),
+            partial_upload: None,
+        }
+    }
+
+    /// Mirror command traffic to `tap` (the serial monitor), for this and later connections
+    pub fn set_traffic_tap(&mut self, tap: TrafficTap) {
+        self.tap = Some(tap);
+    }
+
+    /// Hand device output nobody asked for (debug prints, boot logs) to `sink`, for this
+    /// and later connections
+    pub fn set_unsolicited_sink(&mut self, sink: LineSink) {
+        self.unsolicited = Some(sink);
+    }
+
+    /// Read whatever the device printed on its own since the last read, without
+    /// waiting, and pass complete lines to the unsolicited sink. Returns the line count.
+    pub fn poll_unsolicited(&mut self) -> Result<usize, SerialError> {
+        self.read_unsolicited().map(|(_, lines)| lines.len())
+    }
+
+    /// Non-blocking read of pending output; returns the byte count and the complete
+    /// lines delivered to the unsolicited sink
+    fn read_unsolicited(&mut self) -> Result<(usize, Vec<String>), SerialError> {
+        let Some(port) = self.port.as_mut() else {
+            return Ok((0, Vec::new()));
+        };
+        let available = port.bytes_to_read().map_err(|e| SerialError::ReadError(e.to_string()))? as usize;
+        if available == 0 {
+            return Ok((0, Vec::new()));
+        }
+        let mut buffer = vec![0u8; available];
+        let n = match port.read(&mut buffer) {
+            Ok(n) => n,
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => 0,
+            Err(e) => return Err(SerialError::ReadError(e.to_string())),
+        };
+        self.rx_pending.push_str(&String::from_utf8_lossy(&buffer[..n]));
+
+        let mut lines = Vec::new();
+        while let Some(pos) = self.rx_pending.find('\n') {
+            let line: String = self.rx_pending.drain(..=pos).collect();
+            if self.deliver_unsolicited(&line) {
+                lines.push(line);
+            }
+        }
+        Ok((n, lines))
+    }
+
+    /// Wait out the boot output many boards print when opening the port resets them,
+    /// draining it so it can't end up in the first command's reply. Ends after the
+    /// profile's quiet period without output, as soon as its ready banner arrives, or
+    /// after `settle_max_ms` (or `limit`, if sooner). Skipped when the port was opened
+    /// without resetting the board. Returns the number of lines drained.
+    pub fn settle(&mut self, limit: Option<Duration>) -> Result<usize, SerialError> {
+        if self.params.suppress_reset {
+            return Ok(0);
+        }
+        self.wait_for_boot(limit).map(|lines| lines.len())
+    }
+
+    /// Drain boot output as `settle` describes, returning the lines
+    fn wait_for_boot(&mut self, limit: Option<Duration>) -> Result<Vec<String>, SerialError> {
+        let quiet = Duration::from_millis(self.protocol.settle_quiet_ms);
+        if quiet.is_zero() {
+            return Ok(Vec::new());
+        }
+        let mut max_wait = Duration::from_millis(self.protocol.settle_max_ms);
+        if let Some(limit) = limit {
+            max_wait = max_wait.min(limit);
+        }
+        let banner = self.protocol.ready_banner.clone().filter(|b| !b.trim().is_empty());
+
+        let started = Instant::now();
+        let mut last_output = started;
+        let mut drained = Vec::new();
+        while started.elapsed() < max_wait && last_output.elapsed() < quiet {
+            let (bytes, lines) = self.read_unsolicited()?;
+            if bytes == 0 {
+                std::thread::sleep(Duration::from_millis(SETTLE_POLL_MS));
+                continue;
+            }
+            last_output = Instant::now();
+            let ready = banner.as_deref().is_some_and(|b| lines.iter().any(|l| l.contains(b)));
+            drained.extend(lines.iter().map(|l| l.trim().to_string()));
+            if ready {
+                break;
+            }
+        }
+        self.take_unsolicited();
+        Ok(drained)
+    }
+
+    fn take_unsolicited(&mut self) {
+        if let Err(e) = self.poll_unsolicited() {
+            eprintln!("[SERIAL] Failed to read unsolicited output: {}", e);
+        }
+        let partial = std::mem::take(&mut self.rx_pending);
+        self.deliver_unsolicited(&partial);
+    }
+
+    fn deliver_unsolicited(&self, line: &str) -> bool {
+        let line = line.trim();
+        if line.is_empty() {
+            return false;
         }
+        self.trace(Direction::Rx, line);
+        if let Some(sink) = &self.unsolicited {
+            sink(line);
+        }
+        true
+    }
+
+    fn trace(&self, direction: Direction, text: &str) {
+        if let Some(tap) = &self.tap {
+            text.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .for_each(|l| tap(direction, l));
+        }
+    }
+
+    /// Command characters to send for run/stop/RPM/NVS commands
+    pub fn set_command_aliases(&mut self, aliases: CommandAliases) {
+        self.aliases = aliases;
+    }
+
+    /// Protocol parameters used for subsequent commands and uploads
+    pub fn set_protocol(&mut self, protocol: ProtocolProfile) {
+        self.protocol = protocol;
+        // The offer may have changed; ask again on the next upload
+        self.config_protocol = None;
+    }
+
+    /// Use a saved connection profile's parameters, remembering its name so later
+    /// edits to the profile can be applied to this connection
+    pub fn use_profile(&mut self, name: &str, protocol: ProtocolProfile) {
+        self.profile_name = Some(name.to_string());
+        self.protocol = protocol;
+        self.config_protocol = None;
+    }
+
+    /// Drop a port that stopped working, keeping what's needed to open it again
+    pub fn drop_dead_port(&mut self) -> Option<LostPort> {
+        let lost = LostPort {
+            port_name: self.port_name.clone()?,
+            params: self.params.clone(),
+            profile: self.profile_name.clone().map(|name| (name, self.protocol.clone())),
+        };
+        let _ = self.disconnect();
+        Some(lost)
+    }
+
+    /// Open a lost connection the way it was opened before and check our firmware answers
+    pub fn reopen(&mut self, lost: &LostPort) -> Result<(), SerialError> {
+        self.connect(&lost.port_name, &lost.params)?;
+        if let Some((name, protocol)) = &lost.profile {
+            self.use_profile(name, protocol.clone());
+        }
+        let ready = self.settle(None).and_then(|_| self.identify(None));
+        if ready.is_err() {
+            let _ = self.disconnect();
+            return ready;
+        }
+        if let Err(e) = self.read_version(None) {
+            eprintln!("[SERIAL] No version reply after reconnecting: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Trade the open port, and everything learned about the device on it, with `other`.
+    /// Command aliases, taps and timing statistics stay with this connection.
+    pub fn swap_link(&mut self, other: &mut SerialConnection) {
+        std::mem::swap(self, other);
+        std::mem::swap(&mut self.aliases, &mut other.aliases);
+        std::mem::swap(&mut self.tap, &mut other.tap);
+        std::mem::swap(&mut self.unsolicited, &mut other.unsolicited);
+        std::mem::swap(&mut self.timings, &mut other.timings);
+    }
+
+    /// Name of the connection profile the current connection was opened with
+    pub fn profile_name(&self) -> Option<&str> {
+        self.profile_name.as_deref()
+    }
+
+    /// Protocol parameters in use
+    pub fn protocol(&self) -> &ProtocolProfile {
+        &self.protocol
     }
 
     pub fn list_ports() -> Result<Vec<PortInfo>, SerialError> {
@@ -76,42 +882,64 @@ impl SerialConnection {
         Ok(ports
             .into_iter()
             .map(|p| {
-                let port_type = match p.port_type {
-                    serialport::SerialPortType::UsbPort(info) => {
-                        format!(
-                            "USB: {} {}",
-                            info.manufacturer.unwrap_or_default(),
-                            info.product.unwrap_or_default()
-                        )
-                    }
-                    serialport::SerialPortType::BluetoothPort => "Bluetooth".to_string(),
-                    serialport::SerialPortType::PciPort => "PCI".to_string(),
-                    serialport::SerialPortType::Unknown => "Unknown".to_string(),
+                let (port_type, usb) = match p.port_type {
+                    serialport::SerialPortType::UsbPort(info) => (PortKind::Usb, Some(info)),
+                    serialport::SerialPortType::BluetoothPort => (PortKind::Bluetooth, None),
+                    serialport::SerialPortType::PciPort => (PortKind::Pci, None),
+                    serialport::SerialPortType::Unknown => (PortKind::Unknown, None),
                 };
+                let usb = usb.as_ref();
                 PortInfo {
                     name: p.port_name,
                     port_type,
+                    vid: usb.map(|info| info.vid),
+                    pid: usb.map(|info| info.pid),
+                    serial_number: usb.and_then(|info| info.serial_number.clone()),
+                    manufacturer: usb.and_then(|info| info.manufacturer.clone()),
+                    product: usb.and_then(|info| info.product.clone()),
+                    previously_used: false,
+                    last_connected: None,
                 }
             })
             .collect())
     }
 
-    pub fn connect(&mut self, port_name: &str) -> Result<(), SerialError> {
+    pub fn connect(&mut self, port_name: &str, params: &SerialParams) -> Result<(), SerialError> {
         if self.port.is_some() {
             return Err(SerialError::AlreadyConnected);
         }
+        params.validate().map_err(SerialError::OpenError)?;
 
-        let port = serialport::new(port_name, BAUD_RATE)
-            .data_bits(DataBits::Eight)
-            .flow_control(FlowControl::None)
-            .parity(Parity::None)
-            .stop_bits(StopBits::One)
-            .timeout(Duration::from_millis(TIMEOUT_MS))
-            .open()
-            .map_err(|e| SerialError::OpenError(e.to_string()))?;
+        let mut builder = serialport::new(port_name, params.baud_rate)
+            .data_bits(params.data_bits())
+            .flow_control(params.flow_control())
+            .parity(params.parity())
+            .stop_bits(params.stop_bits())
+            .timeout(Duration::from_millis(TIMEOUT_MS));
+        if params.suppress_reset {
+            builder = builder.dtr_on_open(false);
+        }
+        let mut port = builder.open().map_err(|e| SerialError::OpenError(e.to_string()))?;
+        if params.suppress_reset {
+            // RTS drives EN on most boards; DTR again for drivers that ignore dtr_on_open
+            port.write_request_to_send(false)
+                .and_then(|_| port.write_data_terminal_ready(false))
+                .map_err(|e| SerialError::OpenError(e.to_string()))?;
+        }
 
         self.port = Some(port);
         self.port_name = Some(port_name.to_string());
+        self.identity = DeviceIdentity {
+            usb_serial: usb_serial_number(port_name),
+            ..Default::default()
+        };
+        self.last_uptime_ms = None;
+        self.reset_count = 0;
+        self.config_uploaded = false;
+        self.config_protocol = None;
+        self.throughput = LinkThroughput::default();
+        self.rx_pending.clear();
+        self.params = params.clone();
         Ok(())
     }
 
@@ -121,6 +949,15 @@ impl SerialConnection {
         }
         self.port = None;
         self.port_name = None;
+        self.protocol = ProtocolProfile::default();
+        self.profile_name = None;
+        self.identity = DeviceIdentity::default();
+        self.last_uptime_ms = None;
+        self.reset_count = 0;
+        self.config_uploaded = false;
+        self.config_protocol = None;
+        self.rx_pending.clear();
+        self.params = SerialParams::default();
         Ok(())
     }
 
@@ -128,117 +965,223 @@ impl SerialConnection {
         self.port.is_some()
     }
 
-    pub fn send_command(&mut self, cmd: char) -> Result<String, SerialError> {
+    pub fn port_name(&self) -> Option<&str> {
+        self.port_name.as_deref()
+    }
+
+    /// Identity of the connected device, as far as the OS and status responses reveal it
+    pub fn identity(&self) -> &DeviceIdentity {
+        &self.identity
+    }
+
+    /// Ask the firmware for its version and build (the profile's `version_command`) and
+    /// keep them in the identity. Older firmware without the request just doesn't answer.
+    /// `limit` caps the wait below the usual reply timeout.
+    pub fn read_version(&mut self, limit: Option<Duration>) -> Result<(), SerialError> {
+        let Some(request) = self.protocol.version_command.clone() else {
+            return Ok(());
+        };
+        let timeout = Duration::from_millis(VERSION_TIMEOUT_MS).min(limit.unwrap_or(Duration::MAX));
+        let lines = self.transact(&request, timeout, |l| {
+            l.starts_with("FW:") || l.starts_with("NAK:")
+        })?;
+        if let Some(rest) = lines.iter().find_map(|l| l.strip_prefix("FW:")) {
+            let (version, build) = parse_firmware_line(rest);
+            self.identity.firmware_version = Some(version);
+            self.identity.firmware_build = build;
+        }
+        Ok(())
+    }
+
+    /// Warning for firmware older than the protocol profile expects, once its version is known
+    pub fn firmware_warning(&self) -> Option<String> {
+        let version = self.identity.firmware_version.as_deref()?;
+        let minimum = self.protocol.min_firmware_version.as_deref()?;
+        version_older(version, minimum).then(|| {
+            format!("Firmware {} is older than {}, which this app expects; update it before relying on newer features", version, minimum)
+        })
+    }
+
+    /// Send a command, encoded with the current aliases, and verify the reply against
+    /// the command's expectation. Reading stops at a refusal, at the prompt, at the first
+    /// quiet gap after a confirmation, or at the expectation's deadline.
+    pub fn send_command(&mut self, cmd: &DeviceCommand) -> Result<CommandOutcome, SerialError> {
+        self.take_unsolicited();
+        let bytes = cmd.encode(&self.aliases);
+        let expectation = cmd.expectation();
+        self.trace(Direction::Tx, &String::from_utf8_lossy(&bytes));
         let port = self.port.as_mut().ok_or(SerialError::NotConnected)?;
 
-        // Send command
-        port.write_all(&[cmd as u8])
+        let started = Instant::now();
+        port.write_all(&bytes)
             .map_err(|e| SerialError::WriteError(e.to_string()))?;
         port.flush()
             .map_err(|e| SerialError::WriteError(e.to_string()))?;
 
-        // Small delay to allow ESP32 to respond (30ms is enough for simple commands)
-        std::thread::sleep(Duration::from_millis(30));
+        // Reads time out after a short gap of silence, which ends the reply once it has
+        // said what we were waiting for
+        let quiet_gap = Duration::from_millis(self.protocol.response_delay_ms.max(1));
+        let previous_timeout = port.timeout();
+        let _ = port.set_timeout(quiet_gap);
 
-        // Read response
+        let prompt = self.protocol.prompt.as_deref().filter(|p| !p.is_empty());
+        let deadline = started + expectation.within;
         let mut buffer = vec![0u8; 1024];
         let mut response = String::new();
+        // Bytes of `response` already split into complete lines and judged
+        let mut judged = 0;
+        let mut verdict = None;
+        let mut first_byte = None;
 
-        loop {
+        let read = loop {
+            let mut quiet = false;
             match port.read(&mut buffer) {
                 Ok(n) if n > 0 => {
+                    first_byte = first_byte.or(Some(started.elapsed()));
                     response.push_str(&String::from_utf8_lossy(&buffer[..n]));
+                    while let Some(pos) = response[judged..].find('\n') {
+                        let line = &response[judged..judged + pos];
+                        judged += pos + 1;
+                        match expectation.judge(line) {
+                            Some(OutcomeStatus::Rejected) => verdict = Some(OutcomeStatus::Rejected),
+                            Some(status) => verdict = verdict.or(Some(status)),
+                            None => {}
+                        }
+                    }
+
+                    // Prompt means the firmware is done with this command
+                    if let Some(prompt) = prompt {
+                        if let Some(stripped) = response.trim_end().strip_suffix(prompt) {
+                            response = stripped.to_string();
+                            break Ok(());
+                        }
+                    }
                 }
-                Ok(_) => break,
-                Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => break,
-                Err(e) => return Err(SerialError::ReadError(e.to_string())),
+                Ok(_) => quiet = true,
+                Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => quiet = true,
+                Err(e) => break Err(SerialError::ReadError(e.to_string())),
             }
-        }
 
-        Ok(response)
+            let settled = match verdict {
+                Some(OutcomeStatus::Rejected) => true,
+                Some(_) => quiet,
+                // Nothing to wait for: the reply ends with its first pause
+                None => quiet && !expectation.is_checked() && !response.is_empty(),
+            };
+            if settled || Instant::now() >= deadline {
+                break Ok(());
+            }
+        };
+        let _ = port.set_timeout(previous_timeout);
+        read?;
+
+        self.trace(Direction::Rx, &response);
+        self.record_timing(&cmd.kind(), first_byte, started.elapsed());
+        Ok(CommandOutcome::evaluate(cmd, response, started.elapsed()))
     }
 
-    pub fn send_config(&mut self, config: &str) -> Result<UploadResult, SerialError> {
+    /// Check that the firmware on the other end answers the status query.
+    /// Anything else (silence, another device's chatter) is `NotOurDevice` with the bytes seen.
+    /// `limit` bounds the whole exchange, retries and reads included; once it runs out the
+    /// device counts as silent.
+    pub fn identify(&mut self, limit: Option<Duration>) -> Result<(), SerialError> {
+        let delay = Duration::from_millis(self.protocol.response_delay_ms);
         let port = self.port.as_mut().ok_or(SerialError::NotConnected)?;
+        let deadline = limit.map(|limit| Instant::now() + limit);
+        let left = || deadline.map_or(Duration::MAX, |d| d.saturating_duration_since(Instant::now()));
+        let previous_timeout = port.timeout();
+        let mut received = Vec::new();
+        let mut buffer = [0u8; 256];
 
-        // Create preview of config (first 200 chars)
-        let config_preview: String = config.chars().take(500).collect();
-
-        // Clear any pending input first
-        let _ = port.clear(serialport::ClearBuffer::All);
-
-        // Send config wrapped in <CFG>...<END> markers
-        let full_message = format!("<CFG>\n{}\n<END>\n", config);
-        let bytes_to_send = full_message.len();
-        
-        // Debug: log message size
-        eprintln!("[SERIAL] Sending config: {} bytes", bytes_to_send);
-        
-        // Send in small chunks to avoid overwhelming ESP32 serial buffer (default 256 bytes)
-        let bytes = full_message.as_bytes();
-        let chunk_size = 64;
-        let mut chunks_sent = 0;
-        
-        for chunk in bytes.chunks(chunk_size) {
-            port.write_all(chunk)
-                .map_err(|e| SerialError::WriteError(e.to_string()))?;
-            port.flush()
-                .map_err(|e| SerialError::WriteError(e.to_string()))?;
-            chunks_sent += 1;
-            // Small delay between chunks to let ESP32 buffer drain
-            std::thread::sleep(Duration::from_millis(2));
-        }
+        let mut answered = Ok(false);
+        for attempt in 0..IDENTIFY_ATTEMPTS {
+            if attempt > 0 {
+                std::thread::sleep(Duration::from_millis(IDENTIFY_RETRY_DELAY_MS).min(left()));
+            }
+            if left().is_zero() {
+                break;
+            }
+            if let Err(e) = port.write_all(b"?").and_then(|_| port.flush()) {
+                answered = Err(SerialError::WriteError(e.to_string()));
+                break;
+            }
+            std::thread::sleep(delay.min(left()));
 
-        // Wait for ESP32 to receive and process config
-        let mut buffer = vec![0u8; 4096];
-        let mut response = String::new();
-        const RESPONSE_CAP: usize = 16 * 1024;
-        let start = std::time::Instant::now();
-        let max_wait = Duration::from_millis(15000); // 15 second max wait for large configs
-
-        let mut saw_ack = false;
-        let mut nak_line: Option<String> = None;
-
-        let scan_for_ack_nak = |s: &str| {
-            let mut ack = false;
-            let mut nak: Option<String> = None;
-            for line in s.lines() {
-                let t = line.trim();
-                if t == "ACK" {
-                    ack = true;
+            let mut reply = Vec::new();
+            while !left().is_zero() {
+                let _ = port.set_timeout(previous_timeout.min(left()));
+                match port.read(&mut buffer) {
+                    Ok(n) if n > 0 => reply.extend_from_slice(&buffer[..n]),
+                    Ok(_) => break,
+                    Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => break,
+                    Err(e) => {
+                        answered = Err(SerialError::ReadError(e.to_string()));
+                        break;
+                    }
                 }
-                if t.starts_with("NAK:") {
-                    nak = Some(t.to_string());
+                if reply.len() >= IDENTIFY_RAW_CAP {
+                    break;
                 }
             }
-            (ack, nak)
-        };
 
-        // Keep reading until we get ACK/NAK or timeout
-        while start.elapsed() < max_wait {
-            std::thread::sleep(Duration::from_millis(100));
-            
-            match port.read(&mut buffer) {
-                Ok(n) if n > 0 => {
-                    response.push_str(&String::from_utf8_lossy(&buffer[..n]));
+            if answered.is_err() || is_recognizable_reply(&String::from_utf8_lossy(&reply)) {
+                answered = answered.map(|_| true);
+                break;
+            }
+            received.extend_from_slice(&reply);
+        }
+        let _ = port.set_timeout(previous_timeout);
 
-                    // Cap response to avoid unbounded growth
-                    if response.len() > RESPONSE_CAP {
-                        let keep_from = response.len() - RESPONSE_CAP;
-                        response = response.split_off(keep_from);
-                    }
-                    
-                    let (ack, nak) = scan_for_ack_nak(&response);
-                    if ack {
-                        saw_ack = true;
-                    }
-                    if nak.is_some() {
-                        nak_line = nak;
-                    }
+        if answered? {
+            return Ok(());
+        }
+        received.truncate(IDENTIFY_RAW_CAP);
+        Err(SerialError::NotOurDevice(received))
+    }
 
-                    // Check if we have a complete response (ACK or NAK)
-                    if saw_ack || nak_line.is_some() {
-                        break;
+    /// Send a request line and collect trimmed, non-empty response lines until
+    /// `is_last` matches one (that line is included) or `timeout` elapses
+    pub fn transact<F>(
+        &mut self,
+        request: &str,
+        timeout: Duration,
+        is_last: F,
+    ) -> Result<Vec<String>, SerialError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.take_unsolicited();
+        self.trace(Direction::Tx, request);
+        let port = self.port.as_mut().ok_or(SerialError::NotConnected)?;
+
+        port.write_all(format!("{}\n", request).as_bytes())
+            .map_err(|e| SerialError::WriteError(e.to_string()))?;
+        port.flush()
+            .map_err(|e| SerialError::WriteError(e.to_string()))?;
+
+        let mut buffer = vec![0u8; 1024];
+        let mut pending = String::new();
+        let mut lines = Vec::new();
+        let mut done = false;
+        let mut first_byte = None;
+        let start = Instant::now();
+
+        while !done && start.elapsed() < timeout {
+            match port.read(&mut buffer) {
+                Ok(n) if n > 0 => {
+                    first_byte = first_byte.or(Some(start.elapsed()));
+                    pending.push_str(&String::from_utf8_lossy(&buffer[..n]));
+                    while let Some(pos) = pending.find('\n') {
+                        let line: String = pending.drain(..=pos).collect();
+                        let line = line.trim();
+                        if line.is_empty() {
+                            continue;
+                        }
+                        done = is_last(line);
+                        lines.push(line.to_string());
+                        if done {
+                            break;
+                        }
                     }
                 }
                 Ok(_) => {}
@@ -247,73 +1190,539 @@ impl SerialConnection {
             }
         }
 
-        // If we saw ACK, drain briefly so trailing logs don't pollute the next command.
-        if saw_ack {
-            let drain_start = std::time::Instant::now();
-            while drain_start.elapsed() < Duration::from_millis(250) {
-                match port.read(&mut buffer) {
-                    Ok(n) if n > 0 => {
-                        response.push_str(&String::from_utf8_lossy(&buffer[..n]));
-                        if response.len() > RESPONSE_CAP {
-                            let keep_from = response.len() - RESPONSE_CAP;
-                            response = response.split_off(keep_from);
-                        }
-                    }
-                    Ok(_) => {}
-                    Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => break,
-                    Err(_) => break,
+        self.trace(Direction::Rx, &lines.join("\n"));
+        self.record_timing(&request_kind(request), first_byte, start.elapsed());
+        if done {
+            Ok(lines)
+        } else {
+            Err(SerialError::Timeout)
+        }
+    }
+
+    fn record_timing(&mut self, kind: &str, first_byte: Option<Duration>, total: Duration) {
+        let firmware = self.identity.firmware_version.as_deref();
+        self.timings.record(firmware, kind, first_byte, total);
+    }
+
+    /// Reply times per firmware build and command since the app started (or the last clear)
+    pub fn timing_stats(&self) -> TimingStats {
+        self.timings.stats()
+    }
+
+    pub fn clear_timing_stats(&mut self) {
+        self.timings.clear();
+    }
+
+    /// Expected duration of uploading `config`, from the speed of earlier uploads on this
+    /// port or, before the first one, from the baud rate and `pacing`
+    pub fn estimate_upload(&self, config: &str, pacing: ChunkPacing) -> UploadEstimate {
+        let version = self.config_protocol.unwrap_or(LEGACY_CONFIG_PROTOCOL);
+        let frame_bytes = config_start_marker(version, config).len() + config.len() + CONFIG_END_MARKER.len();
+        let per_chunk = if version >= WINDOWED_CONFIG_PROTOCOL {
+            Duration::ZERO
+        } else {
+            Duration::from_millis(pacing.chunk_delay_ms)
+        };
+        self.throughput
+            .estimate(frame_bytes, self.params.baud_rate, pacing.chunk_size, per_chunk)
+    }
+
+    /// Current RPM and run state via the short `<RPM>` query, without the full status dump
+    pub fn get_rpm_fast(&mut self) -> Result<RpmReading, SerialError> {
+        let lines = self.transact(FAST_RPM_QUERY, Duration::from_millis(FAST_RPM_TIMEOUT_MS), |l| {
+            l.starts_with("RPM:") || l.starts_with("NAK:")
+        })?;
+        check_nak(&lines)?;
+        lines
+            .last()
+            .and_then(|l| parse_rpm_reading(l))
+            .ok_or_else(|| SerialError::DeviceError("Malformed RPM reply".into()))
+    }
+
+    /// Drive the DTR and RTS modem control lines; `None` leaves a line as it is.
+    /// With the usual ESP32 auto-reset circuit RTS pulls EN (reset) low and DTR pulls
+    /// IO0 (boot mode) low.
+    pub fn set_modem_lines(&mut self, dtr: Option<bool>, rts: Option<bool>) -> Result<(), SerialError> {
+        let port = self.port.as_mut().ok_or(SerialError::NotConnected)?;
+        if let Some(dtr) = dtr {
+            port.write_data_terminal_ready(dtr)
+                .map_err(|e| SerialError::WriteError(e.to_string()))?;
+        }
+        if let Some(rts) = rts {
+            port.write_request_to_send(rts)
+                .map_err(|e| SerialError::WriteError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Restart the board with esptool's DTR/RTS sequence and collect its boot banner.
+    /// EN is pulsed through RTS; for `bootloader` IO0 is held low through DTR while EN
+    /// is released, so the ROM stays in download mode instead of starting the firmware.
+    pub fn reset(&mut self, bootloader: bool) -> Result<BootReport, SerialError> {
+        // Older output must not pass for the banner
+        self.take_unsolicited();
+        self.set_modem_lines(Some(false), Some(true))?;
+        std::thread::sleep(Duration::from_millis(HARD_RESET_PULSE_MS));
+        if bootloader {
+            self.set_modem_lines(Some(true), Some(false))?;
+            std::thread::sleep(Duration::from_millis(BOOTLOADER_HOLD_MS));
+            self.set_modem_lines(Some(false), None)?;
+        } else {
+            self.set_modem_lines(None, Some(false))?;
+        }
+        // This restart was asked for; don't report it as a spontaneous reset
+        self.last_uptime_ms = None;
+
+        let banner = self.wait_for_boot(None)?;
+        Ok(BootReport {
+            bootloader,
+            reset_reason: banner.iter().find_map(|l| parse_reset_reason(l)),
+            download_mode: banner
+                .iter()
+                .any(|l| DOWNLOAD_MODE_MARKERS.iter().any(|m| l.contains(m))),
+            banner,
+        })
+    }
+
+    /// Change the firmware's log verbosity using the profile's log level command
+    pub fn set_log_level(&mut self, level: DeviceLogLevel) -> Result<(), SerialError> {
+        let template = self
+            .protocol
+            .log_level_command
+            .clone()
+            .ok_or_else(|| SerialError::Unsupported("log level control".into()))?;
+        let request = template.replace("{level}", level.as_str());
+
+        let lines = self.transact(&request, Duration::from_millis(TIMEOUT_MS), |l| {
+            l == "ACK" || l.starts_with("NAK:")
+        })?;
+        check_nak(&lines)
+    }
+
+    /// Ask the firmware to suppress periodic output for `seconds` (0 ends the window).
+    /// The device confirms with `QUIET_OK` (or `ACK`).
+    pub fn request_quiet(&mut self, seconds: u32) -> Result<(), SerialError> {
+        let template = self
+            .protocol
+            .quiet_command
+            .clone()
+            .ok_or_else(|| SerialError::Unsupported("quiet window".into()))?;
+        let request = template.replace("{seconds}", &seconds.to_string());
+
+        let lines = self.transact(&request, Duration::from_millis(TIMEOUT_MS), |l| {
+            l == "QUIET_OK" || l == "ACK" || l.starts_with("NAK:")
+        })?;
+        check_nak(&lines)
+    }
+
+    /// Read back the config stored on the device with the profile's readback command
+    pub fn read_config(&mut self) -> Result<String, SerialError> {
+        let request = self
+            .protocol
+            .config_readback_command
+            .clone()
+            .ok_or_else(|| SerialError::Unsupported("config readback".into()))?;
+        let start_marker = CONFIG_START_MARKER.trim();
+        let end_marker = CONFIG_END_MARKER.trim();
+
+        let timeout = Duration::from_millis(self.protocol.upload_timeout_ms);
+        let lines = self.transact(&request, timeout, |l| l == end_marker || l.starts_with("NAK:"))?;
+        check_nak(&lines)?;
+        let start = lines
+            .iter()
+            .position(|l| l == start_marker)
+            .ok_or_else(|| SerialError::DeviceError("Readback reply has no config frame".into()))?;
+        Ok(lines[start + 1..lines.len() - 1].join("\n"))
+    }
+
+    /// Upload a config, forwarding device-reported progress and output lines to `on_event`.
+    ///
+    /// When the profile supports it, a quiet window is negotiated and device logging is
+    /// turned down for the upload so chatty output doesn't interleave with the ACK stream.
+    pub fn send_config<F>(
+        &mut self,
+        config: &str,
+        on_event: F,
+    ) -> Result<UploadResult, SerialError>
+    where
+        F: FnMut(UploadEvent),
+    {
+        self.send_config_cancellable(config, ChunkPacing::default(), &|| false, on_event)
+    }
+
+    /// `send_config` with the given chunk pacing that gives up with `SerialError::Cancelled`
+    /// once `cancelled` returns true, between chunks or while waiting for the device's
+    /// verdict. A frame cut short is left unterminated, so the firmware drops it instead
+    /// of applying part of it.
+    pub fn send_config_cancellable<F>(
+        &mut self,
+        config: &str,
+        pacing: ChunkPacing,
+        cancelled: &dyn Fn() -> bool,
+        mut on_event: F,
+    ) -> Result<UploadResult, SerialError>
+    where
+        F: FnMut(UploadEvent),
+    {
+        on_event(UploadEvent::Sent(UploadProgress::new(UploadStage::Preparing, 0, 0, 0)));
+        let quiet_window = self.protocol.quiet_command.is_some();
+        if quiet_window {
+            if let Err(e) = self.request_quiet(self.protocol.quiet_window_secs) {
+                eprintln!("[SERIAL] Device did not confirm quiet window: {}", e);
+            }
+        }
+
+        let quiet_logs = self.protocol.log_level_command.is_some();
+        if quiet_logs {
+            if let Err(e) = self.set_log_level(self.protocol.upload_log_level) {
+                eprintln!("[SERIAL] Could not lower device log level: {}", e);
+            }
+        }
+
+        let result = if cancelled() {
+            Err(SerialError::Cancelled)
+        } else {
+            self.config_protocol()
+                .and_then(|version| self.stream_config(config, version, pacing, cancelled, on_event))
+        };
+
+        if quiet_logs && self.is_connected() {
+            if let Err(e) = self.set_log_level(self.protocol.default_log_level) {
+                eprintln!("[SERIAL] Could not restore device log level: {}", e);
+            }
+        }
+
+        // End the quiet window early rather than waiting for it to expire
+        if quiet_window && self.is_connected() {
+            if let Err(e) = self.request_quiet(0) {
+                eprintln!("[SERIAL] Could not end quiet window: {}", e);
+            }
+        }
+
+        if result.as_ref().is_ok_and(|r| r.success) {
+            self.config_uploaded = true;
+        }
+        result
+    }
+
+    /// Whether a config was accepted by the device since connecting
+    pub fn config_uploaded(&self) -> bool {
+        self.config_uploaded
+    }
+
+    /// Config framing version for this connection. Offered once as `<CFG v<n>>`; the
+    /// firmware answers `CFG:v<m>` with the version it supports. Firmware that doesn't
+    /// answer or refuses the offer gets the legacy frame.
+    fn config_protocol(&mut self) -> Result<u32, SerialError> {
+        if let Some(version) = self.config_protocol {
+            return Ok(version);
+        }
+        let offered = self.protocol.config_protocol_version;
+        let version = if offered <= LEGACY_CONFIG_PROTOCOL {
+            LEGACY_CONFIG_PROTOCOL
+        } else {
+            let request = format!("<CFG v{}>", offered);
+            let timeout = Duration::from_millis(CONFIG_NEGOTIATE_TIMEOUT_MS);
+            match self.transact(&request, timeout, |l| l.starts_with("CFG:v") || l.starts_with("NAK:")) {
+                Ok(lines) => lines
+                    .iter()
+                    .find_map(|l| l.strip_prefix("CFG:v"))
+                    .and_then(|v| v.trim().parse::<u32>().ok())
+                    .map_or(LEGACY_CONFIG_PROTOCOL, |v| v.clamp(LEGACY_CONFIG_PROTOCOL, offered)),
+                Err(SerialError::Timeout) => LEGACY_CONFIG_PROTOCOL,
+                Err(e) => return Err(e),
+            }
+        };
+        eprintln!("[SERIAL] Using config protocol v{}", version);
+        self.config_protocol = Some(version);
+        Ok(version)
+    }
+
+    /// Stream the config in framing `version` and wait for the device's ACK/NAK. From v2
+    /// it goes out deflated if the profile says the firmware inflates uploads. From v4
+    /// an upload of the same config that was interrupted earlier continues from the last
+    /// payload byte the device credited; if the device refuses, the whole frame is sent.
+    fn stream_config<F>(
+        &mut self,
+        config: &str,
+        version: u32,
+        pacing: ChunkPacing,
+        cancelled: &dyn Fn() -> bool,
+        mut on_event: F,
+    ) -> Result<UploadResult, SerialError>
+    where
+        F: FnMut(UploadEvent),
+    {
+        let deflated = if self.protocol.compress_uploads && version > LEGACY_CONFIG_PROTOCOL {
+            match deflate_config(config) {
+                // Not worth inflating on the device unless it shrinks the transfer
+                Ok(deflated) => Some(deflated).filter(|d| d.len() < config.len()),
+                Err(e) => {
+                    eprintln!("[SERIAL] Couldn't compress the config, sending it as is: {}", e);
+                    None
                 }
-                std::thread::sleep(Duration::from_millis(20));
             }
+        } else {
+            None
+        };
+        let frame = UploadFrame {
+            config,
+            compressed: deflated.is_some(),
+            payload: deflated.unwrap_or_else(|| config.as_bytes().to_vec()),
+            version,
+            pacing,
+        };
+
+        let crc32 = config_crc32(config);
+        let mut resume_at = self
+            .partial_upload
+            .as_ref()
+            .filter(|p| {
+                version >= RESUMABLE_CONFIG_PROTOCOL
+                    && p.crc32 == crc32
+                    && p.len == config.len()
+                    && p.compressed == frame.compressed
+            })
+            .map(|p| p.offset);
+        loop {
+            let result = self.send_frame(&frame, resume_at, cancelled, &mut on_event);
+            let refused = resume_at.is_some()
+                && result
+                    .as_ref()
+                    .is_ok_and(|r| r.error_message.as_deref().is_some_and(|e| e.contains(RESUME_REFUSED)));
+            if !refused {
+                return result;
+            }
+            eprintln!("[SERIAL] Device refused to resume the upload, sending it again in full");
+            resume_at = None;
         }
+    }
 
-        // Check for empty response (timeout without acknowledgment)
-        if response.trim().is_empty() {
-            return Ok(UploadResult {
-                success: false,
-                bytes_sent: bytes_to_send,
-                chunks_sent,
-                raw_response: response,
-                config_preview,
-                error_message: Some("No response from ESP32 - config may not have been applied (timeout)".to_string()),
-            });
+    /// Remember how far an interrupted upload got, so the next attempt can resume there.
+    /// Binary framing sends the frame as one packet, so there is nothing to resume.
+    fn note_partial_upload(&mut self, frame: &UploadFrame, offset: usize) {
+        let resumable = frame.version >= RESUMABLE_CONFIG_PROTOCOL && self.protocol.framing == FrameEncoding::Text;
+        self.partial_upload = (resumable && offset > 0).then(|| PartialUpload {
+            crc32: config_crc32(frame.config),
+            len: frame.config.len(),
+            compressed: frame.compressed,
+            offset: offset.min(frame.payload.len()),
+        });
+        if let Some(partial) = &self.partial_upload {
+            eprintln!("[SERIAL] Upload can resume at byte {} of {}", partial.offset, frame.payload.len());
         }
+    }
 
-        // Check for NAK (error) response first
-        if let Some(line) = nak_line {
-            return Ok(UploadResult {
-                success: false,
-                bytes_sent: bytes_to_send,
-                chunks_sent,
-                raw_response: response,
-                config_preview,
-                error_message: Some(line),
-            });
+    /// Send one config frame, the whole payload or from `resume_at` on
+    fn send_frame(
+        &mut self,
+        frame: &UploadFrame,
+        resume_at: Option<usize>,
+        cancelled: &dyn Fn() -> bool,
+        on_event: &mut dyn FnMut(UploadEvent),
+    ) -> Result<UploadResult, SerialError> {
+        let UploadFrame { config, version, pacing, .. } = *frame;
+        let mut fields = String::new();
+        if frame.compressed {
+            fields.push_str(&format!(" ENC=DEFLATE ZLEN={}", frame.payload.len()));
+        }
+        if let Some(offset) = resume_at {
+            fields.push_str(&format!(" AT={}", offset));
         }
+        let start_marker = if fields.is_empty() {
+            config_start_marker(version, config)
+        } else {
+            config_marker_with(version, config, &fields)
+        };
+        let base = resume_at.unwrap_or(0);
+        let mut spec = self.frame_spec(&start_marker, CONFIG_END_MARKER);
+        spec.chunk_size = pacing.chunk_size;
+        spec.pacing = Pacing::Delay(Duration::from_millis(pacing.chunk_delay_ms));
+        if version >= WINDOWED_CONFIG_PROTOCOL {
+            spec.pacing = Pacing::Windowed {
+                window: pacing.window(),
+                credit: UPLOAD_CREDIT_PREFIX,
+                timeout: Duration::from_millis(UPLOAD_CREDIT_TIMEOUT_MS),
+            };
+        }
+        let estimate_ms = self.estimate_upload(config, pacing).eta_ms;
+        self.take_unsolicited();
+        let port = self.port.as_mut().ok_or(SerialError::NotConnected)?;
 
-        // Verify ACK was received
-        if !saw_ack {
-            let preview: String = response.chars().take(300).collect();
-            return Ok(UploadResult {
-                success: false,
-                bytes_sent: bytes_to_send,
-                chunks_sent,
-                raw_response: response,
-                config_preview,
-                error_message: Some(format!("No ACK received. Response preview: {}", preview)),
-            });
+        let config_preview = preview::build_preview(config);
+
+        // Clear any pending input first
+        let _ = port.clear(serialport::ClearBuffer::All);
+
+        // Debug: log message size
+        match resume_at {
+            Some(offset) => eprintln!("[SERIAL] Resuming config at byte {} of {}", offset, frame.payload.len()),
+            None if frame.compressed => {
+                eprintln!("[SERIAL] Sending config: {} bytes deflated to {}", config.len(), frame.payload.len())
+            }
+            None => eprintln!("[SERIAL] Sending config: {} bytes", frame_config(config).len()),
+        }
+
+        let mut last_percent: Option<u8> = None;
+        let mut last_progress_at: Option<std::time::Instant> = None;
+        let progress_interval = Duration::from_millis(UPLOAD_PROGRESS_INTERVAL_MS);
+        let started = std::time::Instant::now();
+        // Chunk and line callbacks both report through `on_event`
+        let on_event = RefCell::new(on_event);
+        let mut last_sent_at: Option<std::time::Instant> = None;
+        let mut chunks_sent = 0;
+        let mut frame_sent = 0;
+
+        // Surface device progress as lines complete, rate-limited; other lines pass through
+        let outcome = FramedTransfer::new(&mut **port, &spec).cancel_when(cancelled).send(
+            &frame.payload[base..],
+            |sent, total| {
+                chunks_sent += 1;
+                frame_sent = sent;
+                let done = sent >= total;
+                if done || last_sent_at.is_none_or(|t| t.elapsed() >= progress_interval) {
+                    last_sent_at = Some(std::time::Instant::now());
+                    let stage = if done { UploadStage::AwaitingAck } else { UploadStage::Sending };
+                    // Progress covers the whole config, including what an earlier attempt sent
+                    let progress = UploadProgress::new(stage, base + sent, chunks_sent, base + total);
+                    (on_event.borrow_mut())(UploadEvent::Sent(progress));
+                }
+            },
+            |line| {
+                let Some(percent) = parse_device_progress(line) else {
+                    (on_event.borrow_mut())(UploadEvent::Line(line.to_string()));
+                    return;
+                };
+                if last_percent == Some(percent) {
+                    return;
+                }
+                let due = last_progress_at.is_none_or(|t| t.elapsed() >= progress_interval);
+                if due || percent == 100 {
+                    last_percent = Some(percent);
+                    last_progress_at = Some(std::time::Instant::now());
+                    let elapsed = started.elapsed().as_millis() as u64;
+                    let eta_ms = if percent == 0 {
+                        estimate_ms.saturating_sub(elapsed)
+                    } else {
+                        elapsed * (100 - percent.min(100) as u64) / percent as u64
+                    };
+                    (on_event.borrow_mut())(UploadEvent::Progress(DeviceProgress {
+                        percent,
+                        line: line.to_string(),
+                        eta_ms: Some(eta_ms),
+                    }));
+                }
+            },
+        );
+        // Credits count frame bytes, start line included
+        let acked_payload = |acked: usize| base + acked.saturating_sub(start_marker.len());
+        let outcome = match outcome {
+            Err(TransferError::Cancelled(sent)) => {
+                // Don't let bytes still queued for the wire complete the frame after all
+                let _ = port.clear(serialport::ClearBuffer::Output);
+                eprintln!("[SERIAL] Config upload cancelled after {} bytes", sent);
+                return Err(SerialError::Cancelled);
+            }
+            Err(TransferError::Stalled(acked)) => {
+                self.note_partial_upload(frame, acked_payload(acked));
+                return Err(TransferError::Stalled(acked).into());
+            }
+            Err(e @ (TransferError::Write(_) | TransferError::Read(_))) => {
+                // Lost the port: a chunk is only sent once the device credited all but
+                // a window's worth before it, so that much is safely on the device
+                let credited = frame_sent.saturating_sub(pacing.window());
+                self.note_partial_upload(frame, acked_payload(credited));
+                return Err(e.into());
+            }
+            other => other?,
+        };
+        if !outcome.saw_ack && outcome.nak_line.is_none() {
+            // Timed out mid-frame: the device may still hold what it credited
+            self.note_partial_upload(frame, acked_payload(outcome.acked_bytes));
+        } else {
+            // Applied, or rejected outright; either way nothing is left to resume
+            self.partial_upload = None;
+        }
+        // Only acknowledged uploads measure the link; a timeout would skew the rate
+        if outcome.saw_ack && outcome.nak_line.is_none() {
+            self.throughput.record(outcome.bytes_sent, started.elapsed());
         }
 
+        let checksum = if version <= LEGACY_CONFIG_PROTOCOL {
+            ChecksumStatus::Unchecked
+        } else if outcome.nak_line.as_deref().is_some_and(|l| l.to_uppercase().contains("CRC")) {
+            ChecksumStatus::Mismatch
+        } else if outcome.saw_ack && outcome.nak_line.is_none() {
+            ChecksumStatus::Verified
+        } else {
+            ChecksumStatus::Unchecked
+        };
+        let error_message = if outcome.response.trim().is_empty() {
+            // Timeout without any acknowledgment
+            Some("No response from ESP32 - config may not have been applied (timeout)".to_string())
+        } else if checksum == ChecksumStatus::Mismatch {
+            Some(format!(
+                "{} - the config was corrupted in transit (CRC32 mismatch); try another USB cable",
+                outcome.nak_line.as_deref().unwrap_or_default()
+            ))
+        } else if let Some(line) = &outcome.nak_line {
+            Some(line.clone())
+        } else if !outcome.saw_ack {
+            let preview: String = outcome.response.chars().take(300).collect();
+            Some(format!("No ACK received. Response preview: {}", preview))
+        } else {
+            None
+        };
+
         Ok(UploadResult {
-            success: true,
-            bytes_sent: bytes_to_send,
-            chunks_sent,
-            raw_response: response,
+            success: error_message.is_none(),
+            bytes_sent: outcome.bytes_sent,
+            chunks_sent: outcome.chunks_sent,
+            raw_response: outcome.response,
             config_preview,
-            error_message: None,
+            error_message,
+            phases: Vec::new(),
+            protocol_version: version,
+            checksum,
+            resumed_from: resume_at,
+            compressed_bytes: frame.compressed.then_some(frame.payload.len()),
         })
     }
 
+    /// Transfer parameters for a `start`...`end` framed upload under the current profile
+    pub fn frame_spec(&self, start: &str, end: &str) -> FrameSpec {
+        FrameSpec {
+            start_marker: start.to_string(),
+            end_marker: end.to_string(),
+            // Small chunks avoid overwhelming the ESP32 serial buffer (default 256 bytes)
+            chunk_size: UPLOAD_CHUNK_SIZE,
+            pacing: Pacing::Delay(Duration::from_millis(UPLOAD_CHUNK_DELAY_MS)),
+            ack_tokens: self.protocol.ack_tokens.clone(),
+            nak_tokens: self.protocol.nak_tokens.clone(),
+            match_partial: false,
+            response_timeout: Duration::from_millis(self.protocol.upload_timeout_ms),
+            drain: Duration::from_millis(UPLOAD_DRAIN_MS),
+            stop_on_eof: false,
+            encoding: self.protocol.framing,
+        }
+    }
+
+    /// Send a framed payload (see `frame_spec`) and wait for the device's verdict
+    pub fn send_framed<C>(
+        &mut self,
+        spec: &FrameSpec,
+        payload: &[u8],
+        on_chunk: C,
+    ) -> Result<TransferOutcome, SerialError>
+    where
+        C: FnMut(usize, usize),
+    {
+        self.take_unsolicited();
+        let port = self.port.as_mut().ok_or(SerialError::NotConnected)?;
+        let _ = port.clear(serialport::ClearBuffer::Input);
+        Ok(FramedTransfer::new(&mut **port, spec).send(payload, on_chunk, |_| {})?)
+    }
+
     pub fn get_status(&mut self) -> Result<DeviceStatus, SerialError> {
         if !self.is_connected() {
             return Ok(DeviceStatus {
@@ -322,7 +1731,7 @@ impl SerialConnection {
             });
         }
 
-        let response = self.send_command('?')?;
+        let response = self.send_command(&DeviceCommand::Status)?.response;
 
         // Parse response - format: "RPM:xxxx STATE:RUN|STOP"
         let mut status = DeviceStatus {
@@ -330,12 +1739,29 @@ impl SerialConnection {
             port_name: self.port_name.clone(),
             running: false,
             rpm: 0,
+            fault: None,
+            device_id: None,
+            firmware_version: None,
+            firmware_build: None,
+            firmware_warning: None,
+            loaded_signal: None,
+            uptime_ms: None,
+            reset_count: self.reset_count,
+            reset_detected: false,
             raw_response: response.clone(),
         };
 
         // Simple parsing - adjust based on actual ESP32 response format
         for line in response.lines() {
             let line = line.trim();
+            // Signal names are free text, keep them out of the keyword checks below
+            if let Some(name) = line.strip_prefix("SIG:") {
+                let name = name.trim();
+                if !name.is_empty() && name != "NONE" {
+                    status.loaded_signal = Some(name.to_string());
+                }
+                continue;
+            }
             if line.contains("RPM") {
                 if let Some(rpm_str) = line.split(':').nth(1) {
                     if let Ok(rpm) = rpm_str.trim().parse::<u16>() {
@@ -349,18 +1775,167 @@ impl SerialConnection {
             if line.contains("STOP") || line.contains("Stopped") {
                 status.running = false;
             }
+            if let Some(fault) = line.strip_prefix("FAULT:") {
+                status.fault = Some(fault.trim().to_string());
+            }
+            if let Some(id) = line.strip_prefix("ID:") {
+                status.device_id = Some(id.trim().to_string());
+            }
+            if let Some(rest) = line.strip_prefix("FW:") {
+                let (version, build) = parse_firmware_line(rest);
+                status.firmware_version = Some(version);
+                status.firmware_build = build;
+            }
+            if let Some(uptime) = line.strip_prefix("UPTIME:") {
+                status.uptime_ms = uptime.trim().parse().ok();
+            }
+        }
+
+        // The device clock only goes backwards when it restarted
+        if let Some(uptime) = status.uptime_ms {
+            if self.last_uptime_ms.is_some_and(|last| uptime < last) {
+                self.reset_count += 1;
+                status.reset_count = self.reset_count;
+                status.reset_detected = true;
+            }
+            self.last_uptime_ms = Some(uptime);
+        }
+
+        if status.device_id.is_some() {
+            self.identity.device_id = status.device_id.clone();
+        }
+        if status.firmware_version.is_some() {
+            self.identity.firmware_version = status.firmware_version.clone();
+            if status.firmware_build.is_some() {
+                self.identity.firmware_build = status.firmware_build.clone();
+            }
         }
+        // Firmware that only answers the version request at connect still shows it here
+        status.firmware_version = self.identity.firmware_version.clone();
+        status.firmware_build = self.identity.firmware_build.clone();
+        status.firmware_warning = self.firmware_warning();
 
         Ok(status)
     }
 }
 
-// Thread-safe global connection
+/// Port work queued for the serial worker
+type Request = Box<dyn FnOnce(&mut SerialConnection) + Send>;
+
+const WORKER_STOPPED: &str = "Serial worker has stopped";
+const WORKER_PANICKED: &str = "Serial operation panicked";
+
+// Handle to the thread that owns the connection. Callers queue work and wait for
+// their own reply, so each port operation runs start to finish without anyone
+// blocking on a shared lock, and a long upload only delays the requests behind it.
 #[derive(Clone)]
-pub struct SerialState(pub Arc<Mutex<SerialConnection>>);
+pub struct SerialState {
+    requests: mpsc::Sender<Request>,
+    // Requests queued or running, so background polling can stay out of the way
+    pending: Arc<AtomicUsize>,
+}
 
 impl Default for SerialState {
     fn default() -> Self {
-        SerialState(Arc::new(Mutex::new(SerialConnection::new())))
+        let (requests, queue) = mpsc::channel::<Request>();
+        std::thread::Builder::new()
+            .name("serial-worker".into())
+            .spawn(move || {
+                let mut connection = SerialConnection::new();
+                for request in queue {
+                    // The caller sees the dropped reply; the worker keeps serving
+                    if std::panic::catch_unwind(AssertUnwindSafe(|| request(&mut connection))).is_err() {
+                        eprintln!("[SERIAL] {}", WORKER_PANICKED);
+                    }
+                }
+            })
+            .expect("failed to start the serial worker thread");
+        SerialState {
+            requests,
+            pending: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+/// Counts a request as pending until it has run (or was dropped unrun)
+struct PendingGuard(Arc<AtomicUsize>);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl SerialState {
+    /// Queue `work` and return the receiver for its result
+    fn submit<T, F>(&self, work: F) -> Result<oneshot::Receiver<T>, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut SerialConnection) -> T + Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        let guard = PendingGuard(self.pending.clone());
+        let request: Request = Box::new(move |connection| {
+            let _pending = guard;
+            let _ = reply.send(work(connection));
+        });
+        self.requests.send(request).map_err(|_| WORKER_STOPPED.to_string())?;
+        Ok(result)
+    }
+
+    /// Run `work` on the serial worker and wait for its result without tying up a thread
+    pub async fn with<T, F>(&self, work: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut SerialConnection) -> T + Send + 'static,
+    {
+        self.submit(work)?.await.map_err(|_| WORKER_PANICKED.to_string())
+    }
+
+    /// `with` for jobs and other blocking threads; never call this on the async runtime
+    pub fn with_blocking<T, F>(&self, work: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut SerialConnection) -> T + Send + 'static,
+    {
+        self.submit(work)?.blocking_recv().map_err(|_| WORKER_PANICKED.to_string())
+    }
+
+    /// `with_blocking` for work that reports progress: the events `work` passes to its
+    /// callback reach `on_event` on the calling thread while the work runs
+    pub fn with_events<T, E, F, H>(&self, work: F, mut on_event: H) -> Result<T, String>
+    where
+        T: Send + 'static,
+        E: Send + 'static,
+        F: FnOnce(&mut SerialConnection, &mut dyn FnMut(E)) -> T + Send + 'static,
+        H: FnMut(E),
+    {
+        let (events, received) = mpsc::channel();
+        let result = self.submit(move |connection| {
+            work(connection, &mut |event| {
+                let _ = events.send(event);
+            })
+        })?;
+        // Ends once the work is done and its sender dropped
+        for event in received {
+            on_event(event);
+        }
+        result.blocking_recv().map_err(|_| WORKER_PANICKED.to_string())
+    }
+
+    /// Queue `work` without waiting for it; later requests see its effect
+    pub fn post<F>(&self, work: F)
+    where
+        F: FnOnce(&mut SerialConnection) + Send + 'static,
+    {
+        if let Err(e) = self.submit(work) {
+            eprintln!("[SERIAL] {}", e);
+        }
+    }
+
+    /// Nothing is queued for or running on the port
+    pub fn is_idle(&self) -> bool {
+        self.pending.load(Ordering::SeqCst) == 0
     }
 }
@@ -0,0 +1,46 @@
+use crate::concurrency::{ConcurrencyGate, Operation};
+use crate::reconnect;
+use crate::serial::SerialState;
+use crate::supervisor::{ConnectionSupervisor, TaskRole};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+// How often an idle port is checked for output the device printed on its own
+const POLL_INTERVAL_MS: u64 = 50;
+
+/// Keep reading the port between commands so unsolicited device output reaches the
+/// UI as `serial://rx` events as it happens. Stops with the connection; a port that
+/// fails to read is treated as lost (see `reconnect`).
+pub fn start(app: &AppHandle) {
+    let state = app.state::<SerialState>().inner().clone();
+    let gate = app.state::<ConcurrencyGate>().inner().clone();
+    let task_app = app.clone();
+    app.state::<ConnectionSupervisor>().start(TaskRole::Reader, move |mut token| async move {
+        while token.sleep(Duration::from_millis(POLL_INTERVAL_MS)).await {
+            // A command using the port reads its own reply; try again later
+            if !state.is_idle() {
+                continue;
+            }
+            let Ok(_reading) = gate.try_begin(Operation::TerminalRead) else {
+                continue;
+            };
+            let polled = state
+                .with(|connection| {
+                    if !connection.is_connected() {
+                        return Ok(false);
+                    }
+                    connection.poll_unsolicited().map(|_| true).map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(Err);
+            match polled {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    reconnect::connection_lost(&task_app, e).await;
+                    break;
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Oldest events are dropped beyond this many entries
+const MAX_EVENTS: usize = 5000;
+
+/// Milliseconds since the Unix epoch
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEventKind {
+    Connected,
+    Disconnected,
+    Upload,
+    /// Operator command sent to the device (run, stop, RPM, NVS)
+    Command,
+    /// Fault reported by the device
+    Fault,
+    /// Free-text marker placed by the operator
+    Marker,
+}
+
+/// One entry in the structured log of the current app session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEvent {
+    pub timestamp: u64,
+    pub kind: SessionEventKind,
+    pub message: String,
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct SessionLog(pub Arc<Mutex<Vec<SessionEvent>>>);
+
+impl SessionLog {
+    pub fn record(&self, kind: SessionEventKind, message: impl Into<String>, note: Option<String>) {
+        if let Ok(mut events) = self.0.lock() {
+            events.push(SessionEvent {
+                timestamp: now_millis(),
+                kind,
+                message: message.into(),
+                note,
+            });
+            if events.len() > MAX_EVENTS {
+                let excess = events.len() - MAX_EVENTS;
+                events.drain(..excess);
+            }
+        }
+    }
+
+    pub fn events(&self) -> Vec<SessionEvent> {
+        self.0.lock().map(|e| e.clone()).unwrap_or_default()
+    }
+
+    /// Events with `from <= timestamp <= to`, oldest first; open ends are unbounded
+    pub fn timeline(&self, from: Option<u64>, to: Option<u64>) -> Vec<SessionEvent> {
+        let mut events: Vec<SessionEvent> = self
+            .events()
+            .into_iter()
+            .filter(|e| from.is_none_or(|from| e.timestamp >= from))
+            .filter(|e| to.is_none_or(|to| e.timestamp <= to))
+            .collect();
+        // Recording order already is time order, barring clock adjustments
+        events.sort_by_key(|e| e.timestamp);
+        events
+    }
+}
@@ -0,0 +1,457 @@
+use crate::events::SETTINGS_CHANGED_EVENT;
+use crate::storage::{self, StorageLocation, DEFAULT_FALLBACK_ORDER};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Events that can trigger automation hooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    DeviceFault,
+    UploadFailed,
+    SignalStopped,
+    DeviceReset,
+    ConnectionLost,
+    /// A config upload was acknowledged
+    UploadCompleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSettings {
+    pub enabled: bool,
+    pub url: String,
+    pub events: Vec<HookEvent>,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        WebhookSettings {
+            enabled: false,
+            url: String::new(),
+            events: vec![
+                HookEvent::DeviceFault,
+                HookEvent::UploadFailed,
+                HookEvent::SignalStopped,
+                HookEvent::DeviceReset,
+                HookEvent::ConnectionLost,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageSettings {
+    /// Locations tried in order when picking where app data lives
+    pub fallback_order: Vec<StorageLocation>,
+    /// Folder the data was migrated to; tried before `fallback_order`
+    pub custom_dir: Option<String>,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        StorageSettings {
+            fallback_order: DEFAULT_FALLBACK_ORDER.to_vec(),
+            custom_dir: None,
+        }
+    }
+}
+
+/// How urgently an event needs the operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// Cues for one event, for operators who can't watch the screen or hear a toast
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttentionRule {
+    pub event: HookEvent,
+    pub severity: Severity,
+    pub sound: bool,
+    /// Flash the screen and the taskbar/dock entry
+    pub flash: bool,
+    /// OS notification
+    pub notify: bool,
+}
+
+impl AttentionRule {
+    fn new(event: HookEvent, severity: Severity, sound: bool, flash: bool, notify: bool) -> Self {
+        AttentionRule {
+            event,
+            severity,
+            sound,
+            flash,
+            notify,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttentionSettings {
+    pub enabled: bool,
+    /// Events without a rule raise no cue
+    pub rules: Vec<AttentionRule>,
+}
+
+impl Default for AttentionSettings {
+    fn default() -> Self {
+        AttentionSettings {
+            enabled: true,
+            rules: vec![
+                AttentionRule::new(HookEvent::UploadCompleted, Severity::Info, true, false, false),
+                AttentionRule::new(HookEvent::UploadFailed, Severity::Warning, true, false, true),
+                AttentionRule::new(HookEvent::DeviceReset, Severity::Warning, true, true, true),
+                AttentionRule::new(HookEvent::DeviceFault, Severity::Critical, true, true, true),
+                AttentionRule::new(HookEvent::SignalStopped, Severity::Critical, true, true, true),
+                AttentionRule::new(HookEvent::ConnectionLost, Severity::Critical, true, true, true),
+            ],
+        }
+    }
+}
+
+/// One-byte command characters, remappable for customized firmware builds
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CommandAliases {
+    pub run: char,
+    pub stop: char,
+    pub increase_rpm: char,
+    pub decrease_rpm: char,
+    pub save_nvs: char,
+    pub reset_defaults: char,
+}
+
+impl Default for CommandAliases {
+    fn default() -> Self {
+        CommandAliases {
+            run: 'r',
+            stop: 's',
+            increase_rpm: '+',
+            decrease_rpm: '-',
+            save_nvs: 'w',
+            reset_defaults: 'd',
+        }
+    }
+}
+
+// Characters the protocol already uses: the status query and framed requests
+const RESERVED_COMMAND_CHARS: [char; 3] = ['?', '<', '>'];
+
+impl CommandAliases {
+    /// Each command must be a distinct printable ASCII character not used by the protocol
+    pub fn validate(&self) -> Result<(), String> {
+        let commands = [
+            ("run", self.run),
+            ("stop", self.stop),
+            ("increase RPM", self.increase_rpm),
+            ("decrease RPM", self.decrease_rpm),
+            ("save to NVS", self.save_nvs),
+            ("reset defaults", self.reset_defaults),
+        ];
+        for (i, (name, c)) in commands.iter().enumerate() {
+            if !c.is_ascii_graphic() {
+                return Err(format!("Command character for {} must be a printable ASCII character", name));
+            }
+            if RESERVED_COMMAND_CHARS.contains(c) {
+                return Err(format!("'{}' is reserved by the protocol and can't be used for {}", c, name));
+            }
+            if let Some((other, _)) = commands[..i].iter().find(|(_, o)| o == c) {
+                return Err(format!("'{}' is assigned to both {} and {}", c, other, name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Owner tag written to devices so a shared lab can tell whose configuration is loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClaimSettings {
+    /// Claim unowned devices on connect and warn about devices claimed by others
+    pub enabled: bool,
+    /// Shown to other users; defaults to the OS user name when empty
+    pub owner_name: String,
+    /// Generated once per installation
+    pub owner_id: String,
+}
+
+impl Default for ClaimSettings {
+    fn default() -> Self {
+        ClaimSettings {
+            enabled: true,
+            owner_name: String::new(),
+            owner_id: String::new(),
+        }
+    }
+}
+
+/// Checks `run_signal` enforces before starting the signal, each one optional
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InterlockSettings {
+    /// The device answers the status query
+    pub handshake: bool,
+    /// A config was uploaded since connecting, or the device reports one loaded
+    pub signal_loaded: bool,
+    /// Refuse to run while the configured RPM is above this
+    pub rpm_ceiling: Option<u16>,
+    /// The operator must confirm every run
+    pub require_confirmation: bool,
+}
+
+impl Default for InterlockSettings {
+    fn default() -> Self {
+        InterlockSettings {
+            handshake: true,
+            signal_loaded: false,
+            rpm_ceiling: None,
+            require_confirmation: false,
+        }
+    }
+}
+
+/// Library hygiene rules a team applies on top of the firmware limits, checked when
+/// signals are imported or saved and by the library scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationRules {
+    /// Largest channel blob accepted, in characters of the encoded SIG1 string
+    pub max_blob_bytes: Option<usize>,
+    /// Optional channels every signal must have, e.g. `["CMP1"]` (CKP is always required)
+    pub required_channels: Vec<String>,
+    /// Name patterns refused, case-insensitive; `*` matches any run of characters and
+    /// `?` a single one (e.g. `"test*"`, `"*copy*"`)
+    pub forbidden_name_patterns: Vec<String>,
+}
+
+/// Limits for one kind of file the app writes on its own; `None` leaves a limit off
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Files last written longer ago than this are deleted
+    pub max_age_days: Option<u32>,
+    /// Oldest files are deleted while the category takes more than this
+    pub max_total_mb: Option<u64>,
+}
+
+/// Cleanup of generated files, run at startup and once a day. The newest file of each
+/// category is always kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionSettings {
+    pub enabled: bool,
+    /// Files downloaded from the device filesystem, mostly device logs
+    pub logs: RetentionPolicy,
+    /// Soak test reports
+    pub telemetry: RetentionPolicy,
+    /// Device configs read back before destructive operations
+    pub backups: RetentionPolicy,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        RetentionSettings {
+            enabled: true,
+            logs: RetentionPolicy {
+                max_age_days: Some(30),
+                max_total_mb: Some(200),
+            },
+            telemetry: RetentionPolicy {
+                max_age_days: Some(30),
+                max_total_mb: Some(100),
+            },
+            backups: RetentionPolicy {
+                max_age_days: Some(180),
+                max_total_mb: Some(50),
+            },
+        }
+    }
+}
+
+/// Reopening the port after the device went away (unplugged, or gone after a reset)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectSettings {
+    pub enabled: bool,
+    /// Tries before giving up
+    pub attempts: u32,
+    /// Wait before each try
+    pub interval_secs: u32,
+}
+
+impl Default for ReconnectSettings {
+    fn default() -> Self {
+        ReconnectSettings {
+            enabled: true,
+            attempts: 10,
+            interval_secs: 2,
+        }
+    }
+}
+
+/// What a config upload does when the device reports the signal running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunningUploadPolicy {
+    /// Send the config without asking, leaving it to the firmware
+    Ignore,
+    /// Refuse until the signal is stopped
+    Refuse,
+    /// Stop the signal, upload, then start it again
+    StopAndRestart,
+}
+
+/// User settings persisted in the app data folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub webhooks: WebhookSettings,
+    pub attention: AttentionSettings,
+    pub storage: StorageSettings,
+    /// Minutes between background library integrity scans (0 disables them)
+    pub library_scan_interval_mins: u32,
+    /// Leave Bluetooth and other ports that can't be the ESP32 out of port lists
+    pub hide_irrelevant_ports: bool,
+    /// Upper rate for high-frequency events sent to the UI (0 = unlimited)
+    pub max_events_per_sec: u32,
+    /// Overall deadline for connecting, from opening the port to the firmware answering (0 = no limit)
+    pub connect_timeout_secs: u32,
+    pub command_aliases: CommandAliases,
+    pub claim: ClaimSettings,
+    pub interlocks: InterlockSettings,
+    pub running_upload_policy: RunningUploadPolicy,
+    pub reconnect: ReconnectSettings,
+    pub validation_rules: ValidationRules,
+    pub retention: RetentionSettings,
+    /// Unlock the debug commands of the protocol profile, for firmware development
+    pub developer_mode: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            webhooks: WebhookSettings::default(),
+            attention: AttentionSettings::default(),
+            storage: StorageSettings::default(),
+            library_scan_interval_mins: 60,
+            hide_irrelevant_ports: true,
+            max_events_per_sec: 20,
+            connect_timeout_secs: 10,
+            command_aliases: CommandAliases::default(),
+            claim: ClaimSettings::default(),
+            interlocks: InterlockSettings::default(),
+            running_upload_policy: RunningUploadPolicy::Refuse,
+            reconnect: ReconnectSettings::default(),
+            validation_rules: ValidationRules::default(),
+            retention: RetentionSettings::default(),
+            developer_mode: false,
+        }
+    }
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join(SETTINGS_FILE))
+}
+
+/// Load settings, falling back to defaults when missing or unreadable
+pub fn load_settings(app: &AppHandle) -> AppSettings {
+    let Ok(path) = settings_path(app) else {
+        return AppSettings::default();
+    };
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(settings_path(app)?, json).map_err(|e| e.to_string())
+}
+
+/// Write settings into another data folder, e.g. the pointer left in the bootstrap location
+pub fn save_settings_in(dir: &Path, settings: &AppSettings) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    fs::write(dir.join(SETTINGS_FILE), json).map_err(|e| e.to_string())
+}
+
+/// Settings keys that changed in one update, tagged with the new revision
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsChange {
+    pub revision: u64,
+    pub changed: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Default)]
+struct Revisioned {
+    settings: AppSettings,
+    // Bumped on every change so windows can drop stale copies
+    revision: u64,
+}
+
+// Shared in-memory copy of the settings
+#[derive(Clone, Default)]
+pub struct SettingsState(Arc<Mutex<Revisioned>>);
+
+impl SettingsState {
+    pub fn new(settings: AppSettings) -> Self {
+        SettingsState(Arc::new(Mutex::new(Revisioned { settings, revision: 0 })))
+    }
+
+    pub fn get(&self) -> AppSettings {
+        self.0.lock().map(|s| s.settings.clone()).unwrap_or_default()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.0.lock().map(|s| s.revision).unwrap_or(0)
+    }
+
+    /// Swap in new settings; `None` when nothing actually changed
+    fn replace(&self, settings: AppSettings) -> Result<Option<SettingsChange>, String> {
+        let mut current = self.0.lock().map_err(|e| e.to_string())?;
+        let changed = changed_keys(&current.settings, &settings)?;
+        if changed.is_empty() {
+            return Ok(None);
+        }
+        current.settings = settings;
+        current.revision += 1;
+        Ok(Some(SettingsChange {
+            revision: current.revision,
+            changed,
+        }))
+    }
+}
+
+/// Top-level keys whose values differ, with their new values
+fn changed_keys(old: &AppSettings, new: &AppSettings) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let serde_json::Value::Object(old) = serde_json::to_value(old).map_err(|e| e.to_string())? else {
+        return Err("settings must serialize to an object".into());
+    };
+    let serde_json::Value::Object(new) = serde_json::to_value(new).map_err(|e| e.to_string())? else {
+        return Err("settings must serialize to an object".into());
+    };
+    Ok(new.into_iter().filter(|(key, value)| old.get(key) != Some(value)).collect())
+}
+
+/// Persist new settings, update the shared copy and tell every window which keys changed.
+/// Every writer (commands, tray, CLI) goes through here so no view is left stale.
+pub fn apply(app: &AppHandle, settings: AppSettings) -> Result<Option<SettingsChange>, String> {
+    save_settings(app, &settings)?;
+    let change = app.state::<SettingsState>().replace(settings)?;
+    if let Some(change) = &change {
+        let _ = app.emit(SETTINGS_CHANGED_EVENT, change);
+    }
+    Ok(change)
+}
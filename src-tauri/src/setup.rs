@@ -0,0 +1,148 @@
+use crate::port_cache;
+use crate::serial::{DeviceIdentity, PortInfo, SerialConnection, SerialError, SerialParams};
+use crate::session::now_millis;
+use crate::settings::{self, AppSettings};
+use crate::signals::{self, SignalConfig, SIGNALS_DIR};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Written once the first-run wizard (or `init`) has finished
+const SETUP_FILE: &str = "setup.json";
+const SETTINGS_FILE: &str = "settings.json";
+// Fixed seeds keep the preset blobs identical between installs
+const PRESET_SEED: u32 = 0x5eed_0000;
+
+/// Record of a finished first-run setup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupRecord {
+    pub completed_at: u64,
+    /// Port the handshake succeeded on, if one was tested
+    pub port: Option<String>,
+}
+
+/// Where the first-run setup stands, for deciding whether to show the wizard
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupState {
+    pub data_dir: String,
+    pub completed: Option<SetupRecord>,
+    pub settings_exist: bool,
+}
+
+/// Outcome of trying our firmware's handshake on one port
+#[derive(Debug, Clone, Serialize)]
+pub struct HandshakeResult {
+    pub port: String,
+    pub ok: bool,
+    pub identity: Option<DeviceIdentity>,
+    pub error: Option<String>,
+}
+
+pub fn state(dir: &Path) -> SetupState {
+    let completed = fs::read_to_string(dir.join(SETUP_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    SetupState {
+        data_dir: dir.to_string_lossy().to_string(),
+        completed,
+        settings_exist: dir.join(SETTINGS_FILE).exists(),
+    }
+}
+
+/// Ports that could be the ESP32, known USB bridges first
+pub fn candidate_ports() -> Result<Vec<PortInfo>, SerialError> {
+    let mut ports = SerialConnection::list_ports()?;
+    ports.retain(|p| !port_cache::is_irrelevant(p));
+    ports.sort_by_key(|p| !port_cache::is_esp32_bridge(p));
+    Ok(ports)
+}
+
+/// Open `port`, let the board boot and see whether our firmware answers; the port is
+/// closed again either way. Blocking.
+pub fn test_handshake(port: &str, params: &SerialParams) -> HandshakeResult {
+    let mut connection = SerialConnection::new();
+    let checked = connection
+        .connect(port, params)
+        .and_then(|_| connection.settle(None))
+        .and_then(|_| connection.identify(None));
+    let identity = checked.is_ok().then(|| connection.identity().clone());
+    let _ = connection.disconnect();
+    HandshakeResult {
+        port: port.to_string(),
+        ok: checked.is_ok(),
+        identity,
+        error: checked.err().map(|e| e.to_string()),
+    }
+}
+
+/// Write `settings` as the settings file unless one exists; returns whether it was created
+pub fn create_settings(dir: &Path, settings: &AppSettings) -> Result<bool, String> {
+    if dir.join(SETTINGS_FILE).exists() {
+        return Ok(false);
+    }
+    settings::save_settings_in(dir, settings)?;
+    Ok(true)
+}
+
+/// Add the bundled example signals to the library, leaving same-name entries alone.
+/// Returns the names of the signals added.
+pub fn install_presets(dir: &Path) -> Result<Vec<String>, String> {
+    let signals_dir = dir.join(SIGNALS_DIR);
+    let mut installed = Vec::new();
+    for preset in presets() {
+        let filename = format!("{}.json", signals::safe_filename(&preset.name));
+        if signals_dir.join(filename).exists() {
+            continue;
+        }
+        signals::save_signal_in(&signals_dir, &preset).map_err(|e| e.to_string())?;
+        installed.push(preset.name);
+    }
+    Ok(installed)
+}
+
+/// Note that setup finished so the wizard isn't shown again
+pub fn complete(dir: &Path, port: Option<String>) -> Result<SetupRecord, String> {
+    let record = SetupRecord {
+        completed_at: now_millis(),
+        port,
+    };
+    let json = serde_json::to_string_pretty(&record).map_err(|e| e.to_string())?;
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    fs::write(dir.join(SETUP_FILE), json).map_err(|e| e.to_string())?;
+    Ok(record)
+}
+
+/// Common trigger wheels, to have something to run before the first signal is drawn
+fn presets() -> Vec<SignalConfig> {
+    let wheels: [(&str, u16, &[u16], bool); 3] = [
+        ("60-2 with 1-tooth cam", 60, &[59, 60], true),
+        ("36-1 with 1-tooth cam", 36, &[36], true),
+        ("36-2-2-2", 36, &[9, 10, 18, 19, 27, 28], false),
+    ];
+    wheels
+        .iter()
+        .zip(PRESET_SEED..)
+        .map(|(&(name, teeth, missing, cam), seed)| SignalConfig {
+            name: format!("Preset {}", name),
+            ckp: signals::encode_sig1(&crank_edges(teeth, missing), seed),
+            // Cam high for the first crank revolution
+            cmp1: cam.then(|| signals::encode_sig1(&[(0, 1), (3600, 0)], seed)),
+            cmp2: None,
+        })
+        .collect()
+}
+
+/// Edges of a crank wheel over the 720° cycle (two revolutions), each present tooth
+/// high for the first half of its pitch
+fn crank_edges(teeth: u16, missing: &[u16]) -> Vec<(u16, u16)> {
+    let pitch = 3600 / teeth;
+    let mut edges = Vec::new();
+    for revolution in 0..2 {
+        for tooth in (1..=teeth).filter(|t| !missing.contains(t)) {
+            let start = revolution * 3600 + (tooth - 1) * pitch;
+            edges.push((start, 1));
+            edges.push((start + pitch / 2, 0));
+        }
+    }
+    edges
+}
@@ -0,0 +1,123 @@
+use crate::serial::DeviceIdentity;
+use crate::session::now_millis;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+// Kept beside the signals folder so library scans never mistake it for a signal
+const INDEX_FILE: &str = "signal_index.json";
+
+/// Last time a library signal was successfully flashed, and to what
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastUpload {
+    pub timestamp: u64,
+    pub port_name: Option<String>,
+    #[serde(default)]
+    pub usb_serial: Option<String>,
+    pub device_id: Option<String>,
+    pub firmware_version: Option<String>,
+}
+
+/// Per-signal metadata that doesn't belong in the signal file itself
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexEntry {
+    #[serde(default)]
+    pub last_upload: Option<LastUpload>,
+    /// Calibrated for one unit: uploading to any other board needs an override
+    #[serde(default)]
+    pub device_specific: bool,
+    /// USB serial of the unit a device-specific signal belongs to
+    #[serde(default)]
+    pub bound_usb_serial: Option<String>,
+}
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join(INDEX_FILE))
+}
+
+/// Load the index keyed by signal filename (empty if none was written yet)
+pub fn load(app: &AppHandle) -> Result<HashMap<String, IndexEntry>, String> {
+    load_in(&storage::data_dir(app)?)
+}
+
+/// Load the index of the app data folder `dir`, for callers without the app (the CLI)
+pub fn load_in(dir: &Path) -> Result<HashMap<String, IndexEntry>, String> {
+    let path = dir.join(INDEX_FILE);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn save(app: &AppHandle, index: &HashMap<String, IndexEntry>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
+    fs::write(index_path(app)?, json).map_err(|e| e.to_string())
+}
+
+/// Remember a successful upload of `filename` to the given device
+pub fn record_upload(app: &AppHandle, filename: &str, port_name: Option<String>, identity: &DeviceIdentity) -> Result<(), String> {
+    let mut index = load(app).unwrap_or_default();
+    let entry = index.entry(filename.to_string()).or_default();
+    entry.last_upload = Some(LastUpload {
+        timestamp: now_millis(),
+        port_name,
+        usb_serial: identity.usb_serial.clone(),
+        device_id: identity.device_id.clone(),
+        firmware_version: identity.firmware_version.clone(),
+    });
+    // A device-specific signal flagged before its first upload binds to that unit
+    if entry.device_specific && entry.bound_usb_serial.is_none() {
+        entry.bound_usb_serial = identity.usb_serial.clone();
+    }
+    save(app, &index)
+}
+
+/// Drop the entry of a signal removed from the library
+pub fn forget(app: &AppHandle, filename: &str) -> Result<(), String> {
+    let mut index = load(app)?;
+    if index.remove(filename).is_some() {
+        save(app, &index)?;
+    }
+    Ok(())
+}
+
+/// Mark a signal as calibrated for the unit it was last uploaded to (or not)
+pub fn set_device_specific(app: &AppHandle, filename: &str, device_specific: bool) -> Result<(), String> {
+    let mut index = load(app)?;
+    let entry = index.entry(filename.to_string()).or_default();
+    entry.device_specific = device_specific;
+    entry.bound_usb_serial = if device_specific {
+        entry.last_upload.as_ref().and_then(|u| u.usb_serial.clone())
+    } else {
+        None
+    };
+    save(app, &index)
+}
+
+/// Why uploading `filename` to the connected unit would mix calibrations, if it would
+pub fn binding_conflict(app: &AppHandle, filename: &str, identity: &DeviceIdentity) -> Result<Option<String>, String> {
+    let index = load(app)?;
+    let Some(bound) = index
+        .get(filename)
+        .filter(|e| e.device_specific)
+        .and_then(|e| e.bound_usb_serial.as_deref())
+    else {
+        return Ok(None);
+    };
+
+    Ok(match identity.usb_serial.as_deref() {
+        Some(current) if current == bound => None,
+        Some(current) => Some(format!(
+            "'{}' is calibrated for device {} but the connected device is {}",
+            filename, bound, current
+        )),
+        None => Some(format!(
+            "'{}' is calibrated for device {} but the connected device reports no USB serial number",
+            filename, bound
+        )),
+    })
+}
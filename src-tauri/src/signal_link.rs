@@ -0,0 +1,111 @@
+use crate::events::LINK_IMPORT_EVENT;
+use crate::signals::{self, ImportOutcome, SignalConfig};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::io::{Read, Write};
+use tauri::{AppHandle, Emitter};
+use thiserror::Error;
+
+const LINK_PREFIX: &str = "esp32sig://signal/";
+// Longer links get mangled or truncated by chat apps; share a file instead
+const MAX_LINK_LEN: usize = 8000;
+// Refuse links that inflate beyond this, whatever they claim to be
+const MAX_DECODED_BYTES: u64 = 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum LinkError {
+    #[error("Not a signal link or QR code (expected esp32sig://signal/... or E32Q:...)")]
+    NotASignalLink,
+    #[error("Signal is too large for a link ({0} characters); export a file instead")]
+    TooLarge(usize),
+    #[error("Link is damaged: {0}")]
+    Corrupt(String),
+    #[error("{0}")]
+    Signal(String),
+}
+
+/// Result of opening a link from outside the app, reported to the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkImport {
+    pub outcome: Option<ImportOutcome>,
+    pub error: Option<String>,
+}
+
+/// `esp32sig://signal/<payload>` for a stored signal
+pub fn export(app: &AppHandle, filename: &str) -> Result<String, LinkError> {
+    let link = format!("{}{}", LINK_PREFIX, encode_signal(app, filename)?);
+    if link.len() > MAX_LINK_LEN {
+        return Err(LinkError::TooLarge(link.len()));
+    }
+    Ok(link)
+}
+
+/// Compact text form of a stored signal: base64url(deflate(json))
+pub fn encode_signal(app: &AppHandle, filename: &str) -> Result<String, LinkError> {
+    let config = signals::load_signal(app, filename).map_err(|e| LinkError::Signal(e.to_string()))?;
+    let json = serde_json::to_vec(&config).map_err(|e| LinkError::Signal(e.to_string()))?;
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(&json)
+        .map_err(|e| LinkError::Signal(e.to_string()))?;
+    let compressed = encoder.finish().map_err(|e| LinkError::Signal(e.to_string()))?;
+    Ok(URL_SAFE_NO_PAD.encode(compressed))
+}
+
+/// Signal carried by a link
+pub fn decode(link: &str) -> Result<SignalConfig, LinkError> {
+    let payload = link
+        .trim()
+        .strip_prefix(LINK_PREFIX)
+        .ok_or(LinkError::NotASignalLink)?
+        .trim_end_matches('/');
+    decode_signal(payload)
+}
+
+/// Reverse of `encode_signal`
+pub fn decode_signal(payload: &str) -> Result<SignalConfig, LinkError> {
+    let compressed = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|e| LinkError::Corrupt(e.to_string()))?;
+
+    let mut json = Vec::new();
+    DeflateDecoder::new(compressed.as_slice())
+        .take(MAX_DECODED_BYTES + 1)
+        .read_to_end(&mut json)
+        .map_err(|e| LinkError::Corrupt(e.to_string()))?;
+    if json.len() as u64 > MAX_DECODED_BYTES {
+        return Err(LinkError::Corrupt("payload is implausibly large".into()));
+    }
+
+    serde_json::from_slice(&json).map_err(|e| LinkError::Corrupt(e.to_string()))
+}
+
+/// Import the signal carried by a link into the library
+pub fn import(app: &AppHandle, link: &str, allow_duplicate: bool) -> Result<ImportOutcome, LinkError> {
+    let config = decode(link)?;
+    signals::import_signal(app, &config, allow_duplicate).map_err(|e| LinkError::Signal(e.to_string()))
+}
+
+/// Import links the OS handed to the app and tell the UI how it went
+pub fn handle_opened(app: &AppHandle, urls: &[String]) {
+    for url in urls.iter().filter(|u| u.starts_with(LINK_PREFIX)) {
+        let result = match import(app, url, false) {
+            Ok(outcome) => LinkImport {
+                outcome: Some(outcome),
+                error: None,
+            },
+            Err(e) => {
+                eprintln!("[LIBRARY] Failed to import signal link: {}", e);
+                LinkImport {
+                    outcome: None,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+        let _ = app.emit(LINK_IMPORT_EVENT, result);
+    }
+}
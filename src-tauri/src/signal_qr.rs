@@ -0,0 +1,148 @@
+use crate::signal_link::{self, LinkError};
+use crate::signals::{self, ImportOutcome};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+
+const CHUNK_PREFIX: &str = "E32Q";
+// Payload characters per code; small enough for a tablet camera to read off a laptop screen
+const CHUNK_CHARS: usize = 600;
+const QR_MIN_SIZE_PX: u32 = 280;
+// Far more codes than any signal needs; guards against a bogus total
+const MAX_CHUNKS: usize = 64;
+
+/// One code of a multi-part QR export
+#[derive(Debug, Clone, Serialize)]
+pub struct QrChunk {
+    pub index: usize,
+    pub total: usize,
+    pub svg: String,
+}
+
+/// Where a QR import stands after a scanned code
+#[derive(Debug, Clone, Serialize)]
+pub struct QrImportProgress {
+    pub received: usize,
+    pub total: usize,
+    /// Set once the last missing code was scanned
+    pub outcome: Option<ImportOutcome>,
+}
+
+struct Assembly {
+    id: String,
+    parts: Vec<Option<String>>,
+}
+
+/// Codes scanned so far for the signal being imported
+#[derive(Clone, Default)]
+pub struct QrAssembly(Arc<Mutex<Option<Assembly>>>);
+
+/// Render a stored signal as QR codes, each tagged `E32Q:<id>:<index>:<total>:<data>`
+pub fn export(app: &AppHandle, filename: &str) -> Result<Vec<QrChunk>, LinkError> {
+    let payload = signal_link::encode_signal(app, filename)?;
+    let id = payload_id(&payload);
+    let parts: Vec<&str> = payload
+        .as_bytes()
+        .chunks(CHUNK_CHARS)
+        // base64url is ASCII, so byte chunks are valid strings
+        .map(|c| std::str::from_utf8(c).unwrap_or_default())
+        .collect();
+    let total = parts.len();
+
+    parts
+        .iter()
+        .enumerate()
+        .map(|(index, data)| {
+            let text = format!("{}:{}:{}:{}:{}", CHUNK_PREFIX, id, index + 1, total, data);
+            let code = QrCode::new(text.as_bytes()).map_err(|e| LinkError::Signal(e.to_string()))?;
+            let svg = code
+                .render::<svg::Color>()
+                .min_dimensions(QR_MIN_SIZE_PX, QR_MIN_SIZE_PX)
+                .build();
+            Ok(QrChunk {
+                index: index + 1,
+                total,
+                svg,
+            })
+        })
+        .collect()
+}
+
+/// Short tag that keeps codes of different signals from being mixed up
+fn payload_id(payload: &str) -> String {
+    let digest = Sha256::digest(payload.as_bytes());
+    digest[..4].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Split a scanned code into (id, index, total, data)
+fn parse_chunk(text: &str) -> Result<(String, usize, usize, String), LinkError> {
+    let mut fields = text.trim().splitn(5, ':');
+    if fields.next() != Some(CHUNK_PREFIX) {
+        return Err(LinkError::NotASignalLink);
+    }
+    let id = fields.next().ok_or(LinkError::NotASignalLink)?.to_string();
+    let index: usize = fields
+        .next()
+        .and_then(|f| f.parse().ok())
+        .ok_or(LinkError::NotASignalLink)?;
+    let total: usize = fields
+        .next()
+        .and_then(|f| f.parse().ok())
+        .ok_or(LinkError::NotASignalLink)?;
+    let data = fields.next().ok_or(LinkError::NotASignalLink)?.to_string();
+    if total == 0 || total > MAX_CHUNKS || index == 0 || index > total {
+        return Err(LinkError::Corrupt(format!("code {} of {} is out of range", index, total)));
+    }
+    Ok((id, index, total, data))
+}
+
+impl QrAssembly {
+    /// Take one scanned code; the signal is imported as soon as every code is in.
+    /// A code from a different signal restarts the assembly.
+    pub fn accept(&self, app: &AppHandle, text: &str) -> Result<QrImportProgress, LinkError> {
+        let (id, index, total, data) = parse_chunk(text)?;
+        let mut slot = self
+            .0
+            .lock()
+            .map_err(|e| LinkError::Signal(e.to_string()))?;
+
+        let restart = slot
+            .as_ref()
+            .is_none_or(|a| a.id != id || a.parts.len() != total);
+        if restart {
+            *slot = Some(Assembly {
+                id: id.clone(),
+                parts: vec![None; total],
+            });
+        }
+        let Some(assembly) = slot.as_mut() else {
+            return Err(LinkError::Signal("QR import state unavailable".into()));
+        };
+        assembly.parts[index - 1] = Some(data);
+
+        let received = assembly.parts.iter().filter(|p| p.is_some()).count();
+        if received < total {
+            return Ok(QrImportProgress {
+                received,
+                total,
+                outcome: None,
+            });
+        }
+
+        let payload: String = assembly.parts.iter().flatten().map(String::as_str).collect();
+        *slot = None;
+        if payload_id(&payload) != id {
+            return Err(LinkError::Corrupt("scanned codes don't add up to the original signal".into()));
+        }
+        let config = signal_link::decode_signal(&payload)?;
+        let outcome = signals::import_signal(app, &config, false).map_err(|e| LinkError::Signal(e.to_string()))?;
+        Ok(QrImportProgress {
+            received,
+            total,
+            outcome: Some(outcome),
+        })
+    }
+}
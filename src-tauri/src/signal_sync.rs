@@ -0,0 +1,102 @@
+use crate::device_fs::DeviceSignal;
+use crate::serial::config_crc32;
+use crate::signals::{self, SignalInfo};
+use serde::Serialize;
+use tauri::AppHandle;
+
+/// How a library signal was paired with a device slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchedBy {
+    /// Slot named after the signal or its library file
+    Name,
+    /// Same content stored under another name
+    Hash,
+}
+
+/// A library signal and the device slot it corresponds to
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncPair {
+    pub local: SignalInfo,
+    pub device: DeviceSignal,
+    pub matched_by: MatchedBy,
+    /// Whether the slot holds exactly the library config; `None` when the firmware
+    /// doesn't report checksums
+    pub same_content: Option<bool>,
+}
+
+/// Result of `reconcile_device_signals`
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncReport {
+    pub paired: Vec<SyncPair>,
+    /// Library signals with no slot on the device (candidates to push)
+    pub local_only: Vec<SignalInfo>,
+    /// Device slots with no library entry (candidates to pull)
+    pub device_only: Vec<DeviceSignal>,
+}
+
+/// Device slot a library signal is pushed to: slot names can't hold spaces, so this
+/// is the library file stem ("Ford 60-2" → "ford_60-2")
+pub fn slot_name(signal_name: &str) -> String {
+    signals::safe_filename(signal_name)
+}
+
+/// Library signals with the CRC32 of the config as it is sent to the device
+pub fn local_signals(app: &AppHandle) -> Result<Vec<(SignalInfo, u32)>, String> {
+    let mut local = Vec::new();
+    for info in signals::list_signals(app).map_err(|e| e.to_string())? {
+        match signals::load_signal(app, &info.filename) {
+            Ok(config) => {
+                let crc = config_crc32(&signals::format_for_esp32(&config));
+                local.push((info, crc));
+            }
+            Err(e) => eprintln!("[SYNC] Skipping {}: {}", info.filename, e),
+        }
+    }
+    Ok(local)
+}
+
+/// Pair library signals with device slots, by name first and then by content
+pub fn reconcile(local: Vec<(SignalInfo, u32)>, device: Vec<DeviceSignal>) -> SyncReport {
+    let mut local: Vec<Option<(SignalInfo, u32)>> = local.into_iter().map(Some).collect();
+    let mut paired = Vec::new();
+    let mut unmatched = Vec::new();
+
+    for slot in device {
+        let by_name = local.iter().position(|l| {
+            l.as_ref()
+                .is_some_and(|(info, _)| slot.name == info.name || slot.name == slot_name(&info.name))
+        });
+        match by_name.and_then(|i| local[i].take()) {
+            Some((info, crc)) => paired.push(SyncPair {
+                same_content: slot.crc32.map(|c| c == crc),
+                local: info,
+                device: slot,
+                matched_by: MatchedBy::Name,
+            }),
+            None => unmatched.push(slot),
+        }
+    }
+
+    let mut device_only = Vec::new();
+    for slot in unmatched {
+        let by_hash = slot
+            .crc32
+            .and_then(|crc| local.iter().position(|l| l.as_ref().is_some_and(|(_, c)| *c == crc)));
+        match by_hash.and_then(|i| local[i].take()) {
+            Some((info, _)) => paired.push(SyncPair {
+                local: info,
+                device: slot,
+                matched_by: MatchedBy::Hash,
+                same_content: Some(true),
+            }),
+            None => device_only.push(slot),
+        }
+    }
+
+    SyncReport {
+        paired,
+        local_only: local.into_iter().flatten().map(|(info, _)| info).collect(),
+        device_only,
+    }
+}
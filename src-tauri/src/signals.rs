@@ -1,6 +1,11 @@
+use crate::settings::{SettingsState, ValidationRules};
+use crate::signal_index::{self, IndexEntry, LastUpload};
+use crate::storage;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
 
 /// Signal configuration from Signal Generator
@@ -15,6 +20,15 @@ pub struct SignalConfig {
     pub cmp2: Option<String>,
 }
 
+/// Library folder inside the app data folder
+pub const SIGNALS_DIR: &str = "signals";
+/// Retired signals, inside the library folder; not listed, uploaded or scanned
+pub const ARCHIVE_DIR: &str = "archive";
+
+// Must match ESP32 firmware limits
+pub const MAX_CKP_EDGES: usize = 700;
+pub const MAX_CMP_EDGES: usize = 50;
+
 /// Signal info for listing (without full blob data)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignalInfo {
@@ -23,6 +37,20 @@ pub struct SignalInfo {
     pub has_ckp: bool,
     pub has_cmp1: bool,
     pub has_cmp2: bool,
+    /// Most recent successful upload, `None` if it was never flashed
+    pub last_upload: Option<LastUpload>,
+    pub device_specific: bool,
+    /// USB serial of the unit a device-specific signal is bound to
+    pub bound_usb_serial: Option<String>,
+}
+
+/// Result of importing a signal into the library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Imported { filename: String },
+    /// Channel blobs match an existing entry; nothing was written
+    Duplicate { of_filename: String, of_name: String },
 }
 
 #[derive(Debug)]
@@ -58,12 +86,9 @@ impl From<serde_json::Error> for SignalError {
 
 /// Get the signals directory path
 pub fn get_signals_dir(app: &AppHandle) -> Result<PathBuf, SignalError> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e: tauri::Error| SignalError::IoError(e.to_string()))?;
+    let app_data_dir = storage::data_dir(app).map_err(SignalError::IoError)?;
     
-    let signals_dir = app_data_dir.join("signals");
+    let signals_dir = app_data_dir.join(SIGNALS_DIR);
     
     // Create directory if it doesn't exist
     if !signals_dir.exists() {
@@ -98,19 +123,193 @@ pub fn validate_signal(config: &SignalConfig) -> Result<(), SignalError> {
     Ok(())
 }
 
+/// Rules from settings that signals entering this app's library must also meet
+pub fn validation_rules(app: &AppHandle) -> ValidationRules {
+    app.try_state::<SettingsState>()
+        .map(|s| s.get().validation_rules)
+        .unwrap_or_default()
+}
+
+/// Check a config against user-defined `rules`, after the built-in checks
+pub fn check_rules(config: &SignalConfig, rules: &ValidationRules) -> Result<(), SignalError> {
+    validate_signal(config)?;
+
+    let name = config.name.to_lowercase();
+    if let Some(pattern) = rules
+        .forbidden_name_patterns
+        .iter()
+        .find(|p| wildcard_match(&p.to_lowercase(), &name))
+    {
+        return Err(SignalError::ValidationError(format!(
+            "Signal name '{}' matches the forbidden pattern '{}'",
+            config.name, pattern
+        )));
+    }
+
+    let channels = [("CKP", Some(&config.ckp)), ("CMP1", config.cmp1.as_ref()), ("CMP2", config.cmp2.as_ref())];
+    for required in &rules.required_channels {
+        let present = channels
+            .iter()
+            .any(|(label, blob)| label.eq_ignore_ascii_case(required) && blob.is_some());
+        if !present {
+            return Err(SignalError::ValidationError(format!("{} channel is required", required.to_uppercase())));
+        }
+    }
+
+    if let Some(max) = rules.max_blob_bytes {
+        for (label, blob) in channels {
+            if let Some(blob) = blob.filter(|b| b.len() > max) {
+                return Err(SignalError::ValidationError(format!(
+                    "{} blob is {} bytes, over the {} byte limit",
+                    label,
+                    blob.len(),
+                    max
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// `*`/`?` wildcard match over the whole of `text`
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was, and the text position it currently stands for
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, from)) = backtrack {
+            // Let the `*` swallow one more character and retry
+            p = star + 1;
+            t = from + 1;
+            backtrack = Some((star, from + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xffff;
+    for byte in data {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0xa001;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    crc
+}
+
+fn derive_key(seed: u32) -> [u8; 16] {
+    let mut key = [0u8; 16];
+    let mut state = seed ^ 0xdeadbeef;
+    for k in key.iter_mut() {
+        state = state.wrapping_mul(1103515245).wrapping_add(12345);
+        *k = ((state >> 16) & 0xff) as u8;
+    }
+    key
+}
+
+/// Encode edges as a SIG1 blob, the inverse of `parse_sig1`. Each edge is an angle in
+/// tenths of a degree over the 720° cycle and the level from that angle on.
+pub fn encode_sig1(edges: &[(u16, u16)], seed: u32) -> String {
+    let mut buf = seed.to_le_bytes().to_vec();
+    buf.extend((edges.len() as u16).to_le_bytes());
+    for (angle_tenths, level) in edges {
+        buf.extend(angle_tenths.to_le_bytes());
+        buf.extend(level.to_le_bytes());
+    }
+    let crc = crc16(&buf[4..]);
+    buf.extend(crc.to_le_bytes());
+
+    let key = derive_key(seed);
+    for (i, byte) in buf.iter_mut().enumerate().skip(4) {
+        let key_idx = (i - 4) % 16;
+        let rotation = ((i - 4) & 0x0f) as u8;
+        *byte = byte.wrapping_add(rotation) ^ key[key_idx];
+    }
+    format!("SIG1{}", STANDARD.encode(buf))
+}
+
+/// Decode a SIG1 blob (same layout as the frontend codec and the firmware),
+/// verifying its CRC and returning the edge count
+pub fn parse_sig1(blob: &str) -> Result<usize, SignalError> {
+    let b64 = blob
+        .strip_prefix("SIG1")
+        .ok_or_else(|| SignalError::ValidationError("Blob must start with SIG1".into()))?;
+    let mut buf = STANDARD
+        .decode(b64)
+        .map_err(|_| SignalError::ValidationError("Invalid Base64 in SIG1 blob".into()))?;
+
+    if buf.len() < 8 {
+        return Err(SignalError::ValidationError("SIG1 payload too short".into()));
+    }
+
+    let seed = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
+    let key = derive_key(seed);
+
+    // De-obfuscate everything after the seed
+    for (i, byte) in buf.iter_mut().enumerate().skip(4) {
+        let key_idx = (i - 4) % 16;
+        let rotation = ((i - 4) & 0x0f) as u8;
+        *byte = (*byte ^ key[key_idx]).wrapping_sub(rotation);
+    }
+
+    let count = u16::from_le_bytes([buf[4], buf[5]]) as usize;
+    let len = buf.len();
+    let stored_crc = u16::from_le_bytes([buf[len - 2], buf[len - 1]]);
+    if stored_crc != crc16(&buf[4..len - 2]) {
+        return Err(SignalError::ValidationError("SIG1 CRC mismatch (corrupted blob)".into()));
+    }
+    if len != 6 + count * 4 + 2 {
+        return Err(SignalError::ValidationError(format!(
+            "SIG1 edge count {} does not match payload size",
+            count
+        )));
+    }
+
+    Ok(count)
+}
+
 /// Generate safe filename from signal name
-fn safe_filename(name: &str) -> String {
+pub(crate) fn safe_filename(name: &str) -> String {
     name.chars()
         .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
         .collect::<String>()
         .to_lowercase()
 }
 
-/// Save a signal configuration
+/// Library filename a signal with this name is stored under, if it is in the library
+pub fn library_filename(app: &AppHandle, name: &str) -> Option<String> {
+    let filename = format!("{}.json", safe_filename(name));
+    let path = get_signals_dir(app).ok()?.join(&filename);
+    path.exists().then_some(filename)
+}
+
+/// Save a signal configuration, subject to the validation rules in settings
 pub fn save_signal(app: &AppHandle, config: &SignalConfig) -> Result<String, SignalError> {
+    check_rules(config, &validation_rules(app))?;
+    save_signal_in(&get_signals_dir(app)?, config)
+}
+
+/// Save a signal configuration into a library folder, e.g. one the CLI was pointed at
+pub fn save_signal_in(signals_dir: &Path, config: &SignalConfig) -> Result<String, SignalError> {
     validate_signal(config)?;
     
-    let signals_dir = get_signals_dir(app)?;
+    fs::create_dir_all(signals_dir)?;
     let filename = format!("{}.json", safe_filename(&config.name));
     let filepath = signals_dir.join(&filename);
     
@@ -122,13 +321,23 @@ pub fn save_signal(app: &AppHandle, config: &SignalConfig) -> Result<String, Sig
 
 /// List all saved signals
 pub fn list_signals(app: &AppHandle) -> Result<Vec<SignalInfo>, SignalError> {
-    let signals_dir = get_signals_dir(app)?;
+    let index = signal_index::load(app).unwrap_or_default();
+    list_signals_in(&get_signals_dir(app)?, &index)
+}
+
+/// List the signals saved in the app data folder `dir`, for callers without the app (the CLI)
+pub fn list_signals_at(dir: &Path) -> Result<Vec<SignalInfo>, SignalError> {
+    let index = signal_index::load_in(dir).unwrap_or_default();
+    list_signals_in(&dir.join(SIGNALS_DIR), &index)
+}
+
+fn list_signals_in(signals_dir: &Path, index: &HashMap<String, IndexEntry>) -> Result<Vec<SignalInfo>, SignalError> {
     let mut signals = Vec::new();
     
-    if let Ok(entries) = fs::read_dir(&signals_dir) {
+    if let Ok(entries) = fs::read_dir(signals_dir) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.extension().map_or(false, |ext| ext == "json") {
+            if path.extension().is_some_and(|ext| ext == "json") {
                 if let Ok(content) = fs::read_to_string(&path) {
                     if let Ok(config) = serde_json::from_str::<SignalConfig>(&content) {
                         let filename = path.file_name()
@@ -136,12 +345,16 @@ pub fn list_signals(app: &AppHandle) -> Result<Vec<SignalInfo>, SignalError> {
                             .unwrap_or("")
                             .to_string();
                         
+                        let entry = index.get(&filename).cloned().unwrap_or_default();
                         signals.push(SignalInfo {
                             name: config.name,
                             filename,
                             has_ckp: !config.ckp.is_empty(),
                             has_cmp1: config.cmp1.is_some(),
                             has_cmp2: config.cmp2.is_some(),
+                            last_upload: entry.last_upload,
+                            device_specific: entry.device_specific,
+                            bound_usb_serial: entry.bound_usb_serial,
                         });
                     }
                 }
@@ -150,11 +363,47 @@ pub fn list_signals(app: &AppHandle) -> Result<Vec<SignalInfo>, SignalError> {
     }
     
     // Sort by name
-    signals.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    signals.sort_by_key(|s| s.name.to_lowercase());
     
     Ok(signals)
 }
 
+/// Find a stored signal whose channel blobs exactly match `config`, whatever its name
+pub fn find_duplicate(app: &AppHandle, config: &SignalConfig) -> Result<Option<SignalInfo>, SignalError> {
+    let signals_dir = get_signals_dir(app)?;
+
+    for info in list_signals(app)? {
+        let Ok(content) = fs::read_to_string(signals_dir.join(&info.filename)) else {
+            continue;
+        };
+        let Ok(existing) = serde_json::from_str::<SignalConfig>(&content) else {
+            continue;
+        };
+        if existing.ckp == config.ckp && existing.cmp1 == config.cmp1 && existing.cmp2 == config.cmp2 {
+            return Ok(Some(info));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Save a signal unless it duplicates an existing entry (or `allow_duplicate` is set)
+pub fn import_signal(app: &AppHandle, config: &SignalConfig, allow_duplicate: bool) -> Result<ImportOutcome, SignalError> {
+    check_rules(config, &validation_rules(app))?;
+
+    if !allow_duplicate {
+        if let Some(existing) = find_duplicate(app, config)? {
+            return Ok(ImportOutcome::Duplicate {
+                of_filename: existing.filename,
+                of_name: existing.name,
+            });
+        }
+    }
+
+    let filename = save_signal(app, config)?;
+    Ok(ImportOutcome::Imported { filename })
+}
+
 /// Load a signal by filename
 pub fn load_signal(app: &AppHandle, filename: &str) -> Result<SignalConfig, SignalError> {
     let signals_dir = get_signals_dir(app)?;
@@ -180,10 +429,76 @@ pub fn delete_signal(app: &AppHandle, filename: &str) -> Result<(), SignalError>
     }
     
     fs::remove_file(&filepath)?;
+    if let Err(e) = signal_index::forget(app, filename) {
+        eprintln!("[LIBRARY] Failed to update signal index: {}", e);
+    }
     
     Ok(())
 }
 
+fn archive_dir(app: &AppHandle) -> Result<PathBuf, SignalError> {
+    Ok(get_signals_dir(app)?.join(ARCHIVE_DIR))
+}
+
+/// Move a signal out of the library into the archive. Its upload history in the
+/// signal index is kept for when it is restored.
+pub fn archive_signal(app: &AppHandle, filename: &str) -> Result<(), SignalError> {
+    let from = get_signals_dir(app)?.join(filename);
+    if !from.exists() {
+        return Err(SignalError::NotFound(format!("Signal '{}' not found", filename)));
+    }
+    let archive = archive_dir(app)?;
+    let to = archive.join(filename);
+    if to.exists() {
+        return Err(SignalError::ValidationError(format!(
+            "An archived signal named '{}' already exists; restore or rename it first",
+            filename
+        )));
+    }
+    fs::create_dir_all(&archive)?;
+    fs::rename(&from, &to)?;
+    Ok(())
+}
+
+/// Archived signals whose name or filename contains `query` (case-insensitive); all of
+/// them without one
+pub fn list_archived(app: &AppHandle, query: Option<&str>) -> Result<Vec<SignalInfo>, SignalError> {
+    let query = query.map(str::to_lowercase).filter(|q| !q.is_empty());
+    let index = signal_index::load(app).unwrap_or_default();
+    let mut signals = list_signals_in(&archive_dir(app)?, &index)?;
+    if let Some(query) = query {
+        signals.retain(|s| s.name.to_lowercase().contains(&query) || s.filename.to_lowercase().contains(&query));
+    }
+    Ok(signals)
+}
+
+/// Read an archived signal, e.g. to see what an old test report refers to
+pub fn load_archived(app: &AppHandle, filename: &str) -> Result<SignalConfig, SignalError> {
+    let filepath = archive_dir(app)?.join(filename);
+    if !filepath.exists() {
+        return Err(SignalError::NotFound(format!("Archived signal '{}' not found", filename)));
+    }
+    let content = fs::read_to_string(&filepath)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Move an archived signal back into the library
+pub fn restore_signal(app: &AppHandle, filename: &str) -> Result<(), SignalError> {
+    let from = archive_dir(app)?.join(filename);
+    if !from.exists() {
+        return Err(SignalError::NotFound(format!("Archived signal '{}' not found", filename)));
+    }
+    let to = get_signals_dir(app)?.join(filename);
+    if to.exists() {
+        return Err(SignalError::ValidationError(format!(
+            "The library already has a signal stored as '{}'",
+            filename
+        )));
+    }
+    fs::rename(&from, &to)?;
+    Ok(())
+}
+
 /// Format signal config as JSON string for ESP32
 pub fn format_for_esp32(config: &SignalConfig) -> String {
     serde_json::to_string(config).unwrap_or_default()
@@ -0,0 +1,173 @@
+use crate::session::now_millis;
+use crate::signals::{self, SignalConfig};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use tauri::AppHandle;
+use thiserror::Error;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const MANIFEST_NAME: &str = "manifest.json";
+const SIGNALS_PREFIX: &str = "signals/";
+// Bumped whenever the bundle layout changes incompatibly
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum SigpackError {
+    #[error("IO Error: {0}")]
+    IoError(String),
+    #[error("Not a valid .sigpack archive: {0}")]
+    InvalidArchive(String),
+    #[error("Bundle has no manifest.json")]
+    MissingManifest,
+    #[error("Unsupported bundle schema version {0}, created by a newer app")]
+    UnsupportedSchema(u32),
+    #[error("Bundle is incomplete: '{0}' is listed in the manifest but missing")]
+    MissingEntry(String),
+    #[error("Checksum mismatch for '{0}': bundle was modified or corrupted")]
+    ChecksumMismatch(String),
+    #[error("Unexpected file '{0}' not listed in the manifest")]
+    UnexpectedEntry(String),
+    #[error("Invalid signal '{0}': {1}")]
+    InvalidSignal(String, String),
+}
+
+impl From<std::io::Error> for SigpackError {
+    fn from(err: std::io::Error) -> Self {
+        SigpackError::IoError(err.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for SigpackError {
+    fn from(err: zip::result::ZipError) -> Self {
+        SigpackError::InvalidArchive(err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub file: String,
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub schema_version: u32,
+    pub app_version: String,
+    pub created_at: u64,
+    pub signals: Vec<ManifestEntry>,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Write the given library signals to a `.sigpack` bundle
+pub fn export(app: &AppHandle, filenames: &[String], dest: &Path) -> Result<Manifest, SigpackError> {
+    let mut zip = ZipWriter::new(File::create(dest)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut entries = Vec::new();
+
+    for filename in filenames {
+        let config = signals::load_signal(app, filename)
+            .map_err(|e| SigpackError::InvalidSignal(filename.clone(), e.to_string()))?;
+        let data = serde_json::to_vec_pretty(&config)
+            .map_err(|e| SigpackError::InvalidSignal(filename.clone(), e.to_string()))?;
+
+        let file = format!("{}{}", SIGNALS_PREFIX, filename);
+        zip.start_file(file.as_str(), options)?;
+        zip.write_all(&data)?;
+
+        entries.push(ManifestEntry {
+            file,
+            name: config.name,
+            size: data.len() as u64,
+            sha256: sha256_hex(&data),
+        });
+    }
+
+    let manifest = Manifest {
+        schema_version: SCHEMA_VERSION,
+        app_version: app.package_info().version.to_string(),
+        created_at: now_millis(),
+        signals: entries,
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| SigpackError::IoError(e.to_string()))?;
+    zip.start_file(MANIFEST_NAME, options)?;
+    zip.write_all(&manifest_json)?;
+    zip.finish()?;
+
+    Ok(manifest)
+}
+
+/// Read and verify a bundle without touching the library
+pub fn read(path: &Path) -> Result<(Manifest, Vec<SignalConfig>), SigpackError> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+
+    let manifest: Manifest = {
+        let mut file = archive
+            .by_name(MANIFEST_NAME)
+            .map_err(|_| SigpackError::MissingManifest)?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        serde_json::from_slice(&content)
+            .map_err(|e| SigpackError::InvalidArchive(format!("bad manifest: {}", e)))?
+    };
+    if manifest.schema_version > SCHEMA_VERSION {
+        return Err(SigpackError::UnsupportedSchema(manifest.schema_version));
+    }
+
+    // Nothing may ride along outside the manifest
+    let listed: HashSet<&str> = manifest.signals.iter().map(|e| e.file.as_str()).collect();
+    for name in archive.file_names() {
+        if name != MANIFEST_NAME && !name.ends_with('/') && !listed.contains(name) {
+            return Err(SigpackError::UnexpectedEntry(name.to_string()));
+        }
+    }
+
+    let mut configs = Vec::with_capacity(manifest.signals.len());
+    for entry in &manifest.signals {
+        let mut data = Vec::new();
+        archive
+            .by_name(&entry.file)
+            .map_err(|_| SigpackError::MissingEntry(entry.file.clone()))?
+            .read_to_end(&mut data)?;
+
+        if data.len() as u64 != entry.size || sha256_hex(&data) != entry.sha256 {
+            return Err(SigpackError::ChecksumMismatch(entry.file.clone()));
+        }
+
+        let config: SignalConfig = serde_json::from_slice(&data)
+            .map_err(|e| SigpackError::InvalidSignal(entry.file.clone(), e.to_string()))?;
+        signals::validate_signal(&config)
+            .map_err(|e| SigpackError::InvalidSignal(entry.file.clone(), e.to_string()))?;
+        configs.push(config);
+    }
+
+    Ok((manifest, configs))
+}
+
+/// Verify a bundle completely, then save all of its signals to the library
+pub fn import(app: &AppHandle, path: &Path) -> Result<Vec<String>, SigpackError> {
+    let (_, configs) = read(path)?;
+    // All or nothing: a signal refused by the team's rules stops the import before any is saved
+    let rules = signals::validation_rules(app);
+    for config in &configs {
+        signals::check_rules(config, &rules)
+            .map_err(|e| SigpackError::InvalidSignal(config.name.clone(), e.to_string()))?;
+    }
+    configs
+        .iter()
+        .map(|config| {
+            signals::save_signal(app, config)
+                .map_err(|e| SigpackError::InvalidSignal(config.name.clone(), e.to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,82 @@
+use crate::critical::CriticalSection;
+use crate::integrity::{LibraryScanState, ScanSummary};
+use crate::jobs::{Job, JobManager};
+use crate::serial::{DeviceIdentity, SerialState};
+use crate::session::{SessionEvent, SessionLog};
+use crate::settings::{AppSettings, SettingsState};
+use crate::signals;
+use crate::soak::{SoakState, SoakSummary};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+// Session events included for the UI to rebuild its log view
+const RECENT_EVENTS: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionSnapshot {
+    pub connected: bool,
+    pub port_name: Option<String>,
+    pub identity: DeviceIdentity,
+    pub config_uploaded: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LibrarySnapshot {
+    pub signal_count: usize,
+    pub last_scan: Option<ScanSummary>,
+}
+
+/// Everything the UI needs to rebuild its view after a webview reload
+#[derive(Debug, Clone, Serialize)]
+pub struct AppSnapshot {
+    /// `None` while an upload or flash holds the port; `busy` says which
+    pub connection: Option<ConnectionSnapshot>,
+    /// Reason of the critical section in progress, if any
+    pub busy: Option<String>,
+    pub settings: AppSettings,
+    /// Revision of `settings`, to match against later `settings://changed` events
+    pub settings_revision: u64,
+    pub library: LibrarySnapshot,
+    pub soak: Option<SoakSummary>,
+    /// Uploads, flashes and transfers still running
+    pub jobs: Vec<Job>,
+    pub recent_events: Vec<SessionEvent>,
+}
+
+/// Gather the backend state without waiting on a port that is in use
+pub async fn collect(app: &AppHandle) -> AppSnapshot {
+    let serial = app.state::<SerialState>().inner().clone();
+    let connection = if serial.is_idle() {
+        serial
+            .with(|c| ConnectionSnapshot {
+                connected: c.is_connected(),
+                port_name: c.port_name().map(String::from),
+                identity: c.identity().clone(),
+                config_uploaded: c.config_uploaded(),
+            })
+            .await
+            .ok()
+    } else {
+        None
+    };
+
+    let library = LibrarySnapshot {
+        signal_count: signals::list_signals(app).map(|s| s.len()).unwrap_or(0),
+        last_scan: app.state::<LibraryScanState>().last(),
+    };
+
+    let events = app.state::<SessionLog>().events();
+    let recent_events = events[events.len().saturating_sub(RECENT_EVENTS)..].to_vec();
+
+    let settings = app.state::<SettingsState>();
+    AppSnapshot {
+        connection,
+        busy: app.state::<CriticalSection>().active(),
+        settings: settings.get(),
+        settings_revision: settings.revision(),
+        library,
+        soak: app.state::<SoakState>().summary(),
+        jobs: app.state::<JobManager>().active(),
+        recent_events,
+    }
+}
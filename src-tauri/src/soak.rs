@@ -0,0 +1,316 @@
+use crate::concurrency::{ConcurrencyGate, Operation};
+use crate::device_command::{DeviceCommand, OutcomeStatus};
+use crate::events::{SOAK_ALERT_EVENT, SOAK_SNAPSHOT_EVENT};
+use crate::hooks::HookState;
+use crate::scheduler;
+use crate::serial::{shows_reset_banner, SerialState};
+use crate::status_history::StatusHistory;
+use crate::session::now_millis;
+use crate::storage;
+use crate::supervisor::{ConnectionSupervisor, TaskRole};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const REPORT_FILE: &str = "soak_report.jsonl";
+pub const REPORT_BACKUP_FILE: &str = "soak_report.1.jsonl";
+// The report is rotated once it grows past this size
+const REPORT_MAX_BYTES: u64 = 2 * 1024 * 1024;
+// RPM deviation from the first running snapshot that counts as drift
+const RPM_DRIFT_TOLERANCE: f64 = 0.05;
+// Alerts kept in memory for get_soak_status; the report keeps everything
+const MAX_ALERTS: usize = 200;
+
+/// Device status and link health at one point of a soak run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakSnapshot {
+    pub timestamp: u64,
+    pub connected: bool,
+    pub running: bool,
+    pub rpm: u16,
+    pub fault: Option<String>,
+    /// Round trip of the status request
+    pub latency_ms: u64,
+    /// Status request failure, if the link misbehaved
+    pub error: Option<String>,
+    pub uptime_ms: Option<u64>,
+    pub reset_count: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoakAlertKind {
+    RpmDrift,
+    Reset,
+    Stopped,
+    Fault,
+    LinkError,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakAlert {
+    pub timestamp: u64,
+    pub kind: SoakAlertKind,
+    pub message: String,
+}
+
+/// Progress of the current (or last) soak run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakSummary {
+    pub active: bool,
+    pub started_at: u64,
+    pub interval_secs: u64,
+    pub snapshots: u64,
+    pub baseline_rpm: Option<u16>,
+    pub last: Option<SoakSnapshot>,
+    pub alerts: VecDeque<SoakAlert>,
+}
+
+/// One line of the report file
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ReportLine<'a> {
+    Snapshot(&'a SoakSnapshot),
+    Alert(&'a SoakAlert),
+}
+
+#[derive(Clone, Default)]
+pub struct SoakState {
+    summary: Arc<Mutex<Option<SoakSummary>>>,
+}
+
+impl SoakState {
+    pub fn summary(&self) -> Option<SoakSummary> {
+        self.summary.lock().ok().and_then(|s| s.clone())
+    }
+
+    fn update<F: FnOnce(&mut SoakSummary)>(&self, f: F) {
+        if let Ok(mut summary) = self.summary.lock() {
+            if let Some(summary) = summary.as_mut() {
+                f(summary);
+            }
+        }
+    }
+}
+
+fn report_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join(REPORT_FILE))
+}
+
+/// Append a line to the report, rotating it to a single backup when it gets large
+fn write_report(app: &AppHandle, line: ReportLine) -> Result<(), String> {
+    let path = report_path(app)?;
+    if fs::metadata(&path).is_ok_and(|m| m.len() > REPORT_MAX_BYTES) {
+        let backup = storage::data_dir(app)?.join(REPORT_BACKUP_FILE);
+        fs::rename(&path, backup).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string(&line).map_err(|e| e.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{}", json).map_err(|e| e.to_string())
+}
+
+fn alert(app: &AppHandle, soak: &SoakState, kind: SoakAlertKind, message: String) {
+    let alert = SoakAlert {
+        timestamp: now_millis(),
+        kind,
+        message,
+    };
+    eprintln!("[SOAK] {:?}: {}", alert.kind, alert.message);
+    if let Err(e) = write_report(app, ReportLine::Alert(&alert)) {
+        eprintln!("[SOAK] Failed to write report: {}", e);
+    }
+    let _ = app.emit(SOAK_ALERT_EVENT, &alert);
+    soak.update(|s| {
+        s.alerts.push_back(alert);
+        if s.alerts.len() > MAX_ALERTS {
+            s.alerts.pop_front();
+        }
+    });
+}
+
+/// Take one snapshot; a stopped signal is restarted so the run keeps going
+fn snapshot(app: &AppHandle, soak: &SoakState) {
+    let Ok(_snapshot) = app.state::<ConcurrencyGate>().try_begin(Operation::SoakSnapshot) else {
+        return;
+    };
+    let state = app.state::<SerialState>();
+    let start = Instant::now();
+    let status = state
+        .with_blocking(|connection| connection.get_status().map_err(|e| e.to_string()))
+        .unwrap_or_else(Err);
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let previous_resets = soak
+        .summary()
+        .and_then(|s| s.last)
+        .map_or(0, |last| last.reset_count);
+    let snapshot = match &status {
+        Ok(status) => SoakSnapshot {
+            timestamp: now_millis(),
+            connected: status.connected,
+            running: status.running,
+            rpm: status.rpm,
+            fault: status.fault.clone(),
+            latency_ms,
+            error: None,
+            uptime_ms: status.uptime_ms,
+            reset_count: status.reset_count,
+        },
+        Err(e) => SoakSnapshot {
+            timestamp: now_millis(),
+            connected: false,
+            running: false,
+            rpm: 0,
+            fault: None,
+            latency_ms,
+            error: Some(e.clone()),
+            uptime_ms: None,
+            reset_count: previous_resets,
+        },
+    };
+
+    if let Err(e) = write_report(app, ReportLine::Snapshot(&snapshot)) {
+        eprintln!("[SOAK] Failed to write report: {}", e);
+    }
+    let _ = app.emit(SOAK_SNAPSHOT_EVENT, &snapshot);
+
+    let previous = soak.summary();
+    soak.update(|s| {
+        s.snapshots += 1;
+        s.last = Some(snapshot.clone());
+        if s.baseline_rpm.is_none() && snapshot.running && snapshot.rpm > 0 {
+            s.baseline_rpm = Some(snapshot.rpm);
+        }
+    });
+
+    let status = match status {
+        Ok(status) if status.connected => status,
+        Ok(_) => {
+            alert(app, soak, SoakAlertKind::LinkError, "Device disconnected".into());
+            return;
+        }
+        Err(e) => {
+            alert(app, soak, SoakAlertKind::LinkError, format!("Status request failed: {}", e));
+            return;
+        }
+    };
+
+    app.state::<StatusHistory>().record(&status);
+    if status.reset_detected {
+        alert(
+            app,
+            soak,
+            SoakAlertKind::Reset,
+            format!("Device uptime went backwards (reset #{})", status.reset_count),
+        );
+    } else if shows_reset_banner(&status.raw_response) {
+        alert(app, soak, SoakAlertKind::Reset, "Device printed a reset banner".into());
+    }
+    if let Some(fault) = &status.fault {
+        let repeated = previous
+            .as_ref()
+            .and_then(|p| p.last.as_ref())
+            .is_some_and(|last| last.fault.as_ref() == Some(fault));
+        if !repeated {
+            alert(app, soak, SoakAlertKind::Fault, format!("Device fault: {}", fault));
+        }
+    }
+    if let Some(baseline) = previous.and_then(|p| p.baseline_rpm) {
+        let drift = (status.rpm as f64 - baseline as f64).abs() / baseline as f64;
+        if status.running && drift > RPM_DRIFT_TOLERANCE {
+            alert(
+                app,
+                soak,
+                SoakAlertKind::RpmDrift,
+                format!("RPM {} drifted {:.1}% from {}", status.rpm, drift * 100.0, baseline),
+            );
+        }
+    }
+
+    if !status.running {
+        alert(app, soak, SoakAlertKind::Stopped, "Signal stopped, restarting it".into());
+        let restarted = state
+            .with_blocking(|connection| connection.send_command(&DeviceCommand::Run).map_err(|e| e.to_string()))
+            .unwrap_or_else(Err);
+        match restarted {
+            Ok(outcome) if !outcome.is_accepted() => eprintln!("[SOAK] {}", outcome.describe()),
+            Ok(_) => {}
+            Err(e) => eprintln!("[SOAK] Failed to restart signal: {}", e),
+        }
+    }
+}
+
+/// Start the signal and snapshot its health every `interval` until stopped or disconnected
+pub async fn start(app: &AppHandle, interval: Duration) -> Result<(), String> {
+    let soak = app.state::<SoakState>().inner().clone();
+    let supervisor = app.state::<ConnectionSupervisor>().inner().clone();
+    if supervisor.get(TaskRole::Soak).is_some() {
+        return Err("A soak run is already in progress".into());
+    }
+
+    let outcome = app
+        .state::<SerialState>()
+        .with(|connection| connection.send_command(&DeviceCommand::Run).map_err(|e| e.to_string()))
+        .await??;
+    if outcome.status == OutcomeStatus::Rejected {
+        return Err(outcome.describe());
+    }
+    app.state::<HookState>().set_expected_running(true);
+
+    let started_at = now_millis();
+    if let Ok(mut summary) = soak.summary.lock() {
+        *summary = Some(SoakSummary {
+            active: true,
+            started_at,
+            interval_secs: interval.as_secs(),
+            snapshots: 0,
+            baseline_rpm: None,
+            last: None,
+            alerts: VecDeque::new(),
+        });
+    }
+
+    let app = app.clone();
+    let run_state = soak.clone();
+    supervisor.start(TaskRole::Soak, move |mut token| async move {
+        eprintln!("[SOAK] Started, snapshot every {}s", interval.as_secs());
+        loop {
+            let (app, soak) = (app.clone(), run_state.clone());
+            if scheduler::blocking(move || snapshot(&app, &soak)).await.is_none() {
+                break;
+            }
+            if !token.sleep(interval).await {
+                break;
+            }
+        }
+        run_state.update(|s| {
+            // A new run may already have replaced this one's summary
+            if s.started_at == started_at {
+                s.active = false;
+            }
+        });
+        eprintln!("[SOAK] Stopped");
+    });
+
+    Ok(())
+}
+
+/// Stop the soak run; the signal itself keeps running
+pub fn stop(app: &AppHandle) -> Result<(), String> {
+    match app.state::<ConnectionSupervisor>().get(TaskRole::Soak) {
+        Some(task) => {
+            task.cancel();
+            Ok(())
+        }
+        None => Err("No soak run in progress".into()),
+    }
+}
@@ -0,0 +1,165 @@
+use crate::scheduler::{self, Scheduler, Scope};
+use crate::serial::{DeviceIdentity, SerialConnection, SerialParams};
+use crate::session;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+// How often standby ports are asked whether the device still answers
+const HEARTBEAT_SECS: u64 = 5;
+
+/// A second bench box kept connected, so switching to it needs no reconnect. The
+/// connection has its own lock so a heartbeat on it doesn't hold up the pool; the port
+/// name and identity are copied out for listing without waiting on that lock.
+struct StandbyDevice {
+    id: String,
+    identity: DeviceIdentity,
+    connection: Arc<Mutex<SerialConnection>>,
+    healthy: bool,
+    last_heartbeat: Option<u64>,
+}
+
+/// A connected device, active or on standby, as listed for the UI.
+/// `id` is the port name, which is unique among open ports.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceSlot {
+    pub id: String,
+    pub identity: DeviceIdentity,
+    pub active: bool,
+    /// Standby only: the last heartbeat got an answer
+    pub healthy: bool,
+    /// Standby only: epoch ms of the last heartbeat
+    pub last_heartbeat: Option<u64>,
+}
+
+/// Connections held open besides the active one; nothing but the heartbeat talks to them
+#[derive(Clone, Default)]
+pub struct StandbyPool(Arc<Mutex<Vec<StandbyDevice>>>);
+
+impl StandbyPool {
+    pub fn slots(&self) -> Vec<DeviceSlot> {
+        let Ok(devices) = self.0.lock() else {
+            return Vec::new();
+        };
+        devices
+            .iter()
+            .map(|d| DeviceSlot {
+                id: d.id.clone(),
+                identity: d.identity.clone(),
+                active: false,
+                healthy: d.healthy,
+                last_heartbeat: d.last_heartbeat,
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.0
+            .lock()
+            .is_ok_and(|devices| devices.iter().any(|d| d.id == id))
+    }
+
+    /// Remove a standby connection to make it active. Waits for a heartbeat still
+    /// talking to that one device, not for the whole round.
+    pub fn take(&self, id: &str) -> Option<SerialConnection> {
+        let device = {
+            let mut devices = self.0.lock().ok()?;
+            let index = devices.iter().position(|d| d.id == id)?;
+            devices.remove(index)
+        };
+        let mut connection = device.connection.lock().ok()?;
+        Some(std::mem::replace(&mut *connection, SerialConnection::new()))
+    }
+
+    /// Park an open connection, e.g. the one that was active before a switch
+    pub fn put(&self, connection: SerialConnection) {
+        let id = connection.port_name().unwrap_or_default().to_string();
+        let identity = connection.identity().clone();
+        if let Ok(mut devices) = self.0.lock() {
+            devices.push(StandbyDevice {
+                id,
+                identity,
+                connection: Arc::new(Mutex::new(connection)),
+                healthy: true,
+                last_heartbeat: None,
+            });
+        }
+    }
+
+    /// Open `port` straight into standby, checking our firmware answers on it.
+    /// Blocking; call it on the blocking pool.
+    pub fn open(&self, port: &str, params: &SerialParams) -> Result<DeviceSlot, String> {
+        if self.contains(port) {
+            return Err(format!("{} is already on standby", port));
+        }
+        let mut connection = SerialConnection::new();
+        connection.connect(port, params).map_err(|e| e.to_string())?;
+        if let Err(e) = connection.settle(None).and_then(|_| connection.identify(None)) {
+            let _ = connection.disconnect();
+            return Err(e.to_string());
+        }
+        let slot = DeviceSlot {
+            id: port.to_string(),
+            identity: connection.identity().clone(),
+            active: false,
+            healthy: true,
+            last_heartbeat: Some(session::now_millis()),
+        };
+        self.put(connection);
+        Ok(slot)
+    }
+
+    /// Close a standby connection
+    pub fn close(&self, id: &str) -> Result<(), String> {
+        let mut connection = self
+            .take(id)
+            .ok_or_else(|| format!("{} is not on standby", id))?;
+        connection.disconnect().map_err(|e| e.to_string())
+    }
+
+    /// Ask every standby device to identify itself, one at a time and without holding
+    /// the pool meanwhile. Blocking.
+    fn heartbeat(&self) {
+        let connections: Vec<(String, Arc<Mutex<SerialConnection>>)> = match self.0.lock() {
+            Ok(devices) => devices.iter().map(|d| (d.id.clone(), d.connection.clone())).collect(),
+            Err(_) => return,
+        };
+        for (id, connection) in connections {
+            let Ok(mut connection) = connection.lock() else {
+                continue;
+            };
+            // Taken out of the pool while waiting for the lock
+            if !connection.is_connected() {
+                continue;
+            }
+            let healthy = connection.identify(None).is_ok();
+            drop(connection);
+
+            let Ok(mut devices) = self.0.lock() else {
+                return;
+            };
+            let Some(device) = devices.iter_mut().find(|d| d.id == id) else {
+                continue;
+            };
+            if healthy != device.healthy {
+                eprintln!("[STANDBY] {} {}", id, if healthy { "answers again" } else { "stopped answering" });
+            }
+            device.healthy = healthy;
+            device.last_heartbeat = Some(session::now_millis());
+        }
+    }
+}
+
+/// Keep standby connections checked in the background, so a switch doesn't land on a
+/// board that went away unnoticed
+pub fn start_heartbeat(app: &AppHandle) {
+    let pool = app.state::<StandbyPool>().inner().clone();
+    let tasks = app.state::<Scheduler>().inner().clone();
+    tasks.spawn("standby heartbeat", Scope::App, move |mut token| async move {
+        while token.sleep(Duration::from_secs(HEARTBEAT_SECS)).await {
+            let pool = pool.clone();
+            scheduler::blocking(move || pool.heartbeat()).await;
+        }
+    });
+}
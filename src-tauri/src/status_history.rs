@@ -0,0 +1,125 @@
+use crate::serial::{shows_reset_banner, DeviceStatus};
+use crate::session::now_millis;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+// About a day of samples at the UI's 1s polling rate
+const MAX_SAMPLES: usize = 86_400;
+
+/// One status response, reduced to what stability analysis needs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusSample {
+    pub timestamp: u64,
+    pub running: bool,
+    pub rpm: u16,
+    /// The response carried the ESP32 reset banner
+    pub reset: bool,
+}
+
+impl StatusSample {
+    pub fn from_status(status: &DeviceStatus) -> Self {
+        StatusSample {
+            timestamp: now_millis(),
+            running: status.running,
+            rpm: status.rpm,
+            reset: shows_reset_banner(&status.raw_response),
+        }
+    }
+}
+
+/// Rolling RPM statistics over running samples in a time window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpmStats {
+    pub window_secs: u64,
+    /// Samples taken while the signal was running
+    pub samples: usize,
+    pub mean: Option<f64>,
+    pub stddev: Option<f64>,
+    pub min: Option<u16>,
+    pub max: Option<u16>,
+    /// Device resets seen in the window, running or not
+    pub reset_count: usize,
+}
+
+impl RpmStats {
+    /// Statistics over `samples`, which are taken to span `window_secs`
+    pub fn from_samples(window_secs: u64, samples: &[StatusSample]) -> Self {
+        let rpms: Vec<u16> = samples.iter().filter(|s| s.running).map(|s| s.rpm).collect();
+
+        let count = rpms.len();
+        let mean = (count > 0).then(|| rpms.iter().map(|&r| r as f64).sum::<f64>() / count as f64);
+        let stddev = mean.map(|mean| {
+            let variance = rpms.iter().map(|&r| (r as f64 - mean).powi(2)).sum::<f64>() / count as f64;
+            variance.sqrt()
+        });
+
+        RpmStats {
+            window_secs,
+            samples: count,
+            mean,
+            stddev,
+            min: rpms.iter().copied().min(),
+            max: rpms.iter().copied().max(),
+            reset_count: samples.iter().filter(|s| s.reset).count(),
+        }
+    }
+}
+
+/// File format for exported status history
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Status responses of the current app session, oldest first
+#[derive(Clone, Default)]
+pub struct StatusHistory(pub Arc<Mutex<VecDeque<StatusSample>>>);
+
+impl StatusHistory {
+    pub fn record(&self, status: &DeviceStatus) {
+        if !status.connected {
+            return;
+        }
+        if let Ok(mut samples) = self.0.lock() {
+            samples.push_back(StatusSample::from_status(status));
+            if samples.len() > MAX_SAMPLES {
+                samples.pop_front();
+            }
+        }
+    }
+
+    /// Samples from the last `window_secs` seconds
+    pub fn window(&self, window_secs: u64) -> Vec<StatusSample> {
+        let since = now_millis().saturating_sub(window_secs.saturating_mul(1000));
+        self.0
+            .lock()
+            .map(|samples| samples.iter().filter(|s| s.timestamp >= since).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn rpm_stats(&self, window_secs: u64) -> RpmStats {
+        RpmStats::from_samples(window_secs, &self.window(window_secs))
+    }
+
+    /// Write the samples of the last `window_secs` seconds to `dest`; returns how many
+    pub fn export(&self, window_secs: u64, dest: &Path, format: ExportFormat) -> Result<usize, String> {
+        let samples = self.window(window_secs);
+        let content = match format {
+            ExportFormat::Json => serde_json::to_string_pretty(&samples).map_err(|e| e.to_string())?,
+            ExportFormat::Csv => {
+                let mut csv = String::from("timestamp,running,rpm,reset\n");
+                for s in &samples {
+                    csv.push_str(&format!("{},{},{},{}\n", s.timestamp, s.running, s.rpm, s.reset));
+                }
+                csv
+            }
+        };
+        fs::write(dest, content).map_err(|e| e.to_string())?;
+        Ok(samples.len())
+    }
+}
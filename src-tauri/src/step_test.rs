@@ -0,0 +1,135 @@
+use crate::device_command::{DeviceCommand, OutcomeStatus};
+use crate::jobs::JobContext;
+use crate::serial::{DeviceStatus, SerialState};
+use crate::session::{now_millis, SessionEventKind, SessionLog};
+use crate::status_history::{RpmStats, StatusHistory, StatusSample};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+// RPM commands move in fixed increments; give up on a setpoint after this many
+const MAX_NUDGES_PER_STEP: u32 = 200;
+// Status sampling period while dwelling at a setpoint
+const SAMPLE_INTERVAL_MS: u64 = 500;
+
+/// Telemetry of one setpoint of a step test
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    pub target_rpm: u16,
+    /// RPM the device settled at; it can only approach the target in its own increments
+    pub settled_rpm: u16,
+    pub reached: bool,
+    /// Time spent nudging the RPM to the setpoint
+    pub settle_ms: u64,
+    /// RPM statistics over the dwell
+    pub stats: RpmStats,
+}
+
+/// Outcome of a whole step test, returned as the job result
+#[derive(Debug, Clone, Serialize)]
+pub struct StepTestSummary {
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub dwell_secs: u64,
+    pub steps: Vec<StepResult>,
+    /// Every setpoint was reached and ran without a device reset
+    pub passed: bool,
+}
+
+/// Start the signal, then walk through `rpm_list`, holding each setpoint for `dwell`
+/// while sampling the status
+pub fn run(
+    job: &mut JobContext,
+    state: &SerialState,
+    history: &StatusHistory,
+    session: &SessionLog,
+    rpm_list: &[u16],
+    dwell: Duration,
+) -> Result<StepTestSummary, String> {
+    let started_at = now_millis();
+    send(state, &DeviceCommand::Run)?;
+
+    let total = rpm_list.len() as u64;
+    let mut steps = Vec::with_capacity(rpm_list.len());
+    for (i, &target) in rpm_list.iter().enumerate() {
+        job.progress(i as u64, total, Some(format!("Step {}/{}: {} RPM", i + 1, total, target)));
+        let step = run_step(job, state, history, target, dwell)?;
+        session.record(
+            SessionEventKind::Command,
+            format!("Step test {} RPM: settled at {}", target, step.settled_rpm),
+            (!step.reached).then(|| "Setpoint not reached".to_string()),
+        );
+        steps.push(step);
+    }
+    job.progress(total, total, Some("Done".into()));
+
+    let passed = steps.iter().all(|s| s.reached && s.stats.reset_count == 0);
+    Ok(StepTestSummary {
+        started_at,
+        finished_at: now_millis(),
+        dwell_secs: dwell.as_secs(),
+        steps,
+        passed,
+    })
+}
+
+fn run_step(job: &JobContext, state: &SerialState, history: &StatusHistory, target: u16, dwell: Duration) -> Result<StepResult, String> {
+    let settle_start = Instant::now();
+    let mut status = read_status(state, history)?;
+    let mut last_nudge = None;
+    let mut nudges = 0;
+    let reached = loop {
+        job.checkpoint()?;
+        if status.rpm == target {
+            break true;
+        }
+        let nudge = if status.rpm < target { DeviceCommand::RpmUp } else { DeviceCommand::RpmDown };
+        // Overshot: the increment doesn't divide the distance, this is as close as it gets
+        if last_nudge.as_ref().is_some_and(|last| *last != nudge) {
+            break true;
+        }
+        if nudges == MAX_NUDGES_PER_STEP {
+            break false;
+        }
+        send(state, &nudge)?;
+        nudges += 1;
+        let previous = status.rpm;
+        status = read_status(state, history)?;
+        // The device is at its limit
+        if status.rpm == previous {
+            break false;
+        }
+        last_nudge = Some(nudge);
+    };
+    let settle_ms = settle_start.elapsed().as_millis() as u64;
+
+    let mut samples = Vec::new();
+    let dwell_start = Instant::now();
+    while dwell_start.elapsed() < dwell {
+        job.checkpoint()?;
+        std::thread::sleep(Duration::from_millis(SAMPLE_INTERVAL_MS).min(dwell.saturating_sub(dwell_start.elapsed())));
+        samples.push(StatusSample::from_status(&read_status(state, history)?));
+    }
+
+    Ok(StepResult {
+        target_rpm: target,
+        settled_rpm: status.rpm,
+        reached,
+        settle_ms,
+        stats: RpmStats::from_samples(dwell.as_secs(), &samples),
+    })
+}
+
+fn read_status(state: &SerialState, history: &StatusHistory) -> Result<DeviceStatus, String> {
+    let status = state.with_blocking(|connection| connection.get_status().map_err(|e| e.to_string()))??;
+    history.record(&status);
+    Ok(status)
+}
+
+fn send(state: &SerialState, command: &DeviceCommand) -> Result<(), String> {
+    let command = command.clone();
+    let outcome = state.with_blocking(move |connection| connection.send_command(&command).map_err(|e| e.to_string()))??;
+    if outcome.status == OutcomeStatus::Rejected {
+        return Err(outcome.describe());
+    }
+    Ok(())
+}
@@ -0,0 +1,284 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+// Folder created next to the executable for portable storage
+const PORTABLE_DIR: &str = "data";
+// Marker file next to the executable that switches on portable mode
+const PORTABLE_FLAG: &str = "portable.flag";
+
+/// Candidate places for settings, logs and the signal library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageLocation {
+    /// The OS app data directory (default)
+    AppData,
+    /// A `data/` folder beside the executable
+    Portable,
+    /// The system temp directory; contents may be wiped by the OS
+    Temp,
+    /// A folder picked by the user, see `migrate`
+    Custom,
+}
+
+pub const DEFAULT_FALLBACK_ORDER: [StorageLocation; 3] = [
+    StorageLocation::AppData,
+    StorageLocation::Portable,
+    StorageLocation::Temp,
+];
+
+/// Where app data is being stored for this run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageInfo {
+    pub location: StorageLocation,
+    pub path: String,
+    /// True when an earlier location in the fallback order was unusable
+    pub is_fallback: bool,
+    /// True when a portable flag file forces storage beside the executable
+    pub portable_mode: bool,
+    pub warning: Option<String>,
+}
+
+fn exe_dir() -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let dir = exe.parent().ok_or("Executable has no parent directory")?;
+    Ok(dir.to_path_buf())
+}
+
+/// The `data/` folder beside the executable used in portable mode
+pub fn portable_dir() -> Result<PathBuf, String> {
+    Ok(exe_dir()?.join(PORTABLE_DIR))
+}
+
+/// Whether the portable flag file sits next to the executable
+pub fn is_portable_mode() -> bool {
+    exe_dir().is_ok_and(|dir| dir.join(PORTABLE_FLAG).exists())
+}
+
+/// Locations to try: portable mode pins storage beside the executable,
+/// otherwise a custom folder (if set) ahead of the configured order
+/// (or the default when none is configured)
+pub fn effective_order(configured: &[StorageLocation], custom_dir: Option<&str>) -> Vec<StorageLocation> {
+    if is_portable_mode() {
+        return vec![StorageLocation::Portable];
+    }
+    let mut order = if configured.is_empty() {
+        DEFAULT_FALLBACK_ORDER.to_vec()
+    } else {
+        configured.to_vec()
+    };
+    order.retain(|l| *l != StorageLocation::Custom);
+    if custom_dir.is_some() {
+        order.insert(0, StorageLocation::Custom);
+    }
+    order
+}
+
+fn candidate_dir(app: &AppHandle, location: StorageLocation, custom_dir: Option<&str>) -> Result<PathBuf, String> {
+    match location {
+        StorageLocation::AppData => app.path().app_data_dir().map_err(|e| e.to_string()),
+        StorageLocation::Portable => portable_dir(),
+        StorageLocation::Temp => Ok(std::env::temp_dir().join(&app.config().identifier)),
+        StorageLocation::Custom => custom_dir
+            .map(PathBuf::from)
+            .ok_or_else(|| "No custom data folder configured".to_string()),
+    }
+}
+
+/// Create the directory and prove it is writable
+fn ensure_writable(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let probe = dir.join(".write_test");
+    fs::write(&probe, b"ok").map_err(|e| e.to_string())?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Pick the first usable location in `order`
+pub fn resolve(app: &AppHandle, order: &[StorageLocation], custom_dir: Option<&str>) -> Result<StorageInfo, String> {
+    let mut failures = Vec::new();
+
+    for (index, location) in order.iter().enumerate() {
+        let dir = match candidate_dir(app, *location, custom_dir) {
+            Ok(dir) => dir,
+            Err(e) => {
+                failures.push(format!("{:?}: {}", location, e));
+                continue;
+            }
+        };
+        if let Err(e) = ensure_writable(&dir) {
+            failures.push(format!("{:?} ({}): {}", location, dir.display(), e));
+            continue;
+        }
+
+        let mut warning = (index > 0).then(|| format!("Using fallback storage. {}", failures.join("; ")));
+        if *location == StorageLocation::Temp {
+            let note = "Data is stored in the temp directory and may be deleted by the OS";
+            warning = Some(match warning {
+                Some(w) => format!("{}. {}", w, note),
+                None => note.to_string(),
+            });
+        }
+        if let Some(ref w) = warning {
+            eprintln!("[STORAGE] {}", w);
+        }
+
+        return Ok(StorageInfo {
+            location: *location,
+            path: dir.to_string_lossy().to_string(),
+            is_fallback: index > 0,
+            portable_mode: is_portable_mode(),
+            warning,
+        });
+    }
+
+    Err(format!("No usable storage location. {}", failures.join("; ")))
+}
+
+/// Storage in use for this run; replaced when the data is migrated elsewhere
+pub struct ActiveStorage(Mutex<StorageInfo>);
+
+impl ActiveStorage {
+    pub fn new(info: StorageInfo) -> Self {
+        ActiveStorage(Mutex::new(info))
+    }
+}
+
+/// Where settings are read from before the real storage is known; it holds
+/// the pointer to a custom data folder
+pub fn bootstrap_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let info = resolve(app, &effective_order(&DEFAULT_FALLBACK_ORDER, None), None)?;
+    Ok(PathBuf::from(info.path))
+}
+
+/// Storage chosen at startup, or a fresh resolution if setup hasn't run yet
+pub fn info(app: &AppHandle) -> Result<StorageInfo, String> {
+    match app.try_state::<ActiveStorage>() {
+        Some(active) => active.0.lock().map(|i| i.clone()).map_err(|e| e.to_string()),
+        None => resolve(app, &effective_order(&DEFAULT_FALLBACK_ORDER, None), None),
+    }
+}
+
+/// Root folder for all persisted app data
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = PathBuf::from(info(app)?.path);
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    Ok(dir)
+}
+
+/// Outcome of moving the app data to another folder
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from: String,
+    pub to: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Relative paths of every file below `root`
+fn list_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.is_dir() {
+            list_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+fn file_digest(path: &Path) -> Result<Vec<u8>, String> {
+    let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Sha256::digest(&data).to_vec())
+}
+
+/// Copy all app data to `target` (a new or empty folder) and switch this run over to it.
+///
+/// Files are copied into a staging folder next to `target` and checked against their
+/// originals before the staging folder is renamed into place, so a failure leaves
+/// `target` untouched and the current data in use. The old copies are removed last.
+pub fn migrate(app: &AppHandle, target: &Path) -> Result<MigrationReport, String> {
+    if is_portable_mode() {
+        return Err("Portable mode keeps the data beside the executable; remove the portable flag first".into());
+    }
+    if !target.is_absolute() {
+        return Err("Choose an absolute folder path".into());
+    }
+    let current = info(app)?;
+    let source = PathBuf::from(&current.path);
+    if target.starts_with(&source) || source.starts_with(target) {
+        return Err("The new folder can't be inside the current data folder or contain it".into());
+    }
+    if target.exists() && fs::read_dir(target).map_err(|e| e.to_string())?.next().is_some() {
+        return Err(format!("{} is not empty", target.display()));
+    }
+    let parent = target.parent().ok_or("The new folder has no parent directory")?;
+    let name = target.file_name().ok_or("The new folder has no name")?;
+    ensure_writable(parent)?;
+
+    let mut files = Vec::new();
+    list_files(&source, &source, &mut files)?;
+
+    let staging = parent.join(format!(".{}.migrating", name.to_string_lossy()));
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| e.to_string())?;
+    }
+    let copy = || -> Result<u64, String> {
+        let mut bytes = 0;
+        for relative in &files {
+            let to = staging.join(relative);
+            if let Some(dir) = to.parent() {
+                fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            bytes += fs::copy(source.join(relative), &to).map_err(|e| format!("{}: {}", relative.display(), e))?;
+            if file_digest(&source.join(relative))? != file_digest(&to)? {
+                return Err(format!("{} differs after copying", relative.display()));
+            }
+        }
+        if target.exists() {
+            fs::remove_dir(target).map_err(|e| e.to_string())?;
+        }
+        fs::rename(&staging, target).map_err(|e| e.to_string())?;
+        Ok(bytes)
+    };
+    let bytes = match copy() {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(format!("Migration aborted, nothing was changed: {}", e));
+        }
+    };
+
+    let to = target.to_string_lossy().to_string();
+    if let Some(active) = app.try_state::<ActiveStorage>() {
+        if let Ok(mut info) = active.0.lock() {
+            *info = StorageInfo {
+                location: StorageLocation::Custom,
+                path: to.clone(),
+                is_fallback: false,
+                portable_mode: false,
+                warning: None,
+            };
+        }
+    }
+    eprintln!("[STORAGE] Moved {} files ({} bytes) from {} to {}", files.len(), bytes, current.path, to);
+
+    for relative in &files {
+        if let Err(e) = fs::remove_file(source.join(relative)) {
+            eprintln!("[STORAGE] Failed to remove old copy of {}: {}", relative.display(), e);
+        }
+    }
+
+    Ok(MigrationReport {
+        from: current.path,
+        to,
+        files: files.len(),
+        bytes,
+    })
+}
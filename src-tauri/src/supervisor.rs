@@ -0,0 +1,75 @@
+use crate::scheduler::{CancelToken, Scheduler, Scope, TaskHandle};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// How long disconnect waits for connection tasks to wind down before giving up on them
+const SHUTDOWN_TIMEOUT_MS: u64 = 3000;
+
+/// Background work that only makes sense while a device is connected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskRole {
+    Soak,
+    Reader,
+    Reconnect,
+}
+
+/// Owner of all tasks tied to the current connection.
+///
+/// Each role runs at most once; `shutdown` cancels everything and waits for it to
+/// return, so no task outlives the port it talks to.
+#[derive(Clone)]
+pub struct ConnectionSupervisor {
+    scheduler: Scheduler,
+    tasks: Arc<Mutex<HashMap<TaskRole, TaskHandle>>>,
+}
+
+impl ConnectionSupervisor {
+    pub fn new(scheduler: Scheduler) -> Self {
+        ConnectionSupervisor {
+            scheduler,
+            tasks: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Start `task` in `role`, cancelling whatever ran in that role before
+    pub fn start<F, Fut>(&self, role: TaskRole, task: F) -> TaskHandle
+    where
+        F: FnOnce(CancelToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = format!("{:?}", role).to_lowercase();
+        let handle = self.scheduler.spawn(&name, Scope::Connection, task);
+        if let Ok(mut tasks) = self.tasks.lock() {
+            if let Some(previous) = tasks.insert(role, handle.clone()) {
+                previous.cancel();
+            }
+        }
+        handle
+    }
+
+    /// Task still running in `role`, if any
+    pub fn get(&self, role: TaskRole) -> Option<TaskHandle> {
+        let tasks = self.tasks.lock().ok()?;
+        tasks.get(&role).filter(|h| h.is_active()).cloned()
+    }
+
+    /// Cancel every connection task and wait for them to finish
+    pub async fn shutdown(&self) {
+        let handles: Vec<(TaskRole, TaskHandle)> = match self.tasks.lock() {
+            Ok(mut tasks) => tasks.drain().collect(),
+            Err(_) => Vec::new(),
+        };
+        // Also catches connection-scoped work started outside the supervisor
+        self.scheduler.cancel_scope(Scope::Connection);
+
+        let deadline = Instant::now() + Duration::from_millis(SHUTDOWN_TIMEOUT_MS);
+        for (role, handle) in handles {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if tokio::time::timeout(remaining, handle.join()).await.is_err() {
+                eprintln!("[SERIAL] {:?} task did not stop within {}ms", role, SHUTDOWN_TIMEOUT_MS);
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// Upper bounds of the histogram buckets; anything slower lands in a final open bucket
+const BUCKET_BOUNDS_MS: [u64; 11] = [5, 10, 20, 50, 100, 200, 500, 1000, 2000, 5000, 10000];
+
+/// Distribution of one duration, in milliseconds
+#[derive(Debug, Clone, Serialize)]
+pub struct Histogram {
+    /// Samples per bucket of `BUCKET_BOUNDS_MS`, plus one for slower ones
+    pub counts: Vec<u64>,
+    pub samples: u64,
+    pub min_ms: u64,
+    pub max_ms: u64,
+    pub total_ms: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            counts: vec![0; BUCKET_BOUNDS_MS.len() + 1],
+            samples: 0,
+            min_ms: 0,
+            max_ms: 0,
+            total_ms: 0,
+        }
+    }
+}
+
+impl Histogram {
+    fn add(&mut self, duration: Duration) {
+        let ms = duration.as_millis() as u64;
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|&bound| ms <= bound)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.counts[bucket] += 1;
+        self.min_ms = if self.samples == 0 { ms } else { self.min_ms.min(ms) };
+        self.max_ms = self.max_ms.max(ms);
+        self.total_ms += ms;
+        self.samples += 1;
+    }
+
+    /// Upper bound of the bucket holding the given percentile, capped at the slowest
+    /// sample; good enough to pick a timeout from
+    fn percentile(&self, percent: u64) -> Option<u64> {
+        if self.samples == 0 {
+            return None;
+        }
+        let rank = (self.samples * percent).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = BUCKET_BOUNDS_MS.get(bucket).copied().unwrap_or(self.max_ms);
+                return Some(bound.min(self.max_ms));
+            }
+        }
+        Some(self.max_ms)
+    }
+}
+
+/// Timings of one kind of command
+#[derive(Debug, Clone, Default)]
+struct CommandTimings {
+    first_byte: Histogram,
+    total: Histogram,
+    // Commands the device never answered
+    no_reply: u64,
+}
+
+/// Timings of one kind of command, as reported to the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandTimingStats {
+    pub command: String,
+    /// From sending the command to the first byte of the reply
+    pub first_byte: Histogram,
+    /// From sending the command to the end of the reply
+    pub total: Histogram,
+    pub no_reply: u64,
+    pub first_byte_p50_ms: Option<u64>,
+    pub first_byte_p95_ms: Option<u64>,
+    pub total_p95_ms: Option<u64>,
+}
+
+/// Timings collected while one firmware build was connected
+#[derive(Debug, Clone, Serialize)]
+pub struct FirmwareTimingStats {
+    /// `None` when the firmware didn't report a version
+    pub firmware_version: Option<String>,
+    pub commands: Vec<CommandTimingStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingStats {
+    /// Histogram bucket upper bounds in ms; the last bucket has none
+    pub bucket_bounds_ms: Vec<u64>,
+    pub firmware: Vec<FirmwareTimingStats>,
+}
+
+/// Response times per firmware build and command, kept for the app session so a
+/// slower firmware stands out next to the previous one
+#[derive(Debug, Default)]
+pub struct TimingRecorder(BTreeMap<Option<String>, BTreeMap<String, CommandTimings>>);
+
+impl TimingRecorder {
+    /// Note one command's timings; `first_byte` is `None` when no reply came at all
+    pub fn record(&mut self, firmware_version: Option<&str>, command: &str, first_byte: Option<Duration>, total: Duration) {
+        let timings = self
+            .0
+            .entry(firmware_version.map(String::from))
+            .or_default()
+            .entry(command.to_string())
+            .or_default();
+        match first_byte {
+            Some(first_byte) => {
+                timings.first_byte.add(first_byte);
+                timings.total.add(total);
+            }
+            None => timings.no_reply += 1,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn stats(&self) -> TimingStats {
+        let firmware = self
+            .0
+            .iter()
+            .map(|(version, commands)| FirmwareTimingStats {
+                firmware_version: version.clone(),
+                commands: commands
+                    .iter()
+                    .map(|(command, t)| CommandTimingStats {
+                        command: command.clone(),
+                        first_byte: t.first_byte.clone(),
+                        total: t.total.clone(),
+                        no_reply: t.no_reply,
+                        first_byte_p50_ms: t.first_byte.percentile(50),
+                        first_byte_p95_ms: t.first_byte.percentile(95),
+                        total_p95_ms: t.total.percentile(95),
+                    })
+                    .collect(),
+            })
+            .collect();
+        TimingStats {
+            bucket_bounds_ms: BUCKET_BOUNDS_MS.to_vec(),
+            firmware,
+        }
+    }
+}
+
+/// Config upload speed measured on this connection: frame bytes sent against the time
+/// until the device acknowledged them
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkThroughput {
+    pub uploads: u64,
+    pub bytes: u64,
+    pub total_ms: u64,
+}
+
+/// How long an upload is expected to take
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadEstimate {
+    pub frame_bytes: usize,
+    pub eta_ms: u64,
+    /// `false` when no upload has completed on this connection yet and the estimate
+    /// comes from the line speed alone
+    pub measured: bool,
+    /// Uploads the measured rate is based on
+    pub samples: u64,
+}
+
+impl LinkThroughput {
+    /// Note one acknowledged upload
+    pub fn record(&mut self, bytes: usize, elapsed: Duration) {
+        self.uploads += 1;
+        self.bytes += bytes as u64;
+        self.total_ms += elapsed.as_millis() as u64;
+    }
+
+    /// Expected duration for `frame_bytes`; before any upload completed, the nominal
+    /// rate of `baud_rate` (10 bits per byte) plus `per_chunk` pacing every `chunk_size` bytes
+    pub fn estimate(&self, frame_bytes: usize, baud_rate: u32, chunk_size: usize, per_chunk: Duration) -> UploadEstimate {
+        let eta_ms = if self.bytes > 0 && self.total_ms > 0 {
+            frame_bytes as u64 * self.total_ms / self.bytes
+        } else {
+            let wire_ms = frame_bytes as u64 * 10 * 1000 / baud_rate.max(1) as u64;
+            let chunks = frame_bytes.div_ceil(chunk_size.max(1)) as u64;
+            wire_ms + chunks * per_chunk.as_millis() as u64
+        };
+        UploadEstimate {
+            frame_bytes,
+            eta_ms,
+            measured: self.uploads > 0,
+            samples: self.uploads,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::serial::DeviceStatus;
+use std::sync::Mutex;
+use tauri::image::Image;
+use tauri::tray::{TrayIcon, TrayIconBuilder};
+use tauri::{AppHandle, Manager};
+
+const TRAY_ID: &str = "bench";
+const APP_NAME: &str = "ESP32 Signal Injector";
+// Badge diameter as a share of the icon width
+const BADGE_SCALE: f32 = 0.45;
+
+/// What the bench is doing, as shown on the tray and taskbar icon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchState {
+    Disconnected,
+    Idle,
+    Running,
+    Fault,
+}
+
+impl BenchState {
+    /// State a status response puts the bench in
+    pub fn of(status: &DeviceStatus) -> Self {
+        if !status.connected {
+            BenchState::Disconnected
+        } else if status.fault.is_some() {
+            BenchState::Fault
+        } else if status.running {
+            BenchState::Running
+        } else {
+            BenchState::Idle
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            BenchState::Disconnected => "disconnected",
+            BenchState::Idle => "connected, idle",
+            BenchState::Running => "signal running",
+            BenchState::Fault => "device fault",
+        }
+    }
+
+    fn badge_color(self) -> [u8; 3] {
+        match self {
+            BenchState::Disconnected => [128, 128, 128],
+            BenchState::Idle => [59, 130, 246],
+            BenchState::Running => [34, 197, 94],
+            BenchState::Fault => [239, 68, 68],
+        }
+    }
+}
+
+struct Shown {
+    tray: TrayIcon,
+    base: Image<'static>,
+    state: BenchState,
+}
+
+/// The tray icon and the state it shows; empty when the platform has no tray
+#[derive(Default)]
+pub struct TrayState(Mutex<Option<Shown>>);
+
+/// Put the app icon in the tray, badged as disconnected
+pub fn init(app: &AppHandle) {
+    let Some(base) = app.default_window_icon().map(|i| i.clone().to_owned()) else {
+        return;
+    };
+    let state = BenchState::Disconnected;
+    let built = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(badged(&base, state))
+        .tooltip(tooltip(state))
+        .build(app);
+    match built {
+        Ok(tray) => {
+            if let Ok(mut shown) = app.state::<TrayState>().0.lock() {
+                *shown = Some(Shown { tray, base, state });
+            }
+        }
+        Err(e) => eprintln!("[TRAY] Failed to create the tray icon: {}", e),
+    }
+}
+
+/// Show `state` on the tray icon and the main window's taskbar icon, if it changed
+pub fn show(app: &AppHandle, state: BenchState) {
+    let tray_state = app.state::<TrayState>();
+    let Ok(mut shown) = tray_state.0.lock() else {
+        return;
+    };
+    let Some(shown) = shown.as_mut().filter(|s| s.state != state) else {
+        return;
+    };
+    shown.state = state;
+    let icon = badged(&shown.base, state);
+    if let Err(e) = shown.tray.set_icon(Some(icon.clone())) {
+        eprintln!("[TRAY] Failed to update the tray icon: {}", e);
+    }
+    let _ = shown.tray.set_tooltip(Some(tooltip(state)));
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_icon(icon);
+    }
+}
+
+fn tooltip(state: BenchState) -> String {
+    format!("{}: {}", APP_NAME, state.label())
+}
+
+/// The icon with a state-coloured dot in the bottom-right corner
+fn badged(base: &Image<'_>, state: BenchState) -> Image<'static> {
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    let radius = width as f32 * BADGE_SCALE / 2.0;
+    let (cx, cy) = (width as f32 - radius, height as f32 - radius);
+    let [r, g, b] = state.badge_color();
+
+    for y in 0..height {
+        for x in 0..width {
+            let distance = ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt();
+            if distance > radius {
+                continue;
+            }
+            // Dark rim so the dot stands out on light and dark taskbars alike
+            let pixel = if distance > radius - (radius / 5.0).max(1.0) { [32, 32, 32] } else { [r, g, b] };
+            let i = ((y * width + x) * 4) as usize;
+            rgba[i..i + 4].copy_from_slice(&[pixel[0], pixel[1], pixel[2], 255]);
+        }
+    }
+    Image::new_owned(rgba, width, height)
+}
@@ -0,0 +1,141 @@
+use crate::serial::ChunkPacing;
+use crate::session::now_millis;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+
+const QUEUE_FILE: &str = "pending_uploads.json";
+
+/// What to send, in a form that survives a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum UploadRequest {
+    /// Config JSON as handed over by the editor
+    Config {
+        config: String,
+        #[serde(default)]
+        pacing: ChunkPacing,
+    },
+    /// Signal from the library, by filename
+    Library { filename: String, override_binding: bool },
+}
+
+/// An upload that was queued or in flight, kept on disk until it ends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingUpload {
+    pub key: String,
+    pub label: String,
+    pub queued_at: u64,
+    pub note: Option<String>,
+    pub request: UploadRequest,
+}
+
+/// Uploads waiting for the port or in progress, persisted so an accidental quit
+/// doesn't lose a provisioning session
+#[derive(Clone, Default)]
+pub struct UploadQueue {
+    // Serializes read-modify-write cycles of the queue file
+    file: Arc<Mutex<()>>,
+    // Left over from the previous run, until resumed or discarded
+    interrupted: Arc<Mutex<Vec<PendingUpload>>>,
+}
+
+/// Keeps an upload in the persisted queue until dropped, however the upload ends
+pub struct QueueEntry {
+    queue: UploadQueue,
+    app: AppHandle,
+    key: String,
+}
+
+impl Drop for QueueEntry {
+    fn drop(&mut self) {
+        if let Err(e) = self.queue.remove(&self.app, std::slice::from_ref(&self.key)) {
+            eprintln!("[UPLOAD] Failed to update the pending upload queue: {}", e);
+        }
+    }
+}
+
+fn queue_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(storage::data_dir(app)?.join(QUEUE_FILE))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<PendingUpload>, String> {
+    let path = queue_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn save(app: &AppHandle, uploads: &[PendingUpload]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(uploads).map_err(|e| e.to_string())?;
+    fs::write(queue_path(app)?, json).map_err(|e| e.to_string())
+}
+
+impl UploadQueue {
+    /// Pick up whatever the previous run left in the queue; call once at startup
+    pub fn restore(&self, app: &AppHandle) {
+        let uploads = match load(app) {
+            Ok(uploads) => uploads,
+            Err(e) => {
+                eprintln!("[UPLOAD] Pending upload queue unreadable, ignoring it: {}", e);
+                Vec::new()
+            }
+        };
+        if !uploads.is_empty() {
+            eprintln!("[UPLOAD] {} upload(s) interrupted by the last shutdown", uploads.len());
+        }
+        if let Ok(mut interrupted) = self.interrupted.lock() {
+            *interrupted = uploads;
+        }
+    }
+
+    /// Uploads the previous run didn't get to finish
+    pub fn interrupted(&self) -> Vec<PendingUpload> {
+        self.interrupted.lock().map(|u| u.clone()).unwrap_or_default()
+    }
+
+    /// Hand over the interrupted uploads and forget them, on disk too.
+    /// Resumed ones are queued again under new keys.
+    pub fn take_interrupted(&self, app: &AppHandle) -> Result<Vec<PendingUpload>, String> {
+        let uploads = std::mem::take(&mut *self.interrupted.lock().map_err(|e| e.to_string())?);
+        let keys: Vec<String> = uploads.iter().map(|u| u.key.clone()).collect();
+        self.remove(app, &keys)?;
+        Ok(uploads)
+    }
+
+    /// Persist an upload about to start; it stays queued until the entry is dropped
+    pub fn add(&self, app: &AppHandle, label: &str, note: Option<String>, request: UploadRequest) -> Result<QueueEntry, String> {
+        let key = uuid::Uuid::new_v4().simple().to_string();
+        let _file = self.file.lock().map_err(|e| e.to_string())?;
+        let mut uploads = load(app).unwrap_or_default();
+        uploads.push(PendingUpload {
+            key: key.clone(),
+            label: label.to_string(),
+            queued_at: now_millis(),
+            note,
+            request,
+        });
+        save(app, &uploads)?;
+        Ok(QueueEntry {
+            queue: self.clone(),
+            app: app.clone(),
+            key,
+        })
+    }
+
+    fn remove(&self, app: &AppHandle, keys: &[String]) -> Result<(), String> {
+        let _file = self.file.lock().map_err(|e| e.to_string())?;
+        let mut uploads = load(app).unwrap_or_default();
+        let before = uploads.len();
+        uploads.retain(|u| !keys.contains(&u.key));
+        if uploads.len() == before {
+            return Ok(());
+        }
+        save(app, &uploads)
+    }
+}
@@ -1,4 +1,6 @@
 import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { PortSelector } from "./components/PortSelector";
 import { StatusDisplay } from "./components/StatusDisplay";
 import { ControlPanel } from "./components/ControlPanel";
@@ -6,22 +8,118 @@ import { SignalLibrary } from "./components/SignalLibrary";
 import { ConfigUploader } from "./components/ConfigUploader";
 import { SignalEditor } from "./components/SignalEditor";
 import { useConnectionStore } from "./store/connectionStore";
-import type { DeviceStatus } from "./types";
-import { Cpu, Waves } from "lucide-react";
+import type { Attention, ConnectionLost, ConnectionRestored, CriticalSectionChange, DeviceStatus, PortInfo, UploadProgress } from "./types";
+import { Cpu, Terminal, Waves } from "lucide-react";
+import { playAttention } from "./utils/attention";
 
 type Tab = 'device' | 'editor';
 
 function App() {
-  const { status, refreshStatus } = useConnectionStore();
+  const { status, refreshStatus, restoreSession } = useConnectionStore();
   const [activeTab, setActiveTab] = useState<Tab>('device');
 
+  // After a webview reload the backend may still hold an open connection
+  useEffect(() => {
+    restoreSession();
+  }, [restoreSession]);
+
+  // The backend announces uploads/flashes/NVS writes that must not be interleaved with polling
+  useEffect(() => {
+    const unlisten = listen<CriticalSectionChange>("device://critical-section", (event) => {
+      useConnectionStore.setState({ deviceBusy: event.payload.active ? event.payload.reason : null });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Bytes sent of the upload in flight, so the uploader shows more than a frozen button
+  useEffect(() => {
+    const unlisten = listen<UploadProgress>("upload://progress", (event) => {
+      useConnectionStore.setState({ uploadProgress: event.payload });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Run/stop/RPM commands are followed by a status query on the backend
+  useEffect(() => {
+    const unlisten = listen<DeviceStatus>("device://status", (event) => {
+      useConnectionStore.setState({ status: event.payload });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // A reset while the signal should be running is worth interrupting the operator for
+  useEffect(() => {
+    const unlisten = listen<DeviceStatus>("device://reset", (event) => {
+      useConnectionStore.setState({
+        notice: `Device reset while running (reset #${event.payload.reset_count} this connection)`,
+      });
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // The backend drops a port that stopped working and may be trying to reopen it
+  useEffect(() => {
+    const unlistenLost = listen<ConnectionLost>("connection://lost", (event) => {
+      const { port, reason, reconnecting } = event.payload;
+      useConnectionStore.setState((state) => ({
+        status: { ...state.status, connected: false, running: false },
+        notice: `Lost connection to ${port}: ${reason}${reconnecting ? " (reconnecting...)" : ""}`,
+      }));
+    });
+    const unlistenRestored = listen<ConnectionRestored>("connection://restored", (event) => {
+      useConnectionStore.setState({ notice: `Reconnected to ${event.payload.port}` });
+      refreshStatus();
+    });
+    return () => {
+      unlistenLost.then((fn) => fn());
+      unlistenRestored.then((fn) => fn());
+    };
+  }, [refreshStatus]);
+
+  // Events the operator must notice even away from the screen (rules in settings)
+  useEffect(() => {
+    const unlisten = listen<Attention>("app://attention", (event) => {
+      playAttention(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Keep the port list current as adapters are plugged in and out
+  useEffect(() => {
+    const unlistenAdded = listen<PortInfo>("port://added", (event) => {
+      useConnectionStore.setState((state) => ({
+        ports: [...state.ports.filter((p) => p.name !== event.payload.name), event.payload],
+      }));
+    });
+    const unlistenRemoved = listen<PortInfo>("port://removed", (event) => {
+      useConnectionStore.setState((state) => ({
+        ports: state.ports.filter((p) => p.name !== event.payload.name),
+      }));
+    });
+    return () => {
+      unlistenAdded.then((fn) => fn());
+      unlistenRemoved.then((fn) => fn());
+    };
+  }, []);
+
   // Auto-refresh status every 2 seconds when connected (but skip when busy with commands)
   useEffect(() => {
     if (!status.connected) return;
 
     const interval = setInterval(() => {
       // Skip refresh if a command is in progress to prevent serial contention
-      if (!useConnectionStore.getState().isCommandBusy) {
+      const { isCommandBusy, deviceBusy } = useConnectionStore.getState();
+      if (!isCommandBusy && !deviceBusy) {
         refreshStatus();
       }
     }, 2000);
@@ -55,6 +153,13 @@ function App() {
               <Waves className="w-4 h-4" />
               Signal Editor
             </button>
+            <button
+              onClick={() => invoke("open_monitor_window")}
+              className="ml-auto flex items-center gap-2 px-4 py-4 text-sm font-medium text-muted-foreground hover:text-foreground transition-colors"
+            >
+              <Terminal className="w-4 h-4" />
+              Serial Monitor
+            </button>
           </div>
         </div>
       </nav>
@@ -1,15 +1,27 @@
+import { invoke } from "@tauri-apps/api/core";
 import { useConnectionStore } from "../../store/connectionStore";
 import type { ChangeEvent } from "react";
 import { isDeviceSignalConfig } from "../../utils/deviceCodec";
+import type { CsvDelimiter, DecimalSeparator } from "../../utils/edgeCsv";
+import type { UploadProgress } from "../../types";
+
+const uploadStageLabel: Record<UploadProgress["stage"], string> = {
+  preparing: "Preparing device",
+  sending: "Sending",
+  awaiting_ack: "Waiting for device",
+};
 
 export function ConfigUploader() {
   const {
     status,
     configJson,
     loadedConfig,
+    csvOptions,
     setConfigJson,
+    setCsvOptions,
     parseConfig,
     uploadConfig,
+    uploadProgress,
   } = useConnectionStore();
 
   const handlePaste = (e: ChangeEvent<HTMLTextAreaElement>) => {
@@ -23,7 +35,7 @@ export function ConfigUploader() {
   const handleFileLoad = async () => {
     const input = document.createElement("input");
     input.type = "file";
-    input.accept = ".json";
+    input.accept = ".json,.csv,.txt";
     input.onchange = async (e) => {
       const file = (e.target as HTMLInputElement).files?.[0];
       if (file) {
@@ -43,7 +55,7 @@ export function ConfigUploader() {
           <div className="flex items-center justify-between mb-2">
             <h2 className="text-sm font-semibold text-foreground">Signal Configuration</h2>
             <p className="text-muted-foreground text-xs">
-              Paste JSON from Signal Generator or load a file
+              Paste JSON from Signal Generator, an edge-list CSV, or load a file
             </p>
           </div>
 
@@ -53,6 +65,36 @@ export function ConfigUploader() {
             placeholder={`{\n  "name": "My Signal",\n  "CKP": "SIG1...",\n  "CMP1": null,\n  "CMP2": null\n}`}
             className="w-full h-24 px-2 py-1.5 bg-muted border border-border rounded-md text-foreground font-mono text-xs resize-none focus:outline-none focus:ring-2 focus:ring-primary"
           />
+
+          {/* CSV exports from pt-BR machines use decimal commas and ';' */}
+          <div className="flex items-center gap-3 mt-1 text-xs text-muted-foreground">
+            <label className="flex items-center gap-1">
+              CSV delimiter
+              <select
+                value={csvOptions.delimiter}
+                onChange={(e) => setCsvOptions({ ...csvOptions, delimiter: e.target.value as CsvDelimiter })}
+                className="bg-muted border border-border rounded px-1"
+              >
+                <option value="auto">Auto</option>
+                <option value=";">;</option>
+                <option value=",">,</option>
+                <option value={"\t"}>Tab</option>
+                <option value=" ">Space</option>
+              </select>
+            </label>
+            <label className="flex items-center gap-1">
+              Decimal
+              <select
+                value={csvOptions.decimal}
+                onChange={(e) => setCsvOptions({ ...csvOptions, decimal: e.target.value as DecimalSeparator })}
+                className="bg-muted border border-border rounded px-1"
+              >
+                <option value="auto">Auto</option>
+                <option value=".">0.125</option>
+                <option value=",">0,125</option>
+              </select>
+            </label>
+          </div>
         </div>
 
         {/* Right side - validation info and buttons */}
@@ -106,6 +148,28 @@ export function ConfigUploader() {
           >
             ⬆ Upload to ESP32
           </button>
+
+          {uploadProgress && (
+            <div className="space-y-1">
+              <div className="h-1.5 bg-muted rounded-full overflow-hidden">
+                <div
+                  className="h-full bg-green-600 transition-all"
+                  style={{ width: `${uploadProgress.percent}%` }}
+                />
+              </div>
+              <div className="flex items-center justify-between text-[10px] text-muted-foreground">
+                <span>
+                  {uploadStageLabel[uploadProgress.stage]} · {uploadProgress.bytes_sent}/{uploadProgress.total_bytes} bytes, {uploadProgress.chunks_sent} chunks
+                </span>
+                <button
+                  onClick={() => invoke("abort_upload").catch(() => {})}
+                  className="px-1.5 py-0.5 border border-border rounded hover:bg-muted"
+                >
+                  Cancel
+                </button>
+              </div>
+            </div>
+          )}
         </div>
       </div>
     </div>
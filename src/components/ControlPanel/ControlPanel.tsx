@@ -1,4 +1,4 @@
-import { useRef, useCallback } from "react";
+import { useRef, useCallback, useEffect, useState, type FormEvent } from "react";
 import { useConnectionStore } from "../../store/connectionStore";
 
 export function ControlPanel() {
@@ -11,9 +11,26 @@ export function ControlPanel() {
     decreaseRpm,
     saveToNvs,
     resetDefaults,
+    lastBackup,
+    loadLastBackup,
+    restoreLastBackup,
+    sendCustomCommand,
     refreshStatus,
   } = useConnectionStore();
 
+  useEffect(() => {
+    loadLastBackup();
+  }, [loadLastBackup]);
+
+  const [customText, setCustomText] = useState("");
+  const [customReply, setCustomReply] = useState<string | null>(null);
+
+  const handleCustomCommand = async (e: FormEvent) => {
+    e.preventDefault();
+    if (!customText.trim()) return;
+    setCustomReply(await sendCustomCommand(customText));
+  };
+
   const isDisabled = !status.connected;
 
   // Debounce timers for RPM buttons
@@ -121,6 +138,45 @@ export function ControlPanel() {
           🔧 Reset
         </button>
       </div>
+
+      {lastBackup && (
+        <div className="mt-2 flex items-center justify-between gap-2 text-[10px] text-muted-foreground">
+          <span className="truncate" title={lastBackup.reason}>
+            Backup from {new Date(lastBackup.taken_at).toLocaleString()}
+          </span>
+          <button
+            onClick={restoreLastBackup}
+            disabled={isDisabled || isCommandBusy}
+            className="py-0.5 px-2 bg-muted hover:bg-muted/80 border border-border rounded-md text-foreground transition-colors disabled:opacity-50"
+            title="Upload the config saved before the last reset"
+          >
+            ↩ Restore
+          </button>
+        </div>
+      )}
+
+      {/* Raw firmware command */}
+      <form onSubmit={handleCustomCommand} className="mt-2 flex gap-1.5">
+        <input
+          value={customText}
+          onChange={(e) => setCustomText(e.target.value)}
+          disabled={isDisabled}
+          placeholder="Custom command"
+          className="flex-1 min-w-0 px-2 py-1 bg-background border border-border rounded-md text-xs font-mono disabled:opacity-50"
+        />
+        <button
+          type="submit"
+          disabled={isDisabled || !customText.trim()}
+          className="py-1 px-2 bg-muted hover:bg-muted/80 border border-border rounded-md text-foreground text-xs transition-colors disabled:opacity-50"
+        >
+          Send
+        </button>
+      </form>
+      {customReply !== null && (
+        <pre className="mt-1 max-h-20 overflow-auto text-[10px] text-muted-foreground font-mono whitespace-pre-wrap">
+          {customReply.trim() || "(no reply)"}
+        </pre>
+      )}
     </div>
   );
 }
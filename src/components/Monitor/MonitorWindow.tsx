@@ -0,0 +1,92 @@
+import { useEffect, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import type { LineBatch, TerminalLine } from "../../types";
+
+// Lines kept on screen; older ones scroll away for good
+const MAX_LINES = 2000;
+
+interface MonitorLine {
+  timestamp: number;
+  direction: "tx" | "rx" | "upload";
+  text: string;
+}
+
+function formatTime(ms: number): string {
+  const d = new Date(ms);
+  return `${d.toLocaleTimeString()}.${d.getMilliseconds().toString().padStart(3, "0")}`;
+}
+
+// Drop each "?" query and the reply lines up to the next command sent
+function withoutStatusPolls(lines: MonitorLine[]): MonitorLine[] {
+  let inPoll = false;
+  return lines.filter((line) => {
+    if (line.direction === "tx") inPoll = line.text === "?";
+    return !inPoll || line.direction === "upload";
+  });
+}
+
+// Serial monitor shown in its own window, fed by the terminal and upload log events
+export function MonitorWindow() {
+  const [lines, setLines] = useState<MonitorLine[]>([]);
+  const [paused, setPaused] = useState(false);
+  const [hidePolls, setHidePolls] = useState(true);
+  const bottomRef = useRef<HTMLDivElement>(null);
+  const pausedRef = useRef(paused);
+  pausedRef.current = paused;
+
+  const append = (incoming: MonitorLine[]) => {
+    if (pausedRef.current) return;
+    setLines((prev) => [...prev, ...incoming].slice(-MAX_LINES));
+  };
+
+  useEffect(() => {
+    const unlistenTerminal = listen<TerminalLine>("serial://terminal", (event) => {
+      append([event.payload]);
+    });
+    const unlistenUpload = listen<LineBatch>("upload://device-log", (event) => {
+      const now = Date.now();
+      append(event.payload.lines.map((text) => ({ timestamp: now, direction: "upload" as const, text })));
+    });
+    return () => {
+      unlistenTerminal.then((fn) => fn());
+      unlistenUpload.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!paused) bottomRef.current?.scrollIntoView({ block: "end" });
+  }, [lines, paused]);
+
+  // The status query ("?") and its reply repeat every couple of seconds
+  const visible = hidePolls ? withoutStatusPolls(lines) : lines;
+
+  return (
+    <div className="h-screen flex flex-col bg-background text-foreground">
+      <header className="flex items-center gap-3 px-4 py-2 border-b border-border bg-card text-sm">
+        <span className="font-semibold">Serial Monitor</span>
+        <label className="flex items-center gap-1 ml-auto text-muted-foreground">
+          <input type="checkbox" checked={hidePolls} onChange={(e) => setHidePolls(e.target.checked)} />
+          Hide status polls
+        </label>
+        <button className="px-2 py-1 rounded border border-border hover:bg-muted" onClick={() => setPaused(!paused)}>
+          {paused ? "Resume" : "Pause"}
+        </button>
+        <button className="px-2 py-1 rounded border border-border hover:bg-muted" onClick={() => setLines([])}>
+          Clear
+        </button>
+      </header>
+      <div className="flex-1 overflow-auto font-mono text-xs p-2">
+        {visible.map((line, i) => (
+          <div key={i} className="whitespace-pre-wrap">
+            <span className="text-muted-foreground">{formatTime(line.timestamp)} </span>
+            <span className={line.direction === "tx" ? "text-blue-500" : line.direction === "upload" ? "text-amber-500" : "text-green-600"}>
+              {line.direction === "tx" ? "→ " : "← "}
+            </span>
+            {line.text}
+          </div>
+        ))}
+        <div ref={bottomRef} />
+      </div>
+    </div>
+  );
+}
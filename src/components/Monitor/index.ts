@@ -0,0 +1 @@
+export { MonitorWindow } from "./MonitorWindow";
@@ -1,5 +1,23 @@
 import { useEffect } from "react";
 import { useConnectionStore } from "../../store/connectionStore";
+import type { PortInfo } from "../../types";
+
+const PORT_KIND_LABELS: Record<PortInfo["port_type"], string> = {
+  usb: "USB",
+  bluetooth: "Bluetooth",
+  pci: "PCI",
+  unknown: "Unknown",
+};
+
+// e.g. "USB Silicon Labs CP2102 (10C4:EA60)"
+function describePort(port: PortInfo): string {
+  const hex = (id: number) => id.toString(16).toUpperCase().padStart(4, "0");
+  const parts = [PORT_KIND_LABELS[port.port_type], port.manufacturer, port.product];
+  if (port.vid !== null && port.pid !== null) {
+    parts.push(`(${hex(port.vid)}:${hex(port.pid)})`);
+  }
+  return parts.filter(Boolean).join(" ");
+}
 
 export function PortSelector() {
   const {
@@ -29,7 +47,8 @@ export function PortSelector() {
         <option value="">Select a port...</option>
         {ports.map((port) => (
           <option key={port.name} value={port.name}>
-            {port.name} - {port.port_type}
+            {port.name} - {describePort(port)}
+            {port.previously_used ? ' ★' : ''}
           </option>
         ))}
       </select>
@@ -1,8 +1,10 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import type { SignalInfo, DeviceSignalConfig, UploadResult, UploadDebugInfo } from '../../types';
+import { listen } from '@tauri-apps/api/event';
+import type { SignalInfo, DeviceSignalConfig, ImportOutcome, LinkImport, PendingUpload, QrChunk, QrImportProgress, ResumedUpload, UploadResult, UploadDebugInfo, CommandError } from '../../types';
 import { useConnectionStore } from '../../store/connectionStore';
 import { debugDecodeSig1Blob } from '../../utils/deviceCodec';
+import { runJob } from '../../utils/jobs';
 
 interface SignalLibraryProps {
   isConnected: boolean;
@@ -16,10 +18,59 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
   const [importText, setImportText] = useState('');
   const [showImport, setShowImport] = useState(false);
   const [uploadingSignal, setUploadingSignal] = useState<string | null>(null);
+  const [qrChunks, setQrChunks] = useState<QrChunk[] | null>(null);
+  const [qrIndex, setQrIndex] = useState(0);
+  const [qrProgress, setQrProgress] = useState<string | null>(null);
+  const [pendingUploads, setPendingUploads] = useState<PendingUpload[]>([]);
+  const [showArchive, setShowArchive] = useState(false);
+  const [archiveQuery, setArchiveQuery] = useState('');
+  const [archived, setArchived] = useState<SignalInfo[]>([]);
 
-  // Load signals on mount
+  // Load signals on mount, along with uploads the last shutdown interrupted
   useEffect(() => {
     loadSignals();
+    invoke<PendingUpload[]>('get_pending_jobs')
+      .then(setPendingUploads)
+      .catch(() => setPendingUploads([]));
+  }, []);
+
+  const resumePendingUploads = async () => {
+    try {
+      const resumed = await invoke<ResumedUpload[]>('resume_pending_jobs');
+      setPendingUploads([]);
+      const failed = resumed.filter((r) => r.error);
+      if (failed.length > 0) {
+        setError(failed.map((r) => `${r.label}: ${r.error}`).join('; '));
+      }
+    } catch (e) {
+      setError(`Failed to resume uploads: ${e}`);
+    }
+  };
+
+  const discardPendingUploads = async () => {
+    try {
+      await invoke<number>('discard_pending_jobs');
+      setPendingUploads([]);
+    } catch (e) {
+      setError(`Failed to discard uploads: ${e}`);
+    }
+  };
+
+  // esp32sig:// links opened from chat are imported by the backend
+  useEffect(() => {
+    const unlisten = listen<LinkImport>('library://link-import', (event) => {
+      const { outcome, error: linkError } = event.payload;
+      if (linkError) {
+        setError(`Link import failed: ${linkError}`);
+      } else if (outcome?.status === 'duplicate') {
+        setError(`Linked signal is identical to "${outcome.of_name}", not imported`);
+      } else {
+        loadSignals();
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   const loadSignals = async () => {
@@ -41,6 +92,42 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
     try {
       setLoading(true);
 
+      // Shared links carry the whole signal
+      if (importText.trim().startsWith('esp32sig://')) {
+        const link = importText.trim();
+        const outcome = await invoke<ImportOutcome>('import_signal_link', { link });
+        if (outcome.status === 'duplicate') {
+          if (!confirm(`This signal is identical to "${outcome.of_name}". Import it anyway?`)) {
+            return;
+          }
+          await invoke<ImportOutcome>('import_signal_link', { link, allowDuplicate: true });
+        }
+        setImportText('');
+        setShowImport(false);
+        await loadSignals();
+        setError(null);
+        return;
+      }
+
+      // One scanned code of a multi-part QR export
+      if (importText.trim().startsWith('E32Q:')) {
+        const progress = await invoke<QrImportProgress>('import_signal_from_qr', { payload: importText.trim() });
+        setImportText('');
+        if (!progress.outcome) {
+          setQrProgress(`Scanned ${progress.received} of ${progress.total} codes`);
+          return;
+        }
+        setQrProgress(null);
+        if (progress.outcome.status === 'duplicate') {
+          setError(`Scanned signal is identical to "${progress.outcome.of_name}", not imported`);
+          return;
+        }
+        setShowImport(false);
+        await loadSignals();
+        setError(null);
+        return;
+      }
+
       // Validate JSON
       const config = JSON.parse(importText) as DeviceSignalConfig;
       if (!config.name || !config.CKP) {
@@ -50,7 +137,13 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
         throw new Error('Invalid CKP: must start with SIG1');
       }
 
-      await invoke('import_signal', { json: importText });
+      const outcome = await invoke<ImportOutcome>('import_signal', { json: importText });
+      if (outcome.status === 'duplicate') {
+        if (!confirm(`This signal is identical to "${outcome.of_name}". Import it anyway?`)) {
+          return;
+        }
+        await invoke<ImportOutcome>('import_signal', { json: importText, allowDuplicate: true });
+      }
       setImportText('');
       setShowImport(false);
       await loadSignals();
@@ -73,6 +166,70 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
     }
   };
 
+  const loadArchived = async (query: string) => {
+    try {
+      setArchived(await invoke<SignalInfo[]>('list_archived_signals', { query: query || null }));
+    } catch (e) {
+      setError(`Failed to load archive: ${e}`);
+    }
+  };
+
+  const handleArchive = async (filename: string) => {
+    try {
+      await invoke('archive_signal', { filename });
+      await loadSignals();
+      if (showArchive) await loadArchived(archiveQuery);
+    } catch (e) {
+      setError(`Archive failed: ${e}`);
+    }
+  };
+
+  const handleRestore = async (filename: string) => {
+    try {
+      await invoke('restore_archived_signal', { filename });
+      await loadSignals();
+      await loadArchived(archiveQuery);
+    } catch (e) {
+      setError(`Restore failed: ${e}`);
+    }
+  };
+
+  const toggleArchive = () => {
+    if (!showArchive) loadArchived(archiveQuery);
+    setShowArchive(!showArchive);
+  };
+
+  const handleCopyLink = async (filename: string) => {
+    try {
+      const link = await invoke<string>('export_signal_link', { filename });
+      await navigator.clipboard.writeText(link);
+    } catch (e) {
+      setError(`Link failed: ${e}`);
+    }
+  };
+
+  const handleShowQr = async (filename: string) => {
+    try {
+      const chunks = await invoke<QrChunk[]>('export_signal_qr', { filename });
+      setQrIndex(0);
+      setQrChunks(chunks);
+    } catch (e) {
+      setError(`QR export failed: ${e}`);
+    }
+  };
+
+  const handleToggleDeviceSpecific = async (signal: SignalInfo) => {
+    try {
+      await invoke('set_signal_device_specific', {
+        filename: signal.filename,
+        deviceSpecific: !signal.device_specific,
+      });
+      await loadSignals();
+    } catch (e) {
+      setError(`Update failed: ${e}`);
+    }
+  };
+
   const handleUpload = async (filename: string) => {
     if (!isConnected) {
       setError('Not connected to device');
@@ -108,8 +265,16 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
         cmp2Decoded: config.CMP2 ? debugDecodeSig1Blob(config.CMP2) : null,
       };
 
-      // Now upload
-      const result = await invoke<UploadResult>('upload_saved_signal', { filename });
+      // Now upload; device-specific signals need confirmation before going to another unit
+      let result: UploadResult;
+      try {
+        result = await runJob<UploadResult>('upload_saved_signal', { filename });
+      } catch (e) {
+        const err = e as CommandError;
+        if (!err?.canBeOverridden) throw err?.message ?? e;
+        if (!confirm(`${err.message}\n\nUpload anyway?`)) return;
+        result = await runJob<UploadResult>('upload_saved_signal', { filename, overrideBinding: true });
+      }
       debugInfo.result = result;
 
       // Update the connection store with debug info
@@ -118,6 +283,7 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
       if (result.success) {
         setError(null);
         onStatusChange?.();
+        await loadSignals();
       } else {
         const errorMsg = result.error_message || 'Unknown error';
         setError(`Upload failed: ${errorMsg}`);
@@ -128,6 +294,7 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
       useConnectionStore.setState({ error: `Upload failed: ${e}` });
     } finally {
       setUploadingSignal(null);
+      useConnectionStore.setState({ uploadProgress: null });
     }
   };
 
@@ -143,6 +310,28 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
         </button>
       </div>
 
+      {pendingUploads.length > 0 && (
+        <div className="mb-2 p-2 bg-amber-500/10 border border-amber-500 rounded text-xs">
+          <p className="mb-1">
+            {pendingUploads.length} upload(s) were interrupted when the app last closed:{' '}
+            {pendingUploads.map((u) => u.label).join(', ')}
+          </p>
+          <div className="flex gap-2">
+            <button
+              onClick={resumePendingUploads}
+              disabled={!isConnected}
+              className="px-2 py-1 bg-primary text-primary-foreground rounded disabled:opacity-50"
+              title={isConnected ? undefined : 'Connect to the device first'}
+            >
+              Resume
+            </button>
+            <button onClick={discardPendingUploads} className="px-2 py-1 border border-border rounded">
+              Discard
+            </button>
+          </div>
+        </div>
+      )}
+
       {error && (
         <div className="mb-2 p-2 bg-destructive/20 border border-destructive rounded text-destructive text-xs">
           {error}
@@ -157,7 +346,7 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
           <textarea
             value={importText}
             onChange={(e) => setImportText(e.target.value)}
-            placeholder='{"name": "...", "CKP": "SIG1...", ...}'
+            placeholder='{"name": "...", "CKP": "SIG1...", ...} or esp32sig://...'
             className="w-full h-16 p-1.5 bg-background border border-border text-foreground rounded font-mono text-xs resize-none"
           />
           <button
@@ -167,6 +356,33 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
           >
             {loading ? 'Importing...' : 'Import Signal'}
           </button>
+          {qrProgress && <p className="mt-1 text-xs text-muted-foreground">{qrProgress}</p>}
+        </div>
+      )}
+
+      {qrChunks && qrChunks.length > 0 && (
+        <div className="mb-2 p-2 bg-white rounded flex flex-col items-center gap-1">
+          <div dangerouslySetInnerHTML={{ __html: qrChunks[qrIndex].svg }} />
+          <div className="flex items-center gap-2 text-xs text-gray-700">
+            <button
+              onClick={() => setQrIndex((i) => Math.max(0, i - 1))}
+              disabled={qrIndex === 0}
+              className="px-2 py-0.5 rounded bg-gray-200 disabled:opacity-50"
+            >
+              ‹
+            </button>
+            <span>Code {qrChunks[qrIndex].index} of {qrChunks[qrIndex].total}</span>
+            <button
+              onClick={() => setQrIndex((i) => Math.min(qrChunks.length - 1, i + 1))}
+              disabled={qrIndex === qrChunks.length - 1}
+              className="px-2 py-0.5 rounded bg-gray-200 disabled:opacity-50"
+            >
+              ›
+            </button>
+            <button onClick={() => setQrChunks(null)} className="px-2 py-0.5 rounded bg-gray-200">
+              Close
+            </button>
+          </div>
         </div>
       )}
 
@@ -190,6 +406,11 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
                     {signal.has_ckp && <span className="text-green-400">CKP </span>}
                     {signal.has_cmp1 && <span className="text-orange-400">CMP1 </span>}
                     {signal.has_cmp2 && <span className="text-purple-400">CMP2</span>}
+                    {signal.last_upload ? (
+                      <span> · on device since {new Date(signal.last_upload.timestamp).toLocaleDateString()}</span>
+                    ) : (
+                      <span className="text-yellow-400"> · never uploaded</span>
+                    )}
                   </p>
                 </div>
                 <div className="flex gap-1 shrink-0">
@@ -203,6 +424,34 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
                   >
                     {uploadingSignal === signal.filename ? '...' : 'Upload'}
                   </button>
+                  <button
+                    onClick={() => handleToggleDeviceSpecific(signal)}
+                    title={signal.bound_usb_serial ? `Calibrated for ${signal.bound_usb_serial}` : 'Bind to the unit it is uploaded to'}
+                    className="px-2 py-1 bg-secondary hover:bg-secondary/80 text-secondary-foreground rounded text-xs"
+                  >
+                    {signal.device_specific ? 'Pinned' : 'Pin'}
+                  </button>
+                  <button
+                    onClick={() => handleCopyLink(signal.filename)}
+                    title="Copy a shareable esp32sig:// link"
+                    className="px-2 py-1 bg-secondary hover:bg-secondary/80 text-secondary-foreground rounded text-xs"
+                  >
+                    Link
+                  </button>
+                  <button
+                    onClick={() => handleShowQr(signal.filename)}
+                    title="Show as QR codes for a tablet"
+                    className="px-2 py-1 bg-secondary hover:bg-secondary/80 text-secondary-foreground rounded text-xs"
+                  >
+                    QR
+                  </button>
+                  <button
+                    onClick={() => handleArchive(signal.filename)}
+                    title="Move to the archive; it stays searchable and can be restored"
+                    className="px-2 py-1 bg-secondary hover:bg-secondary/80 text-secondary-foreground rounded text-xs"
+                  >
+                    Archive
+                  </button>
                   <button
                     onClick={() => handleDelete(signal.filename)}
                     className="px-2 py-1 bg-destructive hover:bg-destructive/90 text-destructive-foreground rounded text-xs"
@@ -216,12 +465,49 @@ export function SignalLibrary({ isConnected, onStatusChange }: SignalLibraryProp
         )}
       </div>
 
-      <button
-        onClick={loadSignals}
-        className="mt-2 text-xs text-muted-foreground hover:text-foreground shrink-0"
-      >
-        ↻ Refresh
-      </button>
+      {showArchive && (
+        <div className="mt-2 p-2 bg-muted rounded shrink-0 max-h-48 overflow-y-auto">
+          <input
+            value={archiveQuery}
+            onChange={(e) => {
+              setArchiveQuery(e.target.value);
+              loadArchived(e.target.value);
+            }}
+            placeholder="Search archived signals"
+            className="w-full mb-1.5 p-1 bg-background border border-border text-foreground rounded text-xs"
+          />
+          {archived.length === 0 ? (
+            <p className="text-xs text-muted-foreground">No archived signals</p>
+          ) : (
+            archived.map((signal) => (
+              <div key={signal.filename} className="flex items-center justify-between gap-2 py-0.5 text-xs">
+                <span className="truncate text-foreground">{signal.name}</span>
+                <button
+                  onClick={() => handleRestore(signal.filename)}
+                  className="px-2 py-0.5 border border-border rounded shrink-0"
+                >
+                  Restore
+                </button>
+              </div>
+            ))
+          )}
+        </div>
+      )}
+
+      <div className="mt-2 flex justify-between shrink-0">
+        <button
+          onClick={loadSignals}
+          className="text-xs text-muted-foreground hover:text-foreground"
+        >
+          ↻ Refresh
+        </button>
+        <button
+          onClick={toggleArchive}
+          className="text-xs text-muted-foreground hover:text-foreground"
+        >
+          {showArchive ? 'Hide archive' : 'Archive…'}
+        </button>
+      </div>
     </div>
   );
 }
@@ -2,6 +2,16 @@ import { useConnectionStore } from "../../store/connectionStore";
 import type { DecodedBlobInfo, UploadDebugInfo } from "../../types";
 import { useState } from "react";
 
+// Format device uptime as "1h 02m 03s"
+function formatUptime(ms: number): string {
+  const total = Math.floor(ms / 1000);
+  const h = Math.floor(total / 3600);
+  const m = Math.floor((total % 3600) / 60);
+  const s = total % 60;
+  const pad = (n: number) => n.toString().padStart(2, "0");
+  return h > 0 ? `${h}h ${pad(m)}m ${pad(s)}s` : `${m}m ${pad(s)}s`;
+}
+
 // Generate a full copyable debug report
 function generateDebugReport(debug: UploadDebugInfo): string {
   const lines: string[] = [];
@@ -22,6 +32,13 @@ function generateDebugReport(debug: UploadDebugInfo): string {
     lines.push(`Success: ${debug.result.success}`);
     lines.push(`Bytes Sent: ${debug.result.bytes_sent}`);
     lines.push(`Chunks Sent: ${debug.result.chunks_sent}`);
+    lines.push(`Protocol: v${debug.result.protocol_version}, checksum ${debug.result.checksum}`);
+    if (debug.result.compressed_bytes != null) {
+      lines.push(`Compressed: ${debug.result.compressed_bytes} bytes on the wire`);
+    }
+    if (debug.result.resumed_from != null) {
+      lines.push(`Resumed from byte ${debug.result.resumed_from}`);
+    }
     if (debug.result.error_message) {
       lines.push(`Error: ${debug.result.error_message}`);
     }
@@ -169,7 +186,7 @@ function DecodedBlobPanel({ name, decoded, color }: { name: string; decoded: Dec
 }
 
 export function StatusDisplay() {
-  const { status, error, clearError, lastUploadDebug, clearUploadDebug } = useConnectionStore();
+  const { status, error, clearError, notice, clearNotice, lastUploadDebug, clearUploadDebug } = useConnectionStore();
 
   return (
     <div className="p-3 bg-card border border-border rounded-lg h-full flex flex-col">
@@ -187,6 +204,18 @@ export function StatusDisplay() {
         </div>
       )}
 
+      {notice && (
+        <div className="mb-2 p-2 bg-orange-900/30 border border-orange-600 rounded-md flex items-start justify-between gap-2">
+          <span className="text-orange-300 text-xs flex-1">{notice}</span>
+          <button
+            onClick={clearNotice}
+            className="text-orange-300 hover:text-orange-200 text-sm shrink-0"
+          >
+            ×
+          </button>
+        </div>
+      )}
+
       <div className="space-y-2 flex-1">
         <div className="flex items-center gap-2">
           <div
@@ -211,6 +240,33 @@ export function StatusDisplay() {
                 {status.running ? "Running" : "Stopped"}
               </span>
             </div>
+
+            {status.firmware_version && (
+              <div className="text-muted-foreground text-xs">
+                Firmware:{" "}
+                <span className={`font-mono ${status.firmware_warning ? "text-orange-400" : "text-foreground"}`}>
+                  {status.firmware_version}
+                  {status.firmware_build && ` (${status.firmware_build})`}
+                </span>
+              </div>
+            )}
+
+            {status.firmware_warning && (
+              <div className="text-orange-400 text-xs">{status.firmware_warning}</div>
+            )}
+
+            {status.uptime_ms !== null && (
+              <div className="text-muted-foreground text-xs">
+                Uptime: <span className="text-foreground font-mono">{formatUptime(status.uptime_ms)}</span>
+              </div>
+            )}
+
+            <div className="text-muted-foreground text-xs">
+              Resets:{" "}
+              <span className={`font-mono ${status.reset_count > 0 ? "text-orange-400" : "text-foreground"}`}>
+                {status.reset_count}
+              </span>
+            </div>
           </>
         )}
       </div>
@@ -252,6 +308,9 @@ export function StatusDisplay() {
               {lastUploadDebug.result && (
                 <div><span className="text-gray-500">Sent:</span> {lastUploadDebug.result.bytes_sent} bytes / {lastUploadDebug.result.chunks_sent} chunks</div>
               )}
+              {lastUploadDebug.result && (
+                <div><span className="text-gray-500">Checksum:</span> {lastUploadDebug.result.checksum}</div>
+              )}
             </div>
 
             <CopyDebugButton debug={lastUploadDebug} />
@@ -1,13 +1,17 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import { MonitorWindow } from "./components/Monitor";
 import { TooltipProvider } from "./components/ui/tooltip";
 import "./index.css";
 
+// The serial monitor window loads the same bundle with ?view=monitor
+const isMonitor = new URLSearchParams(window.location.search).get("view") === "monitor";
+
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
     <TooltipProvider>
-      <App />
+      {isMonitor ? <MonitorWindow /> : <App />}
     </TooltipProvider>
   </React.StrictMode>,
 );
@@ -1,27 +1,42 @@
 import { create } from "zustand";
 import { invoke } from "@tauri-apps/api/core";
-import type { DeviceSignalConfig, DeviceStatus, FullConfig, PortInfo, UploadDebugInfo, UploadResult } from "../types";
+import type { AppSnapshot, ClaimStatus, CommandError, CommandOutcome, DeviceBackup, DeviceSignalConfig, DeviceStatus, FullConfig, ImportOutcome, PortInfo, SerialParams, UploadDebugInfo, UploadProgress, UploadResult } from "../types";
 import { prepareConfigForUpload, debugDecodeSig1Blob } from "../utils/deviceCodec";
+import { runJob } from "../utils/jobs";
+import { DEFAULT_CSV_OPTIONS, type CsvOptions } from "../utils/edgeCsv";
 
 interface ConnectionState {
   // Connection state
   ports: PortInfo[];
   selectedPort: string | null;
+  // Line settings for the next connect; null uses the firmware defaults
+  serialParams: Partial<SerialParams> | null;
   status: DeviceStatus;
   isConnecting: boolean;
   isCommandBusy: boolean;
+  // Reason of the backend critical section in progress (upload, flash, NVS write)
+  deviceBusy: string | null;
   error: string | null;
+  // Warning that must survive status refreshes (device reset, foreign claim); stays until dismissed
+  notice: string | null;
 
   // Config (either legacy full config or device config)
   loadedConfig: FullConfig | DeviceSignalConfig | null;
   configJson: string;
+  // How pasted edge-list CSV is read
+  csvOptions: CsvOptions;
 
   // Upload debug info
   lastUploadDebug: UploadDebugInfo | null;
+  // Send progress of the upload in flight, from upload://progress
+  uploadProgress: UploadProgress | null;
+  // Config saved from the device before the last reset to defaults
+  lastBackup: DeviceBackup | null;
 
   // Actions
   refreshPorts: () => Promise<void>;
   selectPort: (port: string | null) => void;
+  setSerialParams: (params: Partial<SerialParams> | null) => void;
   connect: () => Promise<void>;
   disconnect: () => Promise<void>;
   runSignal: () => Promise<void>;
@@ -30,12 +45,18 @@ interface ConnectionState {
   decreaseRpm: () => Promise<void>;
   saveToNvs: () => Promise<void>;
   resetDefaults: () => Promise<void>;
+  loadLastBackup: () => Promise<void>;
+  restoreLastBackup: () => Promise<void>;
+  sendCustomCommand: (text: string) => Promise<string | null>;
   refreshStatus: () => Promise<void>;
+  restoreSession: () => Promise<void>;
   uploadConfig: (config?: DeviceSignalConfig) => Promise<void>;
   saveSignal: (config: DeviceSignalConfig) => Promise<void>;
   setConfigJson: (json: string) => void;
+  setCsvOptions: (options: CsvOptions) => void;
   parseConfig: () => void;
   clearError: () => void;
+  clearNotice: () => void;
   clearUploadDebug: () => void;
 }
 
@@ -44,6 +65,15 @@ const defaultStatus: DeviceStatus = {
   port_name: null,
   running: false,
   rpm: 0,
+  fault: null,
+  device_id: null,
+  firmware_version: null,
+  firmware_build: null,
+  firmware_warning: null,
+  loaded_signal: null,
+  uptime_ms: null,
+  reset_count: 0,
+  reset_detected: false,
   raw_response: "",
 };
 
@@ -58,17 +88,35 @@ function parseRunningFromResponse(response: string): boolean | null {
   return null;
 }
 
+// Why a command the device answered didn't go through, or null if it did
+function outcomeProblem(outcome: CommandOutcome): string | null {
+  switch (outcome.status) {
+    case "rejected":
+      return `device refused it${outcome.matched ? ` (${outcome.matched})` : ""}`;
+    case "no_confirmation":
+      return `no confirmation within ${outcome.elapsed_ms} ms`;
+    default:
+      return null;
+  }
+}
+
 export const useConnectionStore = create<ConnectionState>((set, get) => ({
   ports: [],
   selectedPort: null,
+  serialParams: null,
   status: defaultStatus,
   isConnecting: false,
   isCommandBusy: false,
+  deviceBusy: null,
   error: null,
+  notice: null,
 
   loadedConfig: null,
   configJson: "",
+  csvOptions: DEFAULT_CSV_OPTIONS,
   lastUploadDebug: null,
+  uploadProgress: null,
+  lastBackup: null,
 
   refreshPorts: async () => {
     try {
@@ -83,8 +131,12 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
     set({ selectedPort: port });
   },
 
+  setSerialParams: (params) => {
+    set({ serialParams: params });
+  },
+
   connect: async () => {
-    const { selectedPort } = get();
+    const { selectedPort, serialParams } = get();
     if (!selectedPort) {
       set({ error: "No port selected" });
       return;
@@ -92,11 +144,16 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
 
     set({ isConnecting: true, error: null });
     try {
-      await invoke("connect", { port: selectedPort });
+      const claim = await invoke<ClaimStatus>("connect", { port: selectedPort, params: serialParams });
       await get().refreshStatus();
       set({ isConnecting: false });
+      if (claim.status === "foreign") {
+        set({ notice: `This device is claimed by ${claim.owner_name || claim.owner_id}; its configuration may be someone else's` });
+      }
     } catch (e) {
-      set({ isConnecting: false, error: `Connection failed: ${e}` });
+      const err = e as CommandError;
+      const message = err?.message ?? String(e);
+      set({ isConnecting: false, error: `Connection failed: ${message}` });
     }
   },
 
@@ -113,11 +170,27 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
     if (get().isCommandBusy) return;
     set({ isCommandBusy: true });
     try {
-      const response = await invoke<string>("run_signal");
-      const running = parseRunningFromResponse(response);
+      let outcome: CommandOutcome;
+      try {
+        outcome = await invoke<CommandOutcome>("run_signal");
+      } catch (e) {
+        // The only interlock the operator can clear from here is the confirmation
+        const err = e as CommandError;
+        if (err?.code !== "interlock" || !err.canBeOverridden || !confirm("Start the signal?")) {
+          throw e;
+        }
+        outcome = await invoke<CommandOutcome>("run_signal", { confirmed: true });
+      }
+      const problem = outcomeProblem(outcome);
+      if (problem) {
+        set({ error: `Run failed: ${problem}` });
+        return;
+      }
+      const running = parseRunningFromResponse(outcome.response);
       set((state) => ({ status: { ...state.status, running: running ?? true } }));
     } catch (e) {
-      set({ error: `Run failed: ${e}` });
+      const err = e as CommandError;
+      set({ error: `Run failed: ${err?.message ?? String(e)}` });
     } finally {
       set({ isCommandBusy: false });
     }
@@ -127,8 +200,13 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
     if (get().isCommandBusy) return;
     set({ isCommandBusy: true });
     try {
-      const response = await invoke<string>("stop_signal");
-      const running = parseRunningFromResponse(response);
+      const outcome = await invoke<CommandOutcome>("stop_signal");
+      const problem = outcomeProblem(outcome);
+      if (problem) {
+        set({ error: `Stop failed: ${problem}` });
+        return;
+      }
+      const running = parseRunningFromResponse(outcome.response);
       set((state) => ({ status: { ...state.status, running: running ?? false } }));
     } catch (e) {
       set({ error: `Stop failed: ${e}` });
@@ -141,8 +219,13 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
     if (get().isCommandBusy) return;
     set({ isCommandBusy: true });
     try {
-      const response = await invoke<string>("increase_rpm");
-      const rpm = parseRpmFromResponse(response);
+      const outcome = await invoke<CommandOutcome>("increase_rpm");
+      const problem = outcomeProblem(outcome);
+      if (problem) {
+        set({ error: `Increase RPM failed: ${problem}` });
+        return;
+      }
+      const rpm = parseRpmFromResponse(outcome.response);
       if (rpm !== null) set((state) => ({ status: { ...state.status, rpm } }));
     } catch (e) {
       set({ error: `Increase RPM failed: ${e}` });
@@ -155,8 +238,13 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
     if (get().isCommandBusy) return;
     set({ isCommandBusy: true });
     try {
-      const response = await invoke<string>("decrease_rpm");
-      const rpm = parseRpmFromResponse(response);
+      const outcome = await invoke<CommandOutcome>("decrease_rpm");
+      const problem = outcomeProblem(outcome);
+      if (problem) {
+        set({ error: `Decrease RPM failed: ${problem}` });
+        return;
+      }
+      const rpm = parseRpmFromResponse(outcome.response);
       if (rpm !== null) set((state) => ({ status: { ...state.status, rpm } }));
     } catch (e) {
       set({ error: `Decrease RPM failed: ${e}` });
@@ -167,8 +255,9 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
 
   saveToNvs: async () => {
     try {
-      await invoke("save_to_nvs");
-      set({ error: null });
+      const outcome = await invoke<CommandOutcome>("save_to_nvs");
+      const problem = outcomeProblem(outcome);
+      set({ error: problem ? `Save to NVS failed: ${problem}` : null });
     } catch (e) {
       set({ error: `Save to NVS failed: ${e}` });
     }
@@ -176,11 +265,49 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
 
   resetDefaults: async () => {
     try {
-      await invoke("reset_defaults");
-      await get().refreshStatus();
+      const outcome = await invoke<CommandOutcome>("reset_defaults");
+      const problem = outcomeProblem(outcome);
+      set({ error: problem ? `Reset defaults failed: ${problem}` : null });
     } catch (e) {
       set({ error: `Reset defaults failed: ${e}` });
     }
+    await get().loadLastBackup();
+  },
+
+  loadLastBackup: async () => {
+    try {
+      const lastBackup = await invoke<DeviceBackup | null>("get_last_device_backup");
+      set({ lastBackup });
+    } catch (e) {
+      console.error("Failed to load device backup:", e);
+    }
+  },
+
+  restoreLastBackup: async () => {
+    if (get().isCommandBusy) return;
+    set({ isCommandBusy: true });
+    try {
+      const result = await runJob<UploadResult>("restore_last_device_backup");
+      set({ error: result.success ? null : `Restore failed: ${result.error_message ?? "device rejected the config"}` });
+      await get().refreshStatus();
+    } catch (e) {
+      const err = e as CommandError;
+      set({ error: `Restore failed: ${err?.message ?? String(e)}` });
+    } finally {
+      set({ isCommandBusy: false });
+    }
+  },
+
+  sendCustomCommand: async (text: string) => {
+    try {
+      const outcome = await invoke<CommandOutcome>("send_custom_command", { text });
+      const problem = outcomeProblem(outcome);
+      set({ error: problem ? `Custom command failed: ${problem}` : null });
+      return outcome.response;
+    } catch (e) {
+      set({ error: `Custom command failed: ${e}` });
+      return null;
+    }
   },
 
   refreshStatus: async () => {
@@ -192,6 +319,23 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
     }
   },
 
+  restoreSession: async () => {
+    try {
+      const snapshot = await invoke<AppSnapshot>("get_app_snapshot");
+      set({ deviceBusy: snapshot.busy });
+      if (snapshot.connection?.connected) {
+        set({ selectedPort: snapshot.connection.port_name });
+        if (!snapshot.busy) {
+          await get().refreshStatus();
+        } else {
+          set({ status: { ...get().status, connected: true } });
+        }
+      }
+    } catch (e) {
+      set({ error: `Failed to restore session: ${e}` });
+    }
+  },
+
   uploadConfig: async (directConfig?: DeviceSignalConfig) => {
     if (get().isCommandBusy) return;
 
@@ -208,7 +352,7 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
       configToUpload = configJson;
     }
 
-    set({ isCommandBusy: true, lastUploadDebug: null });
+    set({ isCommandBusy: true, lastUploadDebug: null, uploadProgress: null });
 
     // Prepare debug info
     let debugInfo: UploadDebugInfo = {
@@ -230,7 +374,7 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
     };
 
     try {
-      const { jsonToSend, device } = prepareConfigForUpload(configToUpload);
+      const { jsonToSend, device } = prepareConfigForUpload(configToUpload, get().csvOptions);
 
       // Extract info for debugging
       debugInfo.signalName = device.name;
@@ -248,7 +392,7 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
       debugInfo.cmp1Decoded = device.CMP1 ? debugDecodeSig1Blob(device.CMP1) : null;
       debugInfo.cmp2Decoded = device.CMP2 ? debugDecodeSig1Blob(device.CMP2) : null;
 
-      const result = await invoke<UploadResult>("upload_config", { config: jsonToSend });
+      const result = await runJob<UploadResult>("upload_config", { config: jsonToSend });
       debugInfo.result = result;
 
       if (result.success) {
@@ -270,14 +414,14 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
         lastUploadDebug: debugInfo
       });
     } finally {
-      set({ isCommandBusy: false });
+      set({ isCommandBusy: false, uploadProgress: null });
     }
   },
 
   saveSignal: async (config: DeviceSignalConfig) => {
     try {
       const json = JSON.stringify(config);
-      await invoke<string>("import_signal", { json });
+      await invoke<ImportOutcome>("import_signal", { json, allowDuplicate: true });
     } catch (e) {
       throw new Error(`Failed to save signal: ${e}`);
     }
@@ -287,10 +431,14 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
     set({ configJson: json });
   },
 
+  setCsvOptions: (options) => {
+    set({ csvOptions: options });
+  },
+
   parseConfig: () => {
-    const { configJson } = get();
+    const { configJson, csvOptions } = get();
     try {
-      const { device } = prepareConfigForUpload(configJson);
+      const { device } = prepareConfigForUpload(configJson, csvOptions);
 
       // Keep legacy in the preview UI when pasted
       let legacy: FullConfig | null = null;
@@ -309,7 +457,7 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
 
       set({ loadedConfig: legacy ?? device, error: null });
     } catch (e) {
-      set({ error: `Invalid JSON: ${e}`, loadedConfig: null });
+      set({ error: `Invalid config: ${e}`, loadedConfig: null });
     }
   },
 
@@ -317,6 +465,10 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
     set({ error: null });
   },
 
+  clearNotice: () => {
+    set({ notice: null });
+  },
+
   clearUploadDebug: () => {
     set({ lastUploadDebug: null });
   },
@@ -1,6 +1,18 @@
+export type PortKind = "usb" | "bluetooth" | "pci" | "unknown";
+
 export interface PortInfo {
   name: string;
-  port_type: string;
+  port_type: PortKind;
+  // USB vendor/product ID, null for non-USB ports
+  vid: number | null;
+  pid: number | null;
+  // USB serial number, stable across reboots unlike the port name
+  serial_number: string | null;
+  // USB descriptor strings, when the device reports them
+  manufacturer: string | null;
+  product: string | null;
+  previously_used: boolean;
+  last_connected: number | null;
 }
 
 export interface DeviceStatus {
@@ -8,9 +20,32 @@ export interface DeviceStatus {
   port_name: string | null;
   running: boolean;
   rpm: number;
+  fault: string | null;
+  device_id: string | null;
+  firmware_version: string | null;
+  firmware_build: string | null;
+  // Set when the firmware is older than the app's protocol expects
+  firmware_warning: string | null;
+  loaded_signal: string | null;
+  uptime_ms: number | null;
+  reset_count: number;
+  reset_detected: boolean;
   raw_response: string;
 }
 
+// Reply of get_rpm_fast, for high-rate gauges
+export interface RpmReading {
+  rpm: number;
+  running: boolean;
+}
+
+// Device ownership reported by connect
+export type ClaimStatus =
+  | { status: 'claimed' }
+  | { status: 'ours' }
+  | { status: 'foreign'; owner_id: string; owner_name: string }
+  | { status: 'unsupported' };
+
 // Legacy edge format (for old config uploader)
 export interface SignalEdge {
   angle: number;
@@ -46,6 +81,396 @@ export interface SignalInfo {
   has_ckp: boolean;
   has_cmp1: boolean;
   has_cmp2: boolean;
+  last_upload: LastUpload | null;
+  device_specific: boolean;
+  bound_usb_serial: string | null;
+}
+
+// Named signal stored on the device (NVS slot / SPIFFS)
+export interface DeviceSignal {
+  name: string;
+  size: number;
+  active: boolean;
+  crc32: number | null;
+}
+
+// Library signal paired with a device slot by reconcile_device_signals
+export interface SyncPair {
+  local: SignalInfo;
+  device: DeviceSignal;
+  matched_by: "name" | "hash";
+  same_content: boolean | null;
+}
+
+export interface SyncReport {
+  paired: SyncPair[];
+  local_only: SignalInfo[];
+  device_only: DeviceSignal[];
+}
+
+// Last successful upload of a library signal
+export interface LastUpload {
+  timestamp: number;
+  port_name: string | null;
+  usb_serial: string | null;
+  device_id: string | null;
+  firmware_version: string | null;
+}
+
+// Structured error returned by commands that can be overridden by the user
+export interface CommandError {
+  code: string;
+  message: string;
+  canBeOverridden: boolean;
+  // Bytes received from the port (not_our_device)
+  rawBytes?: number[];
+}
+
+// Command sent to the firmware (serialized DeviceCommand)
+export type DeviceCommand =
+  | { type: "run" | "stop" | "rpm_up" | "rpm_down" | "save_nvs" | "reset_defaults" | "status" }
+  | { type: "custom"; text: string }
+  | { type: "extension"; text: ExtensionCall }
+  | { type: "debug"; text: ExtensionCall };
+
+// Extension command with its parameters filled in
+export interface ExtensionCall {
+  name: string;
+  line: string;
+  expect: string[];
+  reject: string[];
+  timeout_ms: number;
+}
+
+// Parameter of an extension command, substituted for {name} in its send line
+export interface ExtensionParam {
+  name: string;
+  kind: "integer" | "text";
+  min: number | null;
+  max: number | null;
+  choices: string[];
+  default: number | string | null;
+}
+
+// Device command declared in a descriptor file in the extensions folder
+export interface ExtensionCommand {
+  name: string;
+  description: string;
+  send: string;
+  expect: string[];
+  reject: string[];
+  timeout_ms: number;
+  params: ExtensionParam[];
+  source: string;
+}
+
+// Result of list_extension_commands / reload_extensions
+export interface ExtensionList {
+  commands: ExtensionCommand[];
+  errors: string[];
+}
+
+// Firmware diagnostic from the protocol profile (list_debug_commands, developer mode only)
+export interface DebugCommand extends ExtensionCommand {
+  // Restarts or hangs the device; run_debug_command needs confirm: true
+  disruptive: boolean;
+}
+
+// One line of the debug audit trail (get_debug_audit)
+export interface DebugAuditEntry {
+  timestamp: number;
+  command: string;
+  line: string;
+  port_name: string | null;
+  device_id: string | null;
+  accepted: boolean;
+  result: string;
+}
+
+export type OutcomeStatus = "confirmed" | "rejected" | "no_confirmation" | "unchecked";
+
+// Device command result, checked against the reply the firmware should give
+export interface CommandOutcome {
+  command: DeviceCommand;
+  status: OutcomeStatus;
+  // Reply line that decided the status
+  matched: string | null;
+  response: string;
+  elapsed_ms: number;
+}
+
+// Result of reset_device: what the board printed after the DTR/RTS reset
+export interface BootReport {
+  bootloader: boolean;
+  // Reset cause from the ROM banner, e.g. "POWERON_RESET"
+  reset_reason: string | null;
+  // The ROM says it is waiting in download mode
+  download_mode: boolean;
+  banner: string[];
+}
+
+// Device config read back before a destructive operation
+export interface DeviceBackup {
+  taken_at: number;
+  reason: string;
+  port_name: string | null;
+  usb_serial: string | null;
+  device_id: string | null;
+  config: string;
+}
+
+// Payload of the device://critical-section event
+export interface CriticalSectionChange {
+  active: boolean;
+  reason: string | null;
+}
+
+// Result of importing a signal into the library
+export type ImportOutcome =
+  | { status: 'imported'; filename: string }
+  | { status: 'duplicate'; of_filename: string; of_name: string };
+
+// Result of opening an esp32sig:// link from outside the app
+export interface LinkImport {
+  outcome: ImportOutcome | null;
+  error: string | null;
+}
+
+// One code of a multi-part QR export
+export interface QrChunk {
+  index: number;
+  total: number;
+  svg: string;
+}
+
+// Progress of a QR import; outcome is set once the last code was scanned
+export interface QrImportProgress {
+  received: number;
+  total: number;
+  outcome: ImportOutcome | null;
+}
+
+// Board and firmware build on the other end of the port
+export interface DeviceIdentity {
+  usb_serial: string | null;
+  device_id: string | null;
+  firmware_version: string | null;
+  firmware_build: string | null;
+}
+
+// Events that trigger webhooks and attention cues
+export type HookEvent =
+  | "device_fault"
+  | "upload_failed"
+  | "signal_stopped"
+  | "device_reset"
+  | "connection_lost"
+  | "upload_completed";
+
+export type Severity = "info" | "warning" | "critical";
+
+// Payload of the app://attention event: cues to play for an event
+export interface Attention {
+  event: HookEvent;
+  severity: Severity;
+  message: string;
+  sound: boolean;
+  flash: boolean;
+  notify: boolean;
+}
+
+// Connected device listed by list_devices; id is the port name
+export interface DeviceSlot {
+  id: string;
+  identity: DeviceIdentity;
+  active: boolean;
+  // Standby only: the last heartbeat got an answer
+  healthy: boolean;
+  last_heartbeat: number | null;
+}
+
+// Port state as seen by get_app_snapshot
+export interface ConnectionSnapshot {
+  connected: boolean;
+  port_name: string | null;
+  identity: DeviceIdentity;
+  config_uploaded: boolean;
+}
+
+// Backend state in one payload, for restoring the UI after a reload
+export interface AppSnapshot {
+  // null while an upload or flash holds the port; busy says which
+  connection: ConnectionSnapshot | null;
+  busy: string | null;
+  settings: Record<string, unknown>;
+  settings_revision: number;
+  library: {
+    signal_count: number;
+    last_scan: Record<string, unknown> | null;
+  };
+  soak: Record<string, unknown> | null;
+  jobs: Job[];
+  recent_events: Record<string, unknown>[];
+}
+
+// Payload of settings://changed: only the keys that changed, with their new values
+export interface SettingsChange {
+  revision: number;
+  changed: Record<string, unknown>;
+}
+
+// Payload of connection://progress: the step a connect attempt has reached
+export interface ConnectProgress {
+  port: string;
+  phase: "opening" | "settling" | "handshaking";
+  elapsed_ms: number;
+}
+
+// Payload of connection://lost: the port stopped working
+export interface ConnectionLost {
+  port: string;
+  reason: string;
+  // More reconnect attempts follow
+  reconnecting: boolean;
+}
+
+// Payload of connection://restored: a lost connection was opened again
+export interface ConnectionRestored {
+  port: string;
+  attempt: number;
+}
+
+// Line settings for connect; omitted fields keep the 115200 8N1 defaults
+export interface SerialParams {
+  baud_rate: number;
+  data_bits: 5 | 6 | 7 | 8;
+  parity: "none" | "odd" | "even";
+  stop_bits: 1 | 2;
+  flow_control: "none" | "software" | "hardware";
+  // Keep DTR/RTS deasserted so opening the port doesn't reset the board
+  suppress_reset: boolean;
+}
+
+// Payload of serial://rx: device output outside any command's reply
+export interface RxLine {
+  timestamp: number;
+  text: string;
+}
+
+// Payload of serial://terminal: one line of command traffic
+export interface TerminalLine {
+  timestamp: number;
+  direction: "tx" | "rx";
+  text: string;
+}
+
+// Payload of batched line events such as upload://device-log
+export interface LineBatch {
+  lines: string[];
+  dropped: number;
+}
+
+// Long operation (upload, flash, file transfer) as sent in job://progress and by get_job
+export interface Job {
+  id: number;
+  kind: 'config_upload' | 'device_file_upload' | 'device_file_download' | 'firmware' | 'step_test';
+  label: string;
+  state: 'running' | 'succeeded' | 'failed' | 'cancelled';
+  done: number;
+  total: number;
+  message: string | null;
+  started_at: number;
+  finished_at: number | null;
+  result: unknown;
+  error: string | null;
+}
+
+// Result of a flash_firmware job; progress arrives as percent with the phase as message
+export interface FlashResult {
+  port: string;
+  chip: string;
+  address: number;
+  bytes_written: number;
+  duration_ms: number;
+}
+
+// Result of an ota_update job; progress arrives with the phase as message, ending in
+// "unverified" when the image check after reboot didn't confirm the new image
+export interface OtaResult {
+  success: boolean;
+  bytes_sent: number;
+  md5: string;
+  image_check: 'running' | 'rolled_back' | 'unverified';
+  message: string;
+  // Set when a rollback could go unnoticed; show it next to the result
+  warning: string | null;
+}
+
+// Chunking of a config upload (upload_config's chunk_size / chunk_delay_ms)
+export interface ChunkPacing {
+  chunk_size: number;
+  // Unused from framing v3 on, where the device paces the upload
+  chunk_delay_ms: number;
+}
+
+// Upload left over from the previous run (get_pending_jobs)
+export interface PendingUpload {
+  key: string;
+  label: string;
+  queued_at: number;
+  note: string | null;
+  request:
+    | { source: 'config'; config: string; pacing: ChunkPacing }
+    | { source: 'library'; filename: string; override_binding: boolean };
+}
+
+// Outcome of resuming one interrupted upload
+export interface ResumedUpload {
+  label: string;
+  job_id: number | null;
+  error: string | null;
+}
+
+// Size of one channel blob in a config
+export interface ChannelSize {
+  channel: string;
+  bytes: number;
+  edges: number | null;
+}
+
+// Summary of a config about to be (or just) uploaded
+export interface ConfigPreview {
+  name: string | null;
+  pretty_json: string;
+  truncated: boolean;
+  channels: ChannelSize[];
+  payload_bytes: number;
+  frame_bytes: number;
+}
+
+// Result of preflight_upload
+export interface PreflightReport {
+  preview: ConfigPreview;
+  problems: string[];
+  // null while disconnected
+  estimate: UploadEstimate | null;
+}
+
+// Expected upload duration; measured = from earlier uploads on this port, else from the baud rate
+export interface UploadEstimate {
+  frame_bytes: number;
+  eta_ms: number;
+  measured: boolean;
+  samples: number;
+}
+
+// Sent in upload://progress while a config frame goes out
+export interface UploadProgress {
+  stage: "preparing" | "sending" | "awaiting_ack";
+  bytes_sent: number;
+  chunks_sent: number;
+  total_bytes: number;
+  percent: number;
 }
 
 // Upload result from ESP32
@@ -54,8 +479,25 @@ export interface UploadResult {
   bytes_sent: number;
   chunks_sent: number;
   raw_response: string;
-  config_preview: string;
+  config_preview: ConfigPreview;
   error_message: string | null;
+  // Stop/upload/restart steps, present when the running-signal policy applied
+  phases?: UploadPhase[];
+  // Config framing agreed with the firmware (1 = legacy <CFG> frame)
+  protocol_version: number;
+  // Whether the firmware verified the CRC32 sent with the frame (v2 framing only)
+  checksum: 'unchecked' | 'verified' | 'mismatch';
+  // Payload offset an interrupted upload continued from (v4 framing only)
+  resumed_from: number | null;
+  // Bytes on the wire when the config went out deflated
+  compressed_bytes: number | null;
+}
+
+// One step of an upload to a device that may be running the signal
+export interface UploadPhase {
+  phase: "check_running" | "stop" | "upload" | "restart";
+  ok: boolean;
+  detail: string | null;
 }
 
 // Debug info for last upload attempt
@@ -169,3 +611,116 @@ export type CKPMode = 'preset' | 'custom';
 // 'sine' - Inductive sensor display (simulated sine wave with peaks at tooth edges)
 export type WaveformDisplayType = 'square' | 'sine';
 
+
+// RPM statistics over a window of status samples
+export interface RpmStats {
+  window_secs: number;
+  samples: number;
+  mean: number | null;
+  stddev: number | null;
+  min: number | null;
+  max: number | null;
+  reset_count: number;
+}
+
+// Distribution of one reply time, in ms
+export interface TimingHistogram {
+  // Samples per bucket of TimingStats.bucket_bounds_ms, plus one for slower replies
+  counts: number[];
+  samples: number;
+  min_ms: number;
+  max_ms: number;
+  total_ms: number;
+}
+
+export interface CommandTimingStats {
+  // Command type ("status", "run", "extension:<name>") or request keyword ("rpm", "getcfg")
+  command: string;
+  first_byte: TimingHistogram;
+  total: TimingHistogram;
+  no_reply: number;
+  first_byte_p50_ms: number | null;
+  first_byte_p95_ms: number | null;
+  total_p95_ms: number | null;
+}
+
+// Result of get_timing_stats, grouped by the firmware build that was connected
+export interface TimingStats {
+  bucket_bounds_ms: number[];
+  firmware: {
+    firmware_version: string | null;
+    commands: CommandTimingStats[];
+  }[];
+}
+
+// One setpoint of a step test
+export interface StepResult {
+  target_rpm: number;
+  settled_rpm: number;
+  reached: boolean;
+  settle_ms: number;
+  stats: RpmStats;
+}
+
+// Result of run_step_test, delivered with the finished job
+export interface StepTestSummary {
+  started_at: number;
+  finished_at: number;
+  dwell_secs: number;
+  steps: StepResult[];
+  passed: boolean;
+}
+
+// Written by complete_setup (or the CLI's init) once the first-run wizard is done
+export interface SetupRecord {
+  completed_at: number;
+  port: string | null;
+}
+
+// Result of get_setup_state; the wizard shows while `completed` is null
+export interface SetupState {
+  data_dir: string;
+  completed: SetupRecord | null;
+  settings_exist: boolean;
+}
+
+// Result of test_handshake, which leaves the port closed either way
+export interface HandshakeResult {
+  port: string;
+  ok: boolean;
+  identity: DeviceIdentity | null;
+  error: string | null;
+}
+
+// One subsystem in a health_check report
+export interface HealthCheck {
+  subsystem: string;
+  state: 'ok' | 'warning' | 'failed';
+  detail: string;
+}
+
+// Result of health_check; overall is the worst of the checks
+export interface HealthReport {
+  checked_at: number;
+  overall: 'ok' | 'warning' | 'failed';
+  checks: HealthCheck[];
+}
+
+// A file removed by the retention cleanup (or that a dry run would remove)
+export interface CleanupItem {
+  category: 'logs' | 'telemetry' | 'backups';
+  // Relative to the app data folder
+  path: string;
+  bytes: number;
+  modified: number;
+  reason: 'age' | 'size';
+}
+
+// Result of run_cleanup_now
+export interface CleanupReport {
+  ran_at: number;
+  dry_run: boolean;
+  items: CleanupItem[];
+  freed_bytes: number;
+  errors: string[];
+}
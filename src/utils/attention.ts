@@ -0,0 +1,54 @@
+import type { Attention, Severity } from "../types";
+
+// Beep pattern per severity: tone (Hz) and how many pulses
+const TONES: Record<Severity, { frequency: number; pulses: number }> = {
+  info: { frequency: 880, pulses: 1 },
+  warning: { frequency: 660, pulses: 2 },
+  critical: { frequency: 440, pulses: 4 },
+};
+const PULSE_MS = 180;
+const FLASH_MS = 1200;
+
+let audio: AudioContext | null = null;
+
+function beep(severity: Severity) {
+  audio ??= new AudioContext();
+  const { frequency, pulses } = TONES[severity];
+  for (let i = 0; i < pulses; i++) {
+    const start = audio.currentTime + (i * 2 * PULSE_MS) / 1000;
+    const oscillator = audio.createOscillator();
+    const gain = audio.createGain();
+    oscillator.type = "square";
+    oscillator.frequency.value = frequency;
+    // Full volume: operators near the dyno wear ear protection
+    gain.gain.value = 1;
+    oscillator.connect(gain).connect(audio.destination);
+    oscillator.start(start);
+    oscillator.stop(start + PULSE_MS / 1000);
+  }
+}
+
+function flashScreen(severity: Severity) {
+  document.body.dataset.attention = severity;
+  setTimeout(() => delete document.body.dataset.attention, FLASH_MS);
+}
+
+async function notify(attention: Attention) {
+  if (Notification.permission === "default") {
+    await Notification.requestPermission();
+  }
+  if (Notification.permission === "granted") {
+    new Notification(attention.severity === "critical" ? "Bench needs attention" : "Signal injector", {
+      body: attention.message,
+    });
+  }
+}
+
+/**
+ * Play the cues the backend asked for with an app://attention event
+ */
+export function playAttention(attention: Attention) {
+  if (attention.sound) beep(attention.severity);
+  if (attention.flash) flashScreen(attention.severity);
+  if (attention.notify) notify(attention).catch(() => {});
+}
@@ -1,4 +1,5 @@
 import type { DecodedBlobInfo, DeviceSignalConfig, FullConfig, SignalEdge } from "../types";
+import { DEFAULT_CSV_OPTIONS, looksLikeEdgeCsv, parseEdgeCsv, type CsvOptions } from "./edgeCsv";
 
 // Matches signal_generator/src/utils/configCodec.ts (device export section)
 
@@ -276,16 +277,24 @@ export function legacyToDeviceSignalConfig(full: FullConfig): DeviceSignalConfig
   };
 }
 
-export function prepareConfigForUpload(rawJson: string): {
+export function prepareConfigForUpload(rawJson: string, csvOptions: CsvOptions = DEFAULT_CSV_OPTIONS): {
   kind: "device" | "legacy";
   device: DeviceSignalConfig;
   jsonToSend: string;
 } {
   let parsed: unknown;
-  try {
-    parsed = JSON.parse(rawJson);
-  } catch (e) {
-    throw new Error(`Invalid JSON: ${e}`);
+  if (looksLikeEdgeCsv(rawJson)) {
+    try {
+      parsed = parseEdgeCsv(rawJson, csvOptions);
+    } catch (e) {
+      throw new Error(`Invalid CSV: ${e instanceof Error ? e.message : e}`);
+    }
+  } else {
+    try {
+      parsed = JSON.parse(rawJson);
+    } catch (e) {
+      throw new Error(`Invalid JSON: ${e}`);
+    }
   }
 
   if (isProtectedWheelExport(parsed)) {
@@ -302,6 +311,7 @@ export function prepareConfigForUpload(rawJson: string): {
     kind = "device";
   } else if (isLegacyFullConfig(parsed)) {
     device = legacyToDeviceSignalConfig(parsed);
+    if (parsed.rpm === 0) device.name = "Imported CSV";
     kind = "legacy";
   } else {
     throw new Error(
@@ -0,0 +1,186 @@
+/**
+ * Edge-list CSV import
+ * Reads logic analyzer style exports (angle column plus one level column per channel)
+ * written on any locale: decimal commas, `;`/tab/space delimiters and thousands separators.
+ */
+
+import type { FullConfig, SignalEdge } from '../types';
+
+export type CsvDelimiter = 'auto' | ',' | ';' | '\t' | ' ';
+export type DecimalSeparator = 'auto' | '.' | ',';
+
+export interface CsvOptions {
+  delimiter: CsvDelimiter;
+  decimal: DecimalSeparator;
+}
+
+export const DEFAULT_CSV_OPTIONS: CsvOptions = { delimiter: 'auto', decimal: 'auto' };
+
+// Tried in this order: `;` and tab first, since `,` may be the decimal separator
+const DELIMITER_CANDIDATES = [';', '\t', ',', ' '] as const;
+
+function splitLine(line: string, delimiter: string): string[] {
+  return delimiter === ' ' ? line.trim().split(/\s+/) : line.split(delimiter).map((f) => f.trim());
+}
+
+/**
+ * Pick the delimiter that splits every line into the same number (2+) of fields
+ */
+export function detectDelimiter(lines: string[]): Exclude<CsvDelimiter, 'auto'> {
+  for (const candidate of DELIMITER_CANDIDATES) {
+    const counts = lines.map((l) => splitLine(l, candidate).length);
+    if (counts[0] >= 2 && counts.every((c) => c === counts[0])) {
+      return candidate;
+    }
+  }
+  throw new Error('Could not detect the column delimiter; choose it explicitly');
+}
+
+function stripSpacing(text: string): string {
+  return text.trim().replace(/[\s ']/g, '');
+}
+
+/**
+ * What a value says about the decimal separator, or `undefined` when it could be either
+ */
+function decimalHint(value: string): '.' | ',' | undefined {
+  const lastComma = value.lastIndexOf(',');
+  const lastDot = value.lastIndexOf('.');
+  if (lastComma >= 0 && lastDot >= 0) return lastComma > lastDot ? ',' : '.';
+  const sep = lastComma >= 0 ? ',' : lastDot >= 0 ? '.' : undefined;
+  if (sep === undefined) return undefined;
+  const other = sep === ',' ? '.' : ',';
+  // Only thousands separators repeat: "1,234,567"
+  if (value.indexOf(sep) !== value.lastIndexOf(sep)) return other;
+  // A thousands group has exactly three digits after a non-zero lead: "1,234"
+  const [lead, group] = value.replace(/^[-+]/, '').split(sep);
+  if (!/^\d{3}$/.test(group) || !/^[1-9]\d{0,2}$/.test(lead)) return sep;
+  return undefined;
+}
+
+/**
+ * Pick the decimal separator for a whole column of values: the first value that
+ * settles it decides. When every value could be either ("1,234", "2.500"), it is
+ * the dot, so "1,234" reads as 1234 and "2.500" as 2.5.
+ */
+export function detectDecimal(values: string[]): '.' | ',' {
+  for (const value of values.map(stripSpacing)) {
+    const hint = decimalHint(value);
+    if (hint) return hint;
+  }
+  return '.';
+}
+
+/**
+ * Parse a number written in either convention: "0,125", "0.125", "1.234,5", "1,234.5".
+ * With `auto` the separator is detected from this value alone (see `detectDecimal`);
+ * pass the one detected for the whole column when parsing many values.
+ */
+export function parseLocaleNumber(text: string, decimal: DecimalSeparator = 'auto'): number {
+  let s = stripSpacing(text);
+  const sep = decimal === 'auto' ? detectDecimal([s]) : decimal;
+  s = sep === ',' ? s.replace(/\./g, '').replace(/,/g, '.') : s.replace(/,/g, '');
+  const value = Number(s);
+  if (s === '' || !Number.isFinite(value)) {
+    throw new Error(`"${text}" is not a number`);
+  }
+  return value;
+}
+
+function isNumeric(text: string, decimal: DecimalSeparator): boolean {
+  try {
+    parseLocaleNumber(text, decimal);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
+/**
+ * Whether pasted text is an edge-list CSV rather than JSON
+ */
+export function looksLikeEdgeCsv(text: string): boolean {
+  const trimmed = text.trim();
+  return trimmed.length > 0 && !trimmed.startsWith('{') && !trimmed.startsWith('[');
+}
+
+/**
+ * Turn an edge-list CSV into the legacy edge config.
+ *
+ * The first column is the crank angle in degrees, the next ones the CKP, CMP1 and CMP2
+ * levels (0/1) in that order, unless a header row names them. An edge is recorded
+ * wherever a channel's level changes.
+ */
+export function parseEdgeCsv(text: string, options: CsvOptions = DEFAULT_CSV_OPTIONS): FullConfig {
+  const lines = text
+    .split(/\r?\n/)
+    .filter((l) => l.trim() !== '' && !l.trim().startsWith('#'));
+  if (lines.length === 0) throw new Error('CSV is empty');
+
+  const delimiter = options.delimiter === 'auto' ? detectDelimiter(lines) : options.delimiter;
+  const rows = lines.map((l) => splitLine(l, delimiter));
+
+  // Header row: map named columns, otherwise angle, CKP, CMP1, CMP2 by position
+  let columns = { angle: 0, ckp: 1, cmp1: 2, cmp2: 3 };
+  if (!isNumeric(rows[0][0], options.decimal)) {
+    const header = rows.shift()!.map((h) => h.toLowerCase());
+    const find = (pattern: RegExp, fallback: number) => {
+      const index = header.findIndex((h) => pattern.test(h));
+      return index >= 0 ? index : fallback;
+    };
+    columns = {
+      angle: find(/angle|deg|pos/, 0),
+      ckp: find(/ckp|crank/, 1),
+      cmp1: find(/cmp1|cam1|cmp$|cam$/, 2),
+      cmp2: find(/cmp2|cam2/, 3),
+    };
+  }
+  if (rows.length === 0) throw new Error('CSV has a header but no data');
+
+  // One decimal separator for the whole file, so "1,234" reads the same on every row
+  const decimal =
+    options.decimal !== 'auto'
+      ? options.decimal
+      : delimiter === ','
+        ? '.'
+        : detectDecimal(rows.map((r) => r[columns.angle] ?? ''));
+
+  const edges: Record<'ckp' | 'cmp1' | 'cmp2', SignalEdge[]> = { ckp: [], cmp1: [], cmp2: [] };
+  const previous: Record<string, number | undefined> = {};
+
+  rows.forEach((row, i) => {
+    const lineNo = i + 1;
+    let angle: number;
+    try {
+      angle = parseLocaleNumber(row[columns.angle] ?? '', decimal);
+    } catch (e) {
+      throw new Error(`Row ${lineNo}: ${e instanceof Error ? e.message : e}`);
+    }
+    for (const channel of ['ckp', 'cmp1', 'cmp2'] as const) {
+      const field = row[columns[channel]];
+      if (field === undefined || field === '') continue;
+      let level: number;
+      try {
+        level = parseLocaleNumber(field, decimal) >= 0.5 ? 1 : 0;
+      } catch (e) {
+        throw new Error(`Row ${lineNo}, ${channel.toUpperCase()}: ${e instanceof Error ? e.message : e}`);
+      }
+      if (previous[channel] !== level) {
+        edges[channel].push({ angle, level });
+        previous[channel] = level;
+      }
+    }
+  });
+
+  if (edges.ckp.length === 0) throw new Error('CSV has no CKP column');
+
+  return {
+    rpm: 0,
+    cycle: 720,
+    signals: {
+      ckp: { edges: edges.ckp },
+      cmp1: { edges: edges.cmp1 },
+      cmp2: { edges: edges.cmp2 },
+    },
+  };
+}
@@ -0,0 +1,44 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type { Job } from "../types";
+
+/**
+ * Invoke a command that starts a backend job and wait for the job to finish.
+ * Resolves with the job's result; rejects with its error when it failed or was cancelled.
+ */
+export async function runJob<T>(
+  command: string,
+  args?: Record<string, unknown>,
+  onProgress?: (job: Job) => void,
+): Promise<T> {
+  let jobId: number | null = null;
+  // Events that arrive before the command returned the job ID
+  const early = new Map<number, Job>();
+  let settle: (job: Job) => void = () => {};
+  const finished = new Promise<Job>((resolve) => {
+    settle = resolve;
+  });
+
+  const unlisten = await listen<Job>("job://progress", (event) => {
+    const job = event.payload;
+    if (jobId === null) {
+      early.set(job.id, job);
+      return;
+    }
+    if (job.id !== jobId) return;
+    onProgress?.(job);
+    if (job.state !== "running") settle(job);
+  });
+
+  try {
+    jobId = await invoke<number>(command, args);
+    const seen = early.get(jobId) ?? (await invoke<Job>("get_job", { id: jobId }));
+    if (seen.state !== "running") settle(seen);
+
+    const job = await finished;
+    if (job.state === "succeeded") return job.result as T;
+    throw job.error ?? `Job ${job.state}`;
+  } finally {
+    unlisten();
+  }
+}